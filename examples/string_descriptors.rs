@@ -16,8 +16,8 @@ fn inspect_device(dev: DeviceInfo) {
         dev.device_address(),
         dev.vendor_id(),
         dev.product_id(),
-        dev.manufacturer_string().unwrap_or(""),
-        dev.product_string().unwrap_or("")
+        dev.manufacturer_string().value().unwrap_or(""),
+        dev.product_string().value().unwrap_or("")
     );
     let dev = match dev.open().wait() {
         Ok(dev) => dev,
@@ -2,7 +2,13 @@
 //!
 //! Descriptors are blocks of data that describe the functionality of a USB device.
 
-use std::{collections::BTreeMap, fmt::Debug, iter, num::NonZeroU8, ops::Deref};
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    iter,
+    num::NonZeroU8,
+    ops::{Deref, Range},
+};
 
 use log::warn;
 
@@ -22,6 +28,12 @@ pub(crate) const DESCRIPTOR_LEN_ENDPOINT: u8 = 7;
 
 pub(crate) const DESCRIPTOR_TYPE_STRING: u8 = 0x03;
 
+pub(crate) const DESCRIPTOR_TYPE_INTERFACE_ASSOCIATION: u8 = 0x0B;
+pub(crate) const DESCRIPTOR_LEN_INTERFACE_ASSOCIATION: u8 = 8;
+
+pub(crate) const DESCRIPTOR_TYPE_SS_ENDPOINT_COMPANION: u8 = 0x30;
+pub(crate) const DESCRIPTOR_LEN_SS_ENDPOINT_COMPANION: u8 = 6;
+
 /// USB defined language IDs for string descriptors.
 ///
 /// In practice, different language IDs are not used,
@@ -45,7 +57,7 @@ impl<'a> Descriptor<'a> {
     /// Returns `None` if
     ///   * the slice length is not at least 2.
     ///   * the `bLength` field (first byte) is greater than the slice length.
-    pub fn new(buf: &[u8]) -> Option<Descriptor> {
+    pub fn new(buf: &[u8]) -> Option<Descriptor<'_>> {
         if buf.len() >= 2 && buf.len() >= buf[0] as usize {
             Some(Descriptor(buf))
         } else {
@@ -352,7 +364,7 @@ impl<'a> ConfigurationDescriptor<'a> {
     /// one from your own descriptor bytes for tests.
     ///
     /// This ignores any trailing data after the length specified in `wTotalLen`.
-    pub fn new(buf: &[u8]) -> Option<ConfigurationDescriptor> {
+    pub fn new(buf: &[u8]) -> Option<ConfigurationDescriptor<'_>> {
         if buf.len() < DESCRIPTOR_LEN_CONFIGURATION as usize {
             if !buf.is_empty() {
                 warn!(
@@ -429,6 +441,32 @@ impl<'a> ConfigurationDescriptor<'a> {
                 interfaces,
             })
     }
+
+    /// Iterate the interface association descriptors of this configuration.
+    ///
+    /// Each interface association groups consecutive interfaces that form a
+    /// single function of a composite device, such as the control and data
+    /// interfaces of a CDC-ACM serial port.
+    pub fn interface_associations(
+        &self,
+    ) -> impl Iterator<Item = InterfaceAssociationDescriptor<'a>> {
+        self.descriptors()
+            .filter(|d| {
+                d.descriptor_type() == DESCRIPTOR_TYPE_INTERFACE_ASSOCIATION
+                    && d.descriptor_len() >= DESCRIPTOR_LEN_INTERFACE_ASSOCIATION as usize
+            })
+            .map(|d| InterfaceAssociationDescriptor(d.0))
+    }
+
+    /// Get the interface association descriptor that includes the interface
+    /// `interface_number`, if any.
+    pub fn interface_association(
+        &self,
+        interface_number: u8,
+    ) -> Option<InterfaceAssociationDescriptor<'a>> {
+        self.interface_associations()
+            .find(|iad| iad.interfaces().contains(&interface_number))
+    }
 }
 
 descriptor_fields! {
@@ -463,6 +501,15 @@ impl<'a> ConfigurationDescriptor<'a> {
     pub fn string_index(&self) -> Option<NonZeroU8> {
         NonZeroU8::new(self.string_index_raw())
     }
+
+    /// Whether the device supports remote wakeup in this configuration, from
+    /// bit 5 of [`attributes`][`Self::attributes`].
+    ///
+    /// A device that supports remote wakeup can be allowed to wake the host
+    /// with `Device::set_remote_wakeup`.
+    pub fn supports_remote_wakeup(&self) -> bool {
+        self.attributes() & 0x20 != 0
+    }
 }
 
 struct DebugEntries<F>(F);
@@ -494,6 +541,77 @@ impl<'a> Debug for ConfigurationDescriptor<'a> {
     }
 }
 
+/// Information about a function of a composite device, which groups
+/// consecutive interfaces.
+///
+/// Interface association descriptors are used by composite devices to
+/// indicate which interfaces must be used together, such as the control and
+/// data interfaces of a CDC-ACM function, or the control and streaming
+/// interfaces of a video function. Claim all of the interfaces of a function
+/// with [`Device::claim_association`][crate::Device::claim_association].
+#[derive(Clone)]
+pub struct InterfaceAssociationDescriptor<'a>(&'a [u8]);
+
+impl<'a> InterfaceAssociationDescriptor<'a> {
+    /// The bytes of the interface association descriptor.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Range of interface numbers included in the function.
+    pub fn interfaces(&self) -> Range<u8> {
+        let first = self.first_interface();
+        first..first.saturating_add(self.interface_count())
+    }
+
+    /// Index of the string descriptor describing this function.
+    #[doc(alias = "iFunction")]
+    pub fn string_index(&self) -> Option<NonZeroU8> {
+        NonZeroU8::new(self.string_index_raw())
+    }
+}
+
+descriptor_fields! {
+    impl<'a> InterfaceAssociationDescriptor<'a> {
+        /// `bFirstInterface` descriptor field: Number of the first interface
+        /// of the function.
+        #[doc(alias = "bFirstInterface")]
+        pub fn first_interface at 2 -> u8;
+
+        /// `bInterfaceCount` descriptor field: Number of consecutive
+        /// interfaces in the function.
+        #[doc(alias = "bInterfaceCount")]
+        pub fn interface_count at 3 -> u8;
+
+        /// `bFunctionClass` descriptor field: Standard class of the function.
+        #[doc(alias = "bFunctionClass")]
+        pub fn class at 4 -> u8;
+
+        /// `bFunctionSubClass` descriptor field: Standard subclass of the function.
+        #[doc(alias = "bFunctionSubClass")]
+        pub fn subclass at 5 -> u8;
+
+        /// `bFunctionProtocol` descriptor field: Standard protocol of the function.
+        #[doc(alias = "bFunctionProtocol")]
+        pub fn protocol at 6 -> u8;
+
+        fn string_index_raw at 7 -> u8;
+    }
+}
+
+impl<'a> Debug for InterfaceAssociationDescriptor<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InterfaceAssociation")
+            .field("first_interface", &self.first_interface())
+            .field("interface_count", &self.interface_count())
+            .field("class", &self.class())
+            .field("subclass", &self.subclass())
+            .field("protocol", &self.protocol())
+            .field("string_index", &self.string_index())
+            .finish()
+    }
+}
+
 /// Interface descriptors for alternate settings, grouped by the interface number.
 #[derive(Clone)]
 pub struct InterfaceDescriptors<'a> {
@@ -647,6 +765,35 @@ impl<'a> EndpointDescriptor<'a> {
     pub fn packets_per_microframe(&self) -> u8 {
         ((self.max_packet_size_raw() >> 11) & 0b11) as u8 + 1
     }
+
+    /// Get the maximum number of bytes the endpoint can transfer per service
+    /// interval.
+    ///
+    /// For SuperSpeed periodic endpoints, this is the `wBytesPerInterval`
+    /// field of the SuperSpeed Endpoint Companion descriptor, if present. For
+    /// high-bandwidth high speed endpoints, it is the maximum packet size
+    /// times the [packets per microframe][Self::packets_per_microframe].
+    /// Otherwise it is the maximum packet size.
+    ///
+    /// This is the largest packet length usable for an isochronous transfer.
+    pub fn max_bytes_per_interval(&self) -> usize {
+        let periodic = matches!(
+            self.transfer_type(),
+            TransferType::Isochronous | TransferType::Interrupt
+        );
+        if !periodic {
+            return self.max_packet_size();
+        }
+
+        let companion = self.descriptors().find(|d| {
+            d.descriptor_type() == DESCRIPTOR_TYPE_SS_ENDPOINT_COMPANION
+                && d.descriptor_len() >= DESCRIPTOR_LEN_SS_ENDPOINT_COMPANION as usize
+        });
+        match companion {
+            Some(d) => u16::from_le_bytes([d[4], d[5]]) as usize,
+            None => self.max_packet_size() * self.packets_per_microframe() as usize,
+        }
+    }
 }
 
 descriptor_fields! {
@@ -689,6 +836,7 @@ impl<'a> Debug for EndpointDescriptor<'a> {
 
 /// Endpoint type.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(dead_code)]
 pub enum TransferType {
     /// Control endpoint.
@@ -708,7 +856,7 @@ pub enum TransferType {
 #[allow(unused)]
 pub(crate) fn parse_concatenated_config_descriptors(
     mut buf: &[u8],
-) -> impl Iterator<Item = ConfigurationDescriptor> {
+) -> impl Iterator<Item = ConfigurationDescriptor<'_>> {
     iter::from_fn(move || {
         let desc = ConfigurationDescriptor::new(buf)?;
         buf = &buf[desc.0.len()..];
@@ -740,6 +888,64 @@ pub fn fuzz_parse_concatenated_config_descriptors(buf: &[u8]) -> impl Iterator<I
     parse_concatenated_config_descriptors(buf)
 }
 
+/// Descriptors are serialized as their bytes.
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{
+        ConfigurationDescriptor, DeviceDescriptor, EndpointDescriptor,
+        InterfaceAssociationDescriptor, InterfaceDescriptor,
+    };
+
+    macro_rules! serialize_as_bytes {
+        ($($t:ident),*) => {
+            $(
+                impl Serialize for $t<'_> {
+                    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                        self.as_bytes().serialize(serializer)
+                    }
+                }
+            )*
+        };
+    }
+
+    serialize_as_bytes!(
+        ConfigurationDescriptor,
+        InterfaceAssociationDescriptor,
+        InterfaceDescriptor,
+        EndpointDescriptor
+    );
+
+    impl Serialize for DeviceDescriptor {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.as_bytes().serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for DeviceDescriptor {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let bytes = Vec::<u8>::deserialize(deserializer)?;
+            DeviceDescriptor::new(&bytes)
+                .ok_or_else(|| de::Error::custom("invalid device descriptor"))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+#[test]
+fn serde_device_descriptor() {
+    let bytes = [
+        0x12, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x40, 0x34, 0x12, 0x78, 0x56, 0x00, 0x01, 0x01,
+        0x02, 0x03, 0x01,
+    ];
+    let desc = DeviceDescriptor::new(&bytes).unwrap();
+    let json = serde_json::to_string(&desc).unwrap();
+    let desc: DeviceDescriptor = serde_json::from_str(&json).unwrap();
+    assert_eq!(desc.as_bytes(), bytes);
+    assert!(serde_json::from_str::<DeviceDescriptor>("[1, 2, 3]").is_err());
+}
+
 #[cfg(test)]
 mod test_concatenated {
     use super::parse_concatenated_config_descriptors;
@@ -850,6 +1056,7 @@ fn test_linux_root_hub() {
     assert_eq!(c.num_interfaces(), 1);
     assert_eq!(c.configuration_value(), 1);
     assert_eq!(c.max_power(), 0);
+    assert!(c.supports_remote_wakeup());
     assert_eq!(c.interfaces().count(), 1);
 
     let interface = c.interfaces().next().unwrap();
@@ -974,8 +1181,20 @@ fn test_dell_webcam() {
 
     assert_eq!(c.configuration_value(), 1);
     assert_eq!(c.num_interfaces(), 2);
+    assert!(!c.supports_remote_wakeup());
     assert_eq!(c.max_power(), 250);
 
+    let iad = c.interface_associations().next().unwrap();
+    assert_eq!(iad.first_interface(), 0);
+    assert_eq!(iad.interface_count(), 2);
+    assert_eq!(iad.interfaces(), 0..2);
+    assert_eq!(iad.class(), 0x0e);
+    assert_eq!(iad.subclass(), 0x03);
+    assert_eq!(iad.string_index(), NonZeroU8::new(5));
+    assert_eq!(c.interface_associations().count(), 1);
+    assert_eq!(c.interface_association(1).unwrap().first_interface(), 0);
+    assert!(c.interface_association(2).is_none());
+
     let mut interfaces = c.interfaces();
     let interface = interfaces.next().unwrap();
     assert_eq!(interface.interface_number(), 0);
@@ -1070,6 +1289,7 @@ fn test_dell_webcam() {
     assert_eq!(endpoint.transfer_type(), TransferType::Isochronous);
     assert_eq!(endpoint.max_packet_size(), 800);
     assert_eq!(endpoint.packets_per_microframe(), 1);
+    assert_eq!(endpoint.max_bytes_per_interval(), 800);
 
     assert!(endpoints.next().is_none());
 
@@ -1087,6 +1307,7 @@ fn test_dell_webcam() {
     assert_eq!(endpoint.transfer_type(), TransferType::Isochronous);
     assert_eq!(endpoint.max_packet_size(), 800);
     assert_eq!(endpoint.packets_per_microframe(), 2);
+    assert_eq!(endpoint.max_bytes_per_interval(), 1600);
 
     let alt = alts.next().unwrap();
     assert_eq!(alt.interface_number(), 1);
@@ -1102,6 +1323,7 @@ fn test_dell_webcam() {
     assert_eq!(endpoint.transfer_type(), TransferType::Isochronous);
     assert_eq!(endpoint.max_packet_size(), 800);
     assert_eq!(endpoint.packets_per_microframe(), 3);
+    assert_eq!(endpoint.max_bytes_per_interval(), 2400);
 
     let alt = alts.next().unwrap();
     assert_eq!(alt.interface_number(), 1);
@@ -1117,8 +1339,23 @@ fn test_dell_webcam() {
     assert_eq!(endpoint.transfer_type(), TransferType::Isochronous);
     assert_eq!(endpoint.max_packet_size(), 1024);
     assert_eq!(endpoint.packets_per_microframe(), 3);
+    assert_eq!(endpoint.max_bytes_per_interval(), 3072);
 
     assert!(endpoints.next().is_none());
     assert!(alts.next().is_none());
     assert!(interfaces.next().is_none());
 }
+
+#[test]
+fn test_ss_companion_bytes_per_interval() {
+    #[rustfmt::skip]
+    let desc = &[
+        // Endpoint 0x81, isochronous, 1024 bytes
+        0x07, 0x05, 0x81, 0x05, 0x00, 0x04, 0x01,
+        // SuperSpeed Endpoint Companion, bMaxBurst 3, Mult 1, 6144 bytes
+        0x06, 0x30, 0x03, 0x01, 0x00, 0x18,
+    ];
+    let endpoint = EndpointDescriptor(desc);
+    assert_eq!(endpoint.max_packet_size(), 1024);
+    assert_eq!(endpoint.max_bytes_per_interval(), 6144);
+}
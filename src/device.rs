@@ -1,25 +1,36 @@
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use crate::transfer::{IsoCompletion, IsoSchedule, Isochronous};
 use crate::{
     descriptors::{
         decode_string_descriptor, validate_string_descriptor, ConfigurationDescriptor,
-        DeviceDescriptor, InterfaceDescriptor, DESCRIPTOR_TYPE_STRING,
+        DeviceDescriptor, EndpointDescriptor, InterfaceDescriptor, TransferType,
+        DESCRIPTOR_TYPE_STRING,
     },
-    io::{EndpointRead, EndpointWrite},
+    io::{EndpointRead, EndpointSink, EndpointStream, EndpointWrite, InterruptStream},
     platform,
+    timer::{block_on_timeout, with_timeout},
     transfer::{
-        Buffer, BulkOrInterrupt, Completion, ControlIn, ControlOut, Direction, EndpointDirection,
-        EndpointType, In, Out, TransferError,
+        Allocator, Buffer, BufferPool, Bulk, BulkOrInterrupt, CallbackEndpoint, CancelSignal,
+        Completion, ControlIn, ControlOut, ControlQueue, Direction, EndpointDirection,
+        EndpointType, Feature, In, Interrupt, Out, PartialTransferError, ShortPacketPolicy,
+        StatsCollector, SubmitReceiver, Submitter, TransferError, TransferId, TransferPriority,
+        TransferStats,
     },
     ActiveConfigurationError, DeviceInfo, Error, ErrorKind, GetDescriptorError, MaybeFuture, Speed,
 };
 use log::{error, warn};
 use std::{
+    collections::VecDeque,
+    ffi::c_void,
     fmt::Debug,
     future::{poll_fn, Future},
     marker::PhantomData,
     num::NonZeroU8,
-    sync::Arc,
-    task::{Context, Poll},
-    time::Duration,
+    ops::Range,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll},
+    thread,
+    time::{Duration, Instant},
 };
 
 /// An opened USB device.
@@ -52,8 +63,11 @@ impl Device {
         Device { backend }
     }
 
-    pub(crate) fn open(d: &DeviceInfo) -> impl MaybeFuture<Output = Result<Device, Error>> {
-        platform::Device::from_device_info(d).map(|d| d.map(Device::wrap))
+    pub(crate) fn open(
+        d: &DeviceInfo,
+        read_only: bool,
+    ) -> impl MaybeFuture<Output = Result<Device, Error>> {
+        platform::Device::from_device_info(d, read_only).map(|d| d.map(Device::wrap))
     }
 
     /// Wrap a usbdevfs file descriptor that is already open.
@@ -64,12 +78,103 @@ impl Device {
     /// [xdg-desktop-portal](https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.Usb.html),
     /// etc.
     ///
+    /// This does not enumerate devices or check permissions, so it can be used
+    /// in sandboxed or unprivileged processes where `/dev/bus/usb` and sysfs
+    /// are not accessible. The `Device` takes ownership of the file descriptor
+    /// and closes it when dropped, so on Android, pass a duplicate of the
+    /// descriptor if the `UsbDeviceConnection` is also used from Java.
+    ///
+    /// ```no_run
+    /// use std::os::fd::{FromRawFd, OwnedFd};
+    /// use nusb::MaybeFuture;
+    /// # fn example(raw_fd: std::os::fd::RawFd) -> Result<(), nusb::Error> {
+    /// // `raw_fd` from `UsbDeviceConnection.getFileDescriptor()`, duplicated with `dup`
+    /// let fd = unsafe { OwnedFd::from_raw_fd(raw_fd) };
+    /// let device = nusb::Device::from_fd(fd).wait()?;
+    /// # Ok(()) }
+    /// ```
+    ///
     /// *Supported on Linux and Android only.*
     #[cfg(any(target_os = "android", target_os = "linux"))]
+    #[doc(alias = "from_raw_fd")]
     pub fn from_fd(fd: std::os::fd::OwnedFd) -> impl MaybeFuture<Output = Result<Device, Error>> {
         platform::Device::from_fd(fd).map(|d| d.map(Device::wrap))
     }
 
+    /// Open the device with the specified bus number and address.
+    ///
+    /// This finds the device directly in sysfs instead of listing all
+    /// devices, for scripted environments that already know which device to
+    /// use, such as from `lsusb` output or the `BUSNUM` and `DEVNUM` udev
+    /// properties. Fails with [`ErrorKind::Disconnected`] if there is no such
+    /// device.
+    ///
+    /// *Supported on Linux only.*
+    #[cfg(target_os = "linux")]
+    pub fn open_by_bus_addr(
+        busnum: u8,
+        device_address: u8,
+    ) -> impl MaybeFuture<Output = Result<Device, Error>> {
+        crate::maybe_future::blocking::Blocking::new(move || {
+            Device::open(&platform::probe_bus_addr(busnum, device_address)?, false).wait()
+        })
+    }
+
+    /// Open the device at the specified sysfs path, such as
+    /// `/sys/bus/usb/devices/1-6`.
+    ///
+    /// The path may also be a symlink to the device directory, such as
+    /// `/sys/dev/char/189:5`. Fails with [`ErrorKind::Disconnected`] if
+    /// there is no such device.
+    ///
+    /// *Supported on Linux only.*
+    #[cfg(target_os = "linux")]
+    pub fn open_by_sysfs_path(
+        path: impl AsRef<std::path::Path>,
+    ) -> impl MaybeFuture<Output = Result<Device, Error>> {
+        let path = path.as_ref().to_owned();
+        crate::maybe_future::blocking::Blocking::new(move || {
+            Device::open(&platform::probe_sysfs_path(&path)?, false).wait()
+        })
+    }
+
+    /// Open the device with the specified instance ID, such as
+    /// `USB\VID_1234&PID_5678\0123456789`.
+    ///
+    /// This looks up the device directly instead of listing all devices.
+    /// The instance ID of a listed device is available from
+    /// `DeviceInfo::instance_id`. Fails with [`ErrorKind::Disconnected`] if
+    /// there is no such device.
+    ///
+    /// *Supported on Windows only.*
+    #[cfg(target_os = "windows")]
+    pub fn open_by_instance_id(
+        instance_id: impl AsRef<std::ffi::OsStr>,
+    ) -> impl MaybeFuture<Output = Result<Device, Error>> {
+        let instance_id = instance_id.as_ref().to_owned();
+        crate::maybe_future::blocking::Blocking::new(move || {
+            Device::open(&platform::probe_instance_id(&instance_id)?, false).wait()
+        })
+    }
+
+    /// Open the device with the specified IOKit location ID.
+    ///
+    /// The location ID identifies the port where the device is connected,
+    /// and is available from `DeviceInfo::location_id` or `ioreg -p IOUSB`.
+    /// This only reads the location of each device instead of probing all
+    /// devices. Fails with [`ErrorKind::Disconnected`] if there is no such
+    /// device.
+    ///
+    /// *Supported on macOS only.*
+    #[cfg(target_os = "macos")]
+    pub fn open_by_location_id(
+        location_id: u32,
+    ) -> impl MaybeFuture<Output = Result<Device, Error>> {
+        crate::maybe_future::blocking::Blocking::new(move || {
+            Device::open(&platform::probe_location_id(location_id)?, false).wait()
+        })
+    }
+
     /// Open an interface of the device and claim it for exclusive use.
     pub fn claim_interface(
         &self,
@@ -81,11 +186,113 @@ impl Device {
             .map(|i| i.map(Interface::wrap))
     }
 
+    /// Claim an interface, waiting up to `timeout` for it to be released if
+    /// it is in use.
+    ///
+    /// This retries [`claim_interface`][`Self::claim_interface`] with a
+    /// short backoff while it fails with [`ErrorKind::Busy`], for services
+    /// that briefly contend for the same device. Other errors are returned
+    /// immediately.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use nusb::MaybeFuture;
+    /// # fn example(device: nusb::Device) -> Result<(), nusb::Error> {
+    /// let interface = device.claim_interface_timeout(0, Duration::from_secs(2)).wait()?;
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// ### Platform-specific details
+    /// * If the interface is still busy after the timeout, the error message
+    ///   reports whether it is claimed or bound to a kernel driver, where
+    ///   [`interface_claim_status`][`Self::interface_claim_status`] can tell.
+    pub fn claim_interface_timeout(
+        &self,
+        interface: u8,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Result<Interface, Error>> {
+        let device = self.clone();
+        crate::maybe_future::blocking::Blocking::new(move || {
+            let deadline = Instant::now().checked_add(timeout);
+            let mut delay = Duration::from_millis(10);
+            loop {
+                match device.claim_interface(interface).wait() {
+                    Err(e) if e.kind() == ErrorKind::Busy => {
+                        let remaining = deadline.map_or(delay, |deadline| {
+                            deadline.saturating_duration_since(Instant::now())
+                        });
+                        if remaining.is_zero() {
+                            return Err(device.busy_error(interface, e));
+                        }
+                        thread::sleep(delay.min(remaining));
+                        delay = (delay * 2).min(Duration::from_millis(200));
+                    }
+                    res => return res,
+                }
+            }
+        })
+    }
+
+    /// Refine a `Busy` error from claiming `interface` with what is holding it.
+    fn busy_error(&self, interface: u8, e: Error) -> Error {
+        match self.interface_claim_status(interface) {
+            Ok(ClaimStatus::Claimed) => Error {
+                message: "interface is claimed by another handle or program",
+                ..e
+            },
+            Ok(ClaimStatus::KernelDriver(_)) => Error {
+                message: "interface is bound to a kernel driver",
+                ..e
+            },
+            _ => e,
+        }
+    }
+
+    /// Claim all of the interfaces of a function of a composite device.
+    ///
+    /// The function is described by the interface association descriptor of
+    /// the active configuration that includes `interface`, and the returned
+    /// `Vec` contains its interfaces in order. If the device has no interface
+    /// association for `interface`, only that interface is claimed. If any
+    /// interface can't be claimed, the interfaces already claimed are released
+    /// and the error is returned.
+    ///
+    /// See [`ConfigurationDescriptor::interface_associations`] to enumerate the
+    /// functions of a device.
+    pub fn claim_association(
+        &self,
+        interface: u8,
+    ) -> impl MaybeFuture<Output = Result<Vec<Interface>, Error>> {
+        let device = self.clone();
+        crate::maybe_future::blocking::Blocking::new(move || {
+            let interfaces = device
+                .active_configuration()?
+                .interface_association(interface)
+                .map_or(interface..interface.saturating_add(1), |iad| {
+                    iad.interfaces()
+                });
+            interfaces
+                .map(|i| device.claim_interface(i).wait())
+                .collect()
+        })
+    }
+
     /// Detach kernel drivers and open an interface of the device and claim it for exclusive use.
     ///
+    /// The kernel driver is reattached when the interface is released, after
+    /// the returned `Interface`, its clones, and its endpoints have been
+    /// dropped. Use [`detach_kernel_driver_guard`][`Self::detach_kernel_driver_guard`]
+    /// to control when the driver is reattached separately from the claim.
+    ///
     /// ### Platform-specific details
-    /// This function can only detach kernel drivers on Linux. Calling on other platforms has
-    /// the same effect as [`claim_interface`][`Device::claim_interface`].
+    /// * On Linux, detaching the driver and claiming the interface is a
+    ///   single atomic operation using the `USBDEVFS_DISCONNECT_CLAIM`
+    ///   ioctl, so the kernel driver cannot rebind in between. If the
+    ///   interface is already claimed by another program using usbfs, it is
+    ///   not disconnected and this fails with [`ErrorKind::Busy`].
+    /// * This function can only detach kernel drivers on Linux. Calling on
+    ///   other platforms has the same effect as
+    ///   [`claim_interface`][`Device::claim_interface`].
     pub fn detach_and_claim_interface(
         &self,
         interface: u8,
@@ -98,6 +305,11 @@ impl Device {
 
     /// Detach kernel drivers for the specified interface.
     ///
+    /// A kernel driver may rebind to the interface before it is claimed, so
+    /// prefer [`detach_and_claim_interface`][`Self::detach_and_claim_interface`],
+    /// which does both atomically, unless the interface must be left
+    /// unclaimed.
+    ///
     /// ### Platform-specific details
     /// This function can only detach kernel drivers on Linux. Calling on other platforms has
     /// no effect.
@@ -109,8 +321,101 @@ impl Device {
         Ok(())
     }
 
+    /// Get the name of the kernel driver bound to the specified interface.
+    ///
+    /// Returns `None` if no driver is bound. This can be used to warn the
+    /// user, or to decide whether to detach the driver, before claiming an
+    /// interface that would otherwise fail with [`ErrorKind::Busy`].
+    ///
+    /// If the interface is claimed by a program using usbfs, including this
+    /// one, the driver is reported as `"usbfs"`.
+    ///
+    /// ### Platform-specific details
+    /// * On Linux and Android, this returns the name of the driver such as
+    ///   `"usbhid"` or `"cdc_acm"`.
+    /// * Not supported on Windows or macOS. On Windows, the driver of the
+    ///   device is available from `DeviceInfo::driver`.
+    pub fn kernel_driver(&self, interface: u8) -> Result<Option<String>, Error> {
+        self.backend.kernel_driver(interface)
+    }
+
+    /// Check whether the specified interface can be claimed.
+    ///
+    /// This reports whether the interface is free, bound to an OS driver, or
+    /// already claimed, so that a program can show an actionable message
+    /// before calling [`claim_interface`][`Self::claim_interface`] instead
+    /// of interpreting an [`ErrorKind::Busy`] error. The status may change
+    /// at any time, so claiming can still fail.
+    ///
+    /// ### Platform-specific details
+    /// * On Linux and Android, an interface claimed through usbfs by this or
+    ///   another program is reported as [`ClaimStatus::Claimed`].
+    /// * On Windows, an interface bound to a driver other than WinUSB is
+    ///   reported as [`ClaimStatus::KernelDriver`] with the driver's service
+    ///   name. An interface whose WinUSB device is opened by another program
+    ///   is reported as `Claimed`, which is detected by briefly opening the
+    ///   device without sharing.
+    /// * Not supported on macOS.
+    pub fn interface_claim_status(&self, interface: u8) -> Result<ClaimStatus, Error> {
+        self.backend.interface_claim_status(interface)
+    }
+
+    /// Detach kernel drivers for the specified interface, returning a guard
+    /// that reattaches them when dropped.
+    ///
+    /// This allows using an interface that is bound to a class driver, such
+    /// as `usbhid` or `cdc_acm`, and returning it to the driver afterwards
+    /// even if the program exits early with an error or panic, as long as
+    /// the guard is dropped. If no kernel driver was bound, the guard does
+    /// nothing.
+    ///
+    /// The driver can only be reattached once the interface is released, so
+    /// drop or [close][`Interface::wait_closed`] the `Interface` before the guard.
+    ///
+    /// ```no_run
+    /// # use nusb::MaybeFuture;
+    /// # fn example(device: nusb::Device) -> Result<(), nusb::Error> {
+    /// let guard = device.detach_kernel_driver_guard(0)?;
+    /// let interface = device.claim_interface(0).wait()?;
+    /// // ...
+    /// interface.wait_closed().wait();
+    /// drop(guard);
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// ### Platform-specific details
+    /// This function can only detach kernel drivers on Linux. On other
+    /// platforms, it returns a guard that does nothing. macOS does not allow
+    /// detaching kernel drivers without a special entitlement, so the
+    /// interface must not be bound to a kernel driver.
+    pub fn detach_kernel_driver_guard(&self, interface: u8) -> Result<KernelDriverGuard, Error> {
+        #[cfg(target_os = "linux")]
+        let detached = match self.backend.detach_kernel_driver(interface) {
+            Ok(()) => true,
+            Err(e) if e.os_error() == Some(rustix::io::Errno::NODATA.raw_os_error() as u32) => {
+                false
+            }
+            Err(e) => return Err(e),
+        };
+        #[cfg(not(target_os = "linux"))]
+        let detached = false;
+
+        Ok(KernelDriverGuard {
+            device: self.clone(),
+            interface,
+            detached,
+        })
+    }
+
     /// Attach kernel drivers for the specified interface.
     ///
+    /// This hands the interface back to the OS, for example to let
+    /// `cdc_acm` bind to a modem after configuring it with vendor requests.
+    /// A driver can only be attached to an interface that is not claimed, so
+    /// the `Interface` must be released first. Use
+    /// [`Interface::attach_kernel_driver`] to release an interface and
+    /// attach the driver in one step.
+    ///
     /// ### Platform-specific details
     /// This function can only attach kernel drivers on Linux. Calling on other platforms has
     /// no effect.
@@ -130,6 +435,19 @@ impl Device {
     }
 
     /// Get the device's connection speed.
+    ///
+    /// This is the speed negotiated with the upstream hub port, which may be
+    /// lower than the highest speed the device supports if it is connected
+    /// through a slower hub or cable. Returns `None` if the OS does not report
+    /// the speed, or reports a speed not represented by [`Speed`].
+    ///
+    /// ### Platform-specific details
+    ///
+    /// * On Linux and Android, this performs an ioctl on each call. The
+    ///   ioctl does not distinguish [`Speed::SuperPlusX2`], which is reported
+    ///   as [`Speed::SuperPlus`], unlike [`DeviceInfo::speed`].
+    /// * On Windows and macOS, this returns the speed cached when the device
+    ///   was opened, and does not perform IO.
     pub fn speed(&self) -> Option<Speed> {
         self.backend.speed()
     }
@@ -139,9 +457,32 @@ impl Device {
     /// This returns cached data and does not perform IO. However, it can fail if the
     /// device is unconfigured, or if it can't find a configuration descriptor for
     /// the configuration reported as active by the OS.
+    ///
+    /// The returned descriptor is parsed, and provides the interfaces,
+    /// alternate settings, and endpoints of the configuration:
+    ///
+    /// ```no_run
+    /// # fn example(device: nusb::Device) -> Result<(), nusb::Error> {
+    /// let config = device.active_configuration()?;
+    /// for interface in config.interfaces() {
+    ///     for alt in interface.alt_settings() {
+    ///         for endpoint in alt.endpoints() {
+    ///             println!(
+    ///                 "interface {} alt {}: endpoint {:02x} {:?}",
+    ///                 alt.interface_number(),
+    ///                 alt.alternate_setting(),
+    ///                 endpoint.address(),
+    ///                 endpoint.transfer_type(),
+    ///             );
+    ///         }
+    ///     }
+    /// }
+    /// # Ok(()) }
+    /// ```
+    #[doc(alias = "active_config")]
     pub fn active_configuration(
         &self,
-    ) -> Result<ConfigurationDescriptor, ActiveConfigurationError> {
+    ) -> Result<ConfigurationDescriptor<'_>, ActiveConfigurationError> {
         let active = self.backend.active_configuration_value();
 
         self.configurations()
@@ -154,7 +495,7 @@ impl Device {
     /// Get an iterator returning information about each configuration of the device.
     ///
     /// This returns cached data and does not perform IO.
-    pub fn configurations(&self) -> impl Iterator<Item = ConfigurationDescriptor> {
+    pub fn configurations(&self) -> impl Iterator<Item = ConfigurationDescriptor<'_>> {
         self.backend.configuration_descriptors()
     }
 
@@ -162,9 +503,23 @@ impl Device {
     ///
     /// The argument is the desired configuration's `bConfigurationValue`
     /// descriptor field from [`ConfigurationDescriptor::configuration_value`] or `0` to
-    /// unconfigure the device.
+    /// unconfigure the device. While the device is unconfigured,
+    /// [`active_configuration()`][`Self::active_configuration`] returns an
+    /// error and no interfaces can be claimed.
+    ///
+    /// Changing the configuration replaces all of the device's interfaces, so
+    /// it fails with [`ErrorKind::Busy`] if any interface is claimed, by this
+    /// or another program or a kernel driver. Drop all [`Interface`]s first.
     ///
     /// ### Platform-specific details
+    /// * On Linux, interfaces bound to kernel drivers also cause
+    ///   `ErrorKind::Busy`, so detach them first with
+    ///   [`detach_kernel_driver`][`Self::detach_kernel_driver`]. Kernel
+    ///   drivers for the new configuration's interfaces are probed after it
+    ///   is set.
+    /// * On macOS, this requires exclusive access to the device, and fails
+    ///   with `ErrorKind::Busy` while any of its interfaces are claimed
+    ///   through this `Device`.
     /// * Not supported on Windows
     pub fn set_configuration(
         &self,
@@ -281,8 +636,132 @@ impl Device {
         self.backend.clone().reset()
     }
 
+    /// Disable and re-enable the port of the upstream hub that the device is
+    /// connected to.
+    ///
+    /// This is a stronger reset than [`reset()`][`Self::reset`], for recovering
+    /// devices whose firmware no longer responds. The device is disconnected
+    /// from the port for `off_time`, which on hubs that support per-port power
+    /// switching also removes its power. The device then re-enumerates, so
+    /// this `Device` can no longer be used; use
+    /// [`watch_devices`][`super::watch_devices`] or
+    /// [`list_devices`][`super::list_devices`] to find and re-open it.
+    ///
+    /// ### Platform-specific details
+    /// * On Linux, this writes the `disable` attribute of the hub port in
+    ///   sysfs, which requires Linux 4.20 or later and usually root
+    ///   privileges. It is not supported for root hubs.
+    /// * Not supported on Android, Windows, or macOS.
+    pub fn cycle_port(&self, off_time: Duration) -> impl MaybeFuture<Output = Result<(), Error>> {
+        self.backend.clone().cycle_port(off_time)
+    }
+
+    /// Watch for changes to the state of the device.
+    ///
+    /// Returns a stream of [`DeviceStateEvent`][`crate::hotplug::DeviceStateEvent`]s
+    /// that reports when the device is suspended or resumed by the OS, when
+    /// its configuration is changed by another program or driver, and when
+    /// it is disconnected, so that long-running programs can react without
+    /// waiting for a transfer to fail.
+    ///
+    /// ```no_run
+    /// use nusb::hotplug::DeviceStateEvent;
+    /// # fn example(device: nusb::Device) {
+    /// for event in futures_lite::stream::block_on(device.watch_state()) {
+    ///     match event {
+    ///         DeviceStateEvent::Disconnected => break,
+    ///         event => println!("{event:?}"),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// ### Platform-specific details
+    /// * On Linux, suspend and resume are reported from the
+    ///   `power/runtime_status` sysfs attribute. Only disconnection is
+    ///   reported for a device opened with `Device::from_fd`.
+    /// * On Windows and macOS, suspend and resume are not reported.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    pub fn watch_state(&self) -> crate::hotplug::DeviceStateWatch {
+        crate::hotplug::DeviceStateWatch::new(self.backend.clone())
+    }
+
+    /// Handle this device's transfer completions on a dedicated thread.
+    ///
+    /// By default, completions for all devices are handled by a single
+    /// event thread shared by the process, so that a device completing many
+    /// transfers at a high rate, or a slow completion callback, delays
+    /// completions for every other device. Calling this method moves the
+    /// device to an event thread of its own, isolating latency-sensitive
+    /// devices from unrelated traffic. The thread exits once the device and
+    /// all of its interfaces and endpoints are dropped.
+    ///
+    /// Calling this method again has no effect. Transfers that are already
+    /// pending continue to complete normally.
+    ///
+    /// ### Platform-specific details
+    /// * On Linux and Android, this creates a new epoll instance and thread
+    ///   that polls only this device.
+    /// * Not supported on Windows or macOS.
+    pub fn use_dedicated_event_thread(&self) -> Result<(), Error> {
+        self.backend.use_dedicated_event_thread()
+    }
+
+    /// Reset the device, and check whether this `Device` is still usable.
+    ///
+    /// This performs a port reset as with [`reset()`][`Self::reset`], then
+    /// re-reads the device's descriptors and address and compares them with
+    /// those from when the device was opened. If they are unchanged, the
+    /// device is still the same and returns [`ResetOutcome::Retained`].
+    /// Otherwise, or if the device disconnected, it returns
+    /// [`ResetOutcome::Reenumerated`], and the device must be found again
+    /// with [`list_devices`][`super::list_devices`] and re-opened.
+    ///
+    /// This is intended for workflows such as firmware updates, where a reset
+    /// may or may not cause the device to come back as a different device.
+    ///
+    /// After a reset, the device's interfaces are back at their default
+    /// alternate settings, and the device has lost any state such as endpoint
+    /// halts and data toggles. [`Interface`]s claimed before the reset are no
+    /// longer usable: drop them and claim the interfaces again.
+    ///
+    /// ### Platform-specific details
+    /// * On Linux, the kernel keeps the device if its descriptors are
+    ///   unchanged, so this usually returns `Retained`. usbfs releases the
+    ///   claimed interfaces to reset the device, after which their
+    ///   [`Interface::endpoint`] and [`Interface::set_alt_setting`] fail and
+    ///   [`Interface::get_alt_setting`] returns 0.
+    /// * On macOS, the device is always re-enumerated, so this returns
+    ///   `Reenumerated` on success.
+    /// * Not supported on Windows
+    pub fn reset_and_revalidate(&self) -> impl MaybeFuture<Output = Result<ResetOutcome, Error>> {
+        self.backend.clone().reset_and_revalidate()
+    }
+
+    /// Drop this handle and wait for the device to be closed.
+    ///
+    /// The device is closed once all clones of the `Device` and all of its
+    /// [`Interface`]s have been dropped or
+    /// [closed][`Interface::wait_closed`], and their pending transfers have
+    /// been cancelled and returned by the OS. This does not cancel any
+    /// transfers itself, and doesn't complete while other handles are alive.
+    /// Dropping an [`Endpoint`] cancels its transfers without blocking, so
+    /// drop the endpoints first and then `.await` this to tear down the
+    /// device deterministically from async code.
+    ///
+    /// The returned `MaybeFuture` completes after the OS handle is closed,
+    /// so the device can immediately be opened again, including by another
+    /// process.
+    pub fn wait_closed(self) -> impl MaybeFuture<Output = ()> {
+        self.backend.closed.closed()
+    }
+
     /// Submit a single **IN (device-to-host)** transfer on the default **control** endpoint.
     ///
+    /// This does not require claiming an interface, so it can be used for
+    /// device-level requests such as reading descriptors or vendor requests
+    /// while the device's interfaces are bound to kernel drivers.
+    ///
     /// ### Example
     ///
     /// ```no_run
@@ -308,7 +787,9 @@ impl Device {
     /// ### Platform-specific details
     ///
     /// * Not supported on Windows. You must [claim an interface][`Device::claim_interface`]
-    ///   and use the interface handle to submit transfers.
+    ///   and use the interface handle to submit transfers. WinUSB only
+    ///   provides control transfers through an interface handle, which
+    ///   requires the interface to be bound to WinUSB.
     #[cfg(any(target_os = "linux", target_os = "macos", target_os = "android"))]
     pub fn control_in(
         &self,
@@ -318,8 +799,32 @@ impl Device {
         self.backend.clone().control_in(data, timeout)
     }
 
+    /// Submit a single **IN (device-to-host)** transfer on the default
+    /// **control** endpoint, receiving the data into `buffer`.
+    ///
+    /// See [`Interface::control_in_buffer`] for details.
+    ///
+    /// ### Platform-specific details
+    ///
+    /// * Not supported on Windows. You must [claim an interface][`Device::claim_interface`]
+    ///   and use the interface handle to submit transfers.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "android"))]
+    pub fn control_in_buffer(
+        &self,
+        data: ControlIn,
+        buffer: Buffer,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Completion> {
+        self.backend
+            .clone()
+            .control_in_buffer(data, buffer, timeout)
+    }
+
     /// Submit a single **OUT (host-to-device)** transfer on the default **control** endpoint.
     ///
+    /// This does not require claiming an interface. See
+    /// [`control_in`][`Self::control_in`].
+    ///
     /// ### Example
     ///
     /// ```no_run
@@ -354,343 +859,2988 @@ impl Device {
     ) -> impl MaybeFuture<Output = Result<(), TransferError>> {
         self.backend.clone().control_out(data, timeout)
     }
-}
-
-impl Debug for Device {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Device").finish()
-    }
-}
-
-/// An opened interface of a USB device.
-///
-/// Obtain an `Interface` with the [`Device::claim_interface`] method.
-///
-/// This type is reference-counted with an [`Arc`] internally, and can be cloned cheaply for
-/// use in multiple places in your program. The interface is released when all clones, and all
-/// associated [`Endpoint`]s are dropped.
-#[derive(Clone)]
-pub struct Interface {
-    backend: Arc<platform::Interface>,
-}
-
-impl Interface {
-    pub(crate) fn wrap(backend: Arc<platform::Interface>) -> Self {
-        Interface { backend }
-    }
 
-    /// Select the alternate setting of this interface.
+    /// Read the device's status with a standard `GET_STATUS` request.
     ///
-    /// An alternate setting is a mode of the interface that makes particular endpoints available
-    /// and may enable or disable functionality of the device. The OS resets the device to the default
-    /// alternate setting when the interface is released or the program exits.
+    /// ### Platform-specific details
     ///
-    /// You must not have any pending transfers or open `Endpoints` on this interface when changing
-    /// the alternate setting.
-    pub fn set_alt_setting(&self, alt_setting: u8) -> impl MaybeFuture<Output = Result<(), Error>> {
-        self.backend.clone().set_alt_setting(alt_setting)
-    }
-
-    /// Get the current alternate setting of this interface.
-    pub fn get_alt_setting(&self) -> u8 {
-        self.backend.get_alt_setting()
+    /// * Not supported on Windows. You must [claim an interface][`Device::claim_interface`]
+    ///   and use the interface handle to submit the request.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "android"))]
+    pub fn get_status(
+        &self,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Result<DeviceStatus, TransferError>> {
+        use crate::transfer::{ControlType, Recipient};
+        const GET_STATUS: u8 = 0x00;
+        self.control_in(
+            ControlIn {
+                control_type: ControlType::Standard,
+                recipient: Recipient::Device,
+                request: GET_STATUS,
+                value: 0,
+                index: 0,
+                length: 2,
+            },
+            timeout,
+        )
+        .map(|res| match *res? {
+            [lo, hi, ..] => Ok(DeviceStatus(u16::from_le_bytes([lo, hi]))),
+            _ => Err(TransferError::ShortPacket),
+        })
     }
 
-    /// Submit a single **IN (device-to-host)** transfer on the default **control** endpoint.
-    ///
-    /// ### Example
+    /// Allow or prevent the device from waking the host from suspend.
     ///
-    /// ```no_run
-    /// use std::time::Duration;
-    /// use futures_lite::future::block_on;
-    /// use nusb::transfer::{ ControlIn, ControlType, Recipient };
-    /// # use nusb::MaybeFuture;
-    /// # fn main() -> Result<(), std::io::Error> {
-    /// # let di = nusb::list_devices().wait().unwrap().next().unwrap();
-    /// # let device = di.open().wait().unwrap();
-    /// # let interface = device.claim_interface(0).wait().unwrap();
+    /// This sends a standard `SET_FEATURE` or `CLEAR_FEATURE` request for the
+    /// `DEVICE_REMOTE_WAKEUP` feature. Check
+    /// [`ConfigurationDescriptor::supports_remote_wakeup`][crate::descriptors::ConfigurationDescriptor::supports_remote_wakeup]
+    /// on the active configuration first, as devices without the capability
+    /// may stall the request.
     ///
-    /// let data: Vec<u8> = interface.control_in(ControlIn {
-    ///     control_type: ControlType::Vendor,
-    ///     recipient: Recipient::Device,
-    ///     request: 0x30,
-    ///     value: 0x0,
-    ///     index: 0x0,
-    ///     length: 64,
-    /// }, Duration::from_millis(100)).wait()?;
-    /// # Ok(()) }
-    /// ```
+    /// The OS may also change this feature when it suspends the device
+    /// according to its own power management policy.
     ///
     /// ### Platform-specific details
-    /// * On Windows, if the `recipient` is `Interface`, the least significant
-    ///   byte of `index` must match the interface number, or
-    ///   `TransferError::InvalidArgument` will be returned. This is a WinUSB
-    ///   limitation.
-    /// * On Windows, the timeout is currently fixed to 5 seconds and the
-    ///   timeout argument is ignored.
-    pub fn control_in(
+    ///
+    /// * Not supported on Windows, where remote wakeup is armed by the OS
+    ///   according to the device's power policy.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "android"))]
+    pub fn set_remote_wakeup(
         &self,
-        data: ControlIn,
+        enable: bool,
         timeout: Duration,
-    ) -> impl MaybeFuture<Output = Result<Vec<u8>, TransferError>> {
-        self.backend.clone().control_in(data, timeout)
+    ) -> impl MaybeFuture<Output = Result<(), TransferError>> {
+        self.feature(Feature::DeviceRemoteWakeup, enable, timeout)
     }
 
-    /// Submit a single **OUT (host-to-device)** transfer on the default
-    /// **control** endpoint.
-    ///
-    /// ### Example
+    /// Allow or prevent the device from initiating transitions to the U1
+    /// link power state.
     ///
-    /// ```no_run
-    /// use std::time::Duration;
-    /// use futures_lite::future::block_on;
-    /// use nusb::transfer::{ ControlOut, ControlType, Recipient };
-    /// # use nusb::MaybeFuture;
-    /// # fn main() -> Result<(), std::io::Error> {
-    /// # let di = nusb::list_devices().wait().unwrap().next().unwrap();
-    /// # let device = di.open().wait().unwrap();
-    /// # let interface = device.claim_interface(0).wait().unwrap();
+    /// This sends a standard `SET_FEATURE` or `CLEAR_FEATURE` request for the
+    /// `U1_ENABLE` feature, and is only valid for SuperSpeed devices in the
+    /// configured state. Disabling U1 and U2 can work around devices that
+    /// misbehave with link power management. The current state is reported
+    /// by [`DeviceStatus::u1_enabled`].
     ///
-    /// interface.control_out(ControlOut {
-    ///     control_type: ControlType::Vendor,
-    ///     recipient: Recipient::Device,
-    ///     request: 0x32,
-    ///     value: 0x0,
-    ///     index: 0x0,
-    ///     data: &[0x01, 0x02, 0x03, 0x04],
-    /// }, Duration::from_millis(100)).wait()?;
-    /// # Ok(()) }
-    /// ```
+    /// The OS may enable the feature again when the device is reset or
+    /// resumed, or when it changes its own link power management policy.
     ///
     /// ### Platform-specific details
-    /// * On Windows, if the `recipient` is `Interface`, the least significant
-    ///   byte of `index` must match the interface number, or
-    ///   `TransferError::InvalidArgument` will be returned. This is a WinUSB
-    ///   limitation.
-    /// * On Windows, the timeout is currently fixed to 5 seconds and the
-    ///   timeout argument is ignored.
-    pub fn control_out(
+    ///
+    /// * Not supported on Windows.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "android"))]
+    pub fn set_u1_enabled(
         &self,
-        data: ControlOut,
+        enable: bool,
         timeout: Duration,
     ) -> impl MaybeFuture<Output = Result<(), TransferError>> {
-        self.backend.clone().control_out(data, timeout)
+        self.feature(Feature::U1Enable, enable, timeout)
     }
 
-    /// Get the interface number.
+    /// Allow or prevent the device from initiating transitions to the U2
+    /// link power state.
+    ///
+    /// See [`set_u1_enabled`][`Self::set_u1_enabled`]. The current state is
+    /// reported by [`DeviceStatus::u2_enabled`].
+    ///
+    /// ### Platform-specific details
+    ///
+    /// * Not supported on Windows.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "android"))]
+    pub fn set_u2_enabled(
+        &self,
+        enable: bool,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Result<(), TransferError>> {
+        self.feature(Feature::U2Enable, enable, timeout)
+    }
+
+    /// Inform the device of the system exit latencies for U1 and U2 with a
+    /// standard `SET_SEL` request.
+    ///
+    /// The OS normally sends this request when configuring a SuperSpeed
+    /// device. Sending it again may be needed after changing link power
+    /// management settings of a device that computes its own timeouts from
+    /// these values.
+    ///
+    /// ### Platform-specific details
+    ///
+    /// * Not supported on Windows.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "android"))]
+    pub fn set_sel(
+        &self,
+        latency: SystemExitLatency,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Result<(), TransferError>> {
+        use crate::transfer::{ControlType, Recipient};
+        const SET_SEL: u8 = 0x30;
+        let [u2_sel_lo, u2_sel_hi] = latency.u2_sel.to_le_bytes();
+        let [u2_pel_lo, u2_pel_hi] = latency.u2_pel.to_le_bytes();
+        let data = [
+            latency.u1_sel,
+            latency.u1_pel,
+            u2_sel_lo,
+            u2_sel_hi,
+            u2_pel_lo,
+            u2_pel_hi,
+        ];
+        self.control_out(
+            ControlOut {
+                control_type: ControlType::Standard,
+                recipient: Recipient::Device,
+                request: SET_SEL,
+                value: 0,
+                index: 0,
+                data: &data,
+            },
+            timeout,
+        )
+    }
+
+    /// Inform the device of the delay from the time the host transmits an
+    /// isochronous packet to when it is received, in nanoseconds, with a
+    /// standard `SET_ISOCH_DELAY` request.
+    ///
+    /// The OS normally sends this request when configuring a SuperSpeed
+    /// device.
+    ///
+    /// ### Platform-specific details
+    ///
+    /// * Not supported on Windows.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "android"))]
+    pub fn set_isoch_delay(
+        &self,
+        delay_ns: u16,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Result<(), TransferError>> {
+        use crate::transfer::{ControlType, Recipient};
+        const SET_ISOCH_DELAY: u8 = 0x31;
+        self.control_out(
+            ControlOut {
+                control_type: ControlType::Standard,
+                recipient: Recipient::Device,
+                request: SET_ISOCH_DELAY,
+                value: delay_ns,
+                index: 0,
+                data: &[],
+            },
+            timeout,
+        )
+    }
+
+    /// Enable a standard feature with a `SET_FEATURE` request.
+    ///
+    /// ### Platform-specific details
+    ///
+    /// * Not supported on Windows. Use [`Interface::set_feature`] instead.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "android"))]
+    pub fn set_feature(
+        &self,
+        feature: Feature,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Result<(), TransferError>> {
+        self.control_out(feature.set(), timeout)
+    }
+
+    /// Disable a standard feature with a `CLEAR_FEATURE` request.
+    ///
+    /// ### Platform-specific details
+    ///
+    /// * Not supported on Windows. Use [`Interface::clear_feature`] instead.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "android"))]
+    pub fn clear_feature(
+        &self,
+        feature: Feature,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Result<(), TransferError>> {
+        self.control_out(feature.clear(), timeout)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "android"))]
+    fn feature(
+        &self,
+        feature: Feature,
+        enable: bool,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Result<(), TransferError>> {
+        let req = if enable {
+            feature.set()
+        } else {
+            feature.clear()
+        };
+        self.control_out(req, timeout)
+    }
+}
+
+/// System exit latencies sent to a SuperSpeed device with `Device::set_sel`.
+///
+/// Latencies are in microseconds.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct SystemExitLatency {
+    /// U1 system exit latency.
+    pub u1_sel: u8,
+
+    /// U1 device-to-host exit latency.
+    pub u1_pel: u8,
+
+    /// U2 system exit latency.
+    pub u2_sel: u16,
+
+    /// U2 device-to-host exit latency.
+    pub u2_pel: u16,
+}
+
+impl Debug for Device {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Device").finish()
+    }
+}
+
+/// *(Linux and Android only)* The usbfs file descriptor of the device.
+///
+/// This can be registered with an `epoll` loop, or passed to another process.
+/// Performing ioctls on the file descriptor that change the state of the
+/// device, such as claiming interfaces or submitting URBs, may interfere with
+/// nusb.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl std::os::fd::AsFd for Device {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        self.backend.fd()
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl std::os::fd::AsRawFd for Device {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        std::os::fd::AsRawFd::as_raw_fd(&self.backend.fd())
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl Device {
+    /// *(macOS-only)* Get the IOKit `IOUSBDeviceInterface` used to access the device.
+    ///
+    /// The returned pointer is an `IOUSBDeviceInterface **`, and is valid as
+    /// long as this `Device` or a clone of it is alive. It should not be
+    /// released by the caller. Use
+    /// [`DeviceInfo::registry_entry_id`] to find the device's IOKit service.
+    pub fn as_raw_iokit_device(&self) -> *mut c_void {
+        self.backend.raw_device()
+    }
+}
+
+/// Reattaches the kernel driver of an interface when dropped.
+///
+/// Returned by [`Device::detach_kernel_driver_guard`].
+#[derive(Debug)]
+pub struct KernelDriverGuard {
+    device: Device,
+    interface: u8,
+    detached: bool,
+}
+
+impl KernelDriverGuard {
+    /// Returns `true` if a kernel driver was detached, and will be
+    /// reattached when the guard is dropped.
+    pub fn was_detached(&self) -> bool {
+        self.detached
+    }
+
+    /// Drop the guard without reattaching the kernel driver.
+    pub fn keep_detached(mut self) {
+        self.detached = false;
+    }
+}
+
+impl Drop for KernelDriverGuard {
+    fn drop(&mut self) {
+        if self.detached {
+            if let Err(e) = self.device.attach_kernel_driver(self.interface) {
+                warn!(
+                    "Failed to reattach kernel driver for interface {}: {e}",
+                    self.interface
+                );
+            }
+        }
+    }
+}
+
+/// Device status bits returned by a standard `GET_STATUS` request.
+///
+/// Returned by `Device::get_status`, or construct it from the two bytes of
+/// the response to a request made through an [`Interface`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DeviceStatus(pub u16);
+
+impl DeviceStatus {
+    /// Whether the device is currently self-powered.
+    pub fn self_powered(&self) -> bool {
+        self.0 & (1 << 0) != 0
+    }
+
+    /// Whether the device is currently allowed to wake the host.
+    ///
+    /// See [`Device::set_remote_wakeup`].
+    pub fn remote_wakeup(&self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+
+    /// Whether the device may initiate U1 link power state transitions
+    /// (SuperSpeed only).
+    pub fn u1_enabled(&self) -> bool {
+        self.0 & (1 << 2) != 0
+    }
+
+    /// Whether the device may initiate U2 link power state transitions
+    /// (SuperSpeed only).
+    pub fn u2_enabled(&self) -> bool {
+        self.0 & (1 << 3) != 0
+    }
+
+    /// Whether Latency Tolerance Messaging is enabled (SuperSpeed only).
+    pub fn ltm_enabled(&self) -> bool {
+        self.0 & (1 << 4) != 0
+    }
+}
+
+impl Debug for DeviceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceStatus")
+            .field("self_powered", &self.self_powered())
+            .field("remote_wakeup", &self.remote_wakeup())
+            .field("u1_enabled", &self.u1_enabled())
+            .field("u2_enabled", &self.u2_enabled())
+            .field("ltm_enabled", &self.ltm_enabled())
+            .finish()
+    }
+}
+
+/// Claim status of an interface, from [`Device::interface_claim_status`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ClaimStatus {
+    /// The interface is not claimed and can be claimed.
+    Free,
+
+    /// The interface is bound to the OS driver with the contained name,
+    /// such as `"usbhid"` or `"cdc_acm"`.
+    ///
+    /// On Linux, the driver can be detached with
+    /// [`Device::detach_and_claim_interface`].
+    KernelDriver(String),
+
+    /// The interface is claimed by another `Interface` handle or program.
+    Claimed,
+}
+
+/// Result of [`Device::reset_and_revalidate`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ResetOutcome {
+    /// The device kept its descriptors and address, and the `Device` can
+    /// still be used.
+    Retained,
+
+    /// The device re-enumerated as a new device or was disconnected, and
+    /// the `Device` can no longer be used.
+    Reenumerated,
+}
+
+/// An opened interface of a USB device.
+///
+/// Obtain an `Interface` with the [`Device::claim_interface`] method.
+///
+/// This type is reference-counted with an [`Arc`] internally, and can be cloned cheaply for
+/// use in multiple places in your program. The interface is released when all clones, and all
+/// associated [`Endpoint`]s are dropped.
+#[derive(Clone)]
+pub struct Interface {
+    backend: Arc<platform::Interface>,
+}
+
+impl Interface {
+    pub(crate) fn wrap(backend: Arc<platform::Interface>) -> Self {
+        Interface { backend }
+    }
+
+    /// Select the alternate setting of this interface.
+    ///
+    /// An alternate setting is a mode of the interface that makes particular endpoints available
+    /// and may enable or disable functionality of the device. The OS resets the device to the default
+    /// alternate setting when the interface is released or the program exits.
+    ///
+    /// You must not have any pending transfers or open `Endpoints` on this interface when changing
+    /// the alternate setting.
+    pub fn set_alt_setting(&self, alt_setting: u8) -> impl MaybeFuture<Output = Result<(), Error>> {
+        self.backend.clone().set_alt_setting(alt_setting)
+    }
+
+    /// Get the current alternate setting of this interface.
+    ///
+    /// This returns the alternate setting last selected by
+    /// [`set_alt_setting`][`Self::set_alt_setting`], and does not perform IO.
+    /// Use [`query_alt_setting`][`Self::query_alt_setting`] to read it from
+    /// the device.
+    pub fn get_alt_setting(&self) -> u8 {
+        self.backend.get_alt_setting()
+    }
+
+    /// Allow or prevent the OS from suspending the device while it is idle.
+    ///
+    /// Disable autosuspend while streaming from a device that sends data
+    /// rarely, such as an interrupt endpoint that reports events, to avoid
+    /// the device being suspended between transfers. Enable it when the
+    /// device is idle to allow it to save power.
+    ///
+    /// ### Platform-specific details
+    ///
+    /// * On Linux, this applies to the whole device, and only takes effect if
+    ///   runtime power management is enabled for the device in the
+    ///   `power/control` sysfs attribute. The setting is reverted when the
+    ///   device is closed.
+    /// * On Windows, this sets the WinUSB `AUTO_SUSPEND` power policy.
+    /// * Not supported on macOS.
+    pub fn set_autosuspend(&self, enable: bool) -> Result<(), Error> {
+        self.backend.set_autosuspend(enable)
+    }
+
+    /// Set how long the device must be idle before the OS suspends it, if
+    /// autosuspend is enabled.
+    ///
+    /// ### Platform-specific details
+    ///
+    /// * On Linux, this writes the `power/autosuspend_delay_ms` sysfs
+    ///   attribute, which usually requires root privileges, and applies to the
+    ///   whole device until it is changed again. Not supported on Android.
+    /// * On Windows, this sets the WinUSB `SUSPEND_DELAY` power policy.
+    /// * Not supported on macOS.
+    pub fn set_autosuspend_delay(&self, delay: Duration) -> Result<(), Error> {
+        self.backend.set_autosuspend_delay(delay)
+    }
+
+    /// Enable a standard feature with a `SET_FEATURE` request.
+    ///
+    /// The feature may be a device, interface, or endpoint feature. Most OSes
+    /// only allow interface and endpoint features to target interfaces and
+    /// endpoints that belong to a claimed interface.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Windows, `timeout` is ignored. An interface feature must target
+    ///   this interface, and device features are sent through it, as with
+    ///   [`control_out`][`Self::control_out`].
+    pub fn set_feature(
+        &self,
+        feature: Feature,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Result<(), TransferError>> {
+        self.control_out(feature.set(), timeout)
+    }
+
+    /// Disable a standard feature with a `CLEAR_FEATURE` request.
+    ///
+    /// See [`set_feature`][`Self::set_feature`].
+    pub fn clear_feature(
+        &self,
+        feature: Feature,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Result<(), TransferError>> {
+        self.control_out(feature.clear(), timeout)
+    }
+
+    /// Read the current alternate setting of this interface from the device.
+    ///
+    /// This sends a standard `GET_INTERFACE` request, and updates the value
+    /// returned by [`get_alt_setting`][`Self::get_alt_setting`] to match. Use
+    /// it to resynchronize after the alternate setting may have been changed
+    /// outside of this `Interface`, such as by a device reset.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Windows, WinUSB waits up to its fixed 5 second control timeout
+    ///   for the device to respond, ignoring `timeout`.
+    pub fn query_alt_setting(
+        &self,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Result<u8, TransferError>> {
+        use crate::transfer::{ControlType, Recipient};
+        const GET_INTERFACE: u8 = 0x0A;
+        let backend = self.backend.clone();
+        self.control_in(
+            ControlIn {
+                control_type: ControlType::Standard,
+                recipient: Recipient::Interface,
+                request: GET_INTERFACE,
+                value: 0,
+                index: self.interface_number() as u16,
+                length: 1,
+            },
+            timeout,
+        )
+        .map(move |res| match res?.first() {
+            Some(&alt_setting) => {
+                backend.update_alt_setting(alt_setting);
+                Ok(alt_setting)
+            }
+            None => Err(TransferError::ShortPacket),
+        })
+    }
+
+    /// Drop this handle and wait for the interface to be released.
+    ///
+    /// The interface is released once all clones of the `Interface` and all
+    /// of its [`Endpoint`]s have been dropped, and their pending transfers
+    /// have been cancelled and returned by the OS. This does not cancel any
+    /// transfers itself, and doesn't complete while other handles are alive.
+    /// Dropping an `Endpoint`
+    /// cancels its transfers without blocking, so drop the endpoints first
+    /// and then `.await` this to wait for the OS to finish with them and
+    /// release the interface, instead of leaving the release to whichever
+    /// `Drop` happens last.
+    ///
+    /// ```no_run
+    /// use nusb::transfer::{Bulk, In};
+    /// # async fn example(interface: nusb::Interface, ep: nusb::Endpoint<Bulk, In>) {
+    /// drop(ep);
+    /// interface.wait_closed().await;
+    /// // The interface can now be claimed again.
+    /// # }
+    /// ```
+    ///
+    /// On Linux, if the interface was claimed with
+    /// [`detach_and_claim_interface`][`Device::detach_and_claim_interface`],
+    /// the kernel driver is reattached before this completes.
+    pub fn wait_closed(self) -> impl MaybeFuture<Output = ()> {
+        self.backend.closed.closed()
+    }
+
+    /// Release the interface, then attach kernel drivers to it.
+    ///
+    /// This waits for the interface to be released as with
+    /// [`wait_closed`][`Self::wait_closed`], so the other clones of the `Interface` and
+    /// its endpoints must be dropped for this to complete. It then returns
+    /// the interface to the OS class driver, whether or not it was claimed
+    /// with [`detach_and_claim_interface`][`Device::detach_and_claim_interface`].
+    ///
+    /// ```no_run
+    /// # use nusb::MaybeFuture;
+    /// # fn example(interface: nusb::Interface) -> Result<(), nusb::Error> {
+    /// // ... configure the device with vendor requests ...
+    /// interface.attach_kernel_driver().wait()?;
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// ### Platform-specific details
+    /// This function can only attach kernel drivers on Linux. Calling on
+    /// other platforms has the same effect as [`wait_closed`][`Self::wait_closed`].
+    pub fn attach_kernel_driver(self) -> impl MaybeFuture<Output = Result<(), Error>> {
+        #[cfg(target_os = "linux")]
+        let device = Device::wrap(self.backend.device.clone());
+        let interface = self.interface_number();
+
+        self.wait_closed().map(move |()| {
+            #[cfg(target_os = "linux")]
+            device.attach_kernel_driver(interface)?;
+            let _ = interface;
+
+            Ok(())
+        })
+    }
+
+    /// Submit a single **IN (device-to-host)** transfer on the default **control** endpoint.
+    ///
+    /// ### Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use futures_lite::future::block_on;
+    /// use nusb::transfer::{ ControlIn, ControlType, Recipient };
+    /// # use nusb::MaybeFuture;
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// # let di = nusb::list_devices().wait().unwrap().next().unwrap();
+    /// # let device = di.open().wait().unwrap();
+    /// # let interface = device.claim_interface(0).wait().unwrap();
+    ///
+    /// let data: Vec<u8> = interface.control_in(ControlIn {
+    ///     control_type: ControlType::Vendor,
+    ///     recipient: Recipient::Device,
+    ///     request: 0x30,
+    ///     value: 0x0,
+    ///     index: 0x0,
+    ///     length: 64,
+    /// }, Duration::from_millis(100)).wait()?;
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// ### Platform-specific details
+    /// * On Windows, if the `recipient` is `Interface`, the least significant
+    ///   byte of `index` must match the interface number, or
+    ///   `TransferError::InvalidArgument` will be returned. This is a WinUSB
+    ///   limitation.
+    /// * On Windows, the timeout is currently fixed to 5 seconds and the
+    ///   timeout argument is ignored.
+    pub fn control_in(
+        &self,
+        data: ControlIn,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Result<Vec<u8>, TransferError>> {
+        self.backend.clone().control_in(data, timeout)
+    }
+
+    /// Submit a single **IN (device-to-host)** transfer on the default
+    /// **control** endpoint, receiving the data into `buffer`.
+    ///
+    /// This is like [`control_in`][`Self::control_in`], but reuses the
+    /// caller's buffer instead of allocating a new `Vec` for each request,
+    /// which reduces allocations when polling a device frequently. The buffer
+    /// is returned in the [`Completion`] along with the status, even if the
+    /// transfer failed, and can be passed to the next request.
+    ///
+    /// The buffer's contents and `requested_len` are ignored; `data.length`
+    /// bytes are requested. If the buffer's capacity is too small, it is
+    /// replaced with a new allocation.
+    ///
+    /// ### Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use nusb::transfer::{ Buffer, ControlIn, ControlType, Recipient };
+    /// # use nusb::MaybeFuture;
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// # let di = nusb::list_devices().wait().unwrap().next().unwrap();
+    /// # let device = di.open().wait().unwrap();
+    /// # let interface = device.claim_interface(0).wait().unwrap();
+    ///
+    /// let mut buffer = Buffer::new(64);
+    /// loop {
+    ///     let c = interface.control_in_buffer(ControlIn {
+    ///         control_type: ControlType::Vendor,
+    ///         recipient: Recipient::Device,
+    ///         request: 0x30,
+    ///         value: 0x0,
+    ///         index: 0x0,
+    ///         length: 64,
+    ///     }, buffer, Duration::from_millis(100)).wait();
+    ///     c.status?;
+    ///     println!("status: {:?}", &c.buffer[..]);
+    ///     buffer = c.buffer;
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// ### Platform-specific details
+    /// * On Linux, the buffer also holds the 8-byte setup packet while the
+    ///   transfer is pending, so a `capacity` of at least `data.length + 8`
+    ///   avoids reallocation.
+    /// * On Windows, if the `recipient` is `Interface`, the least significant
+    ///   byte of `index` must match the interface number, or
+    ///   `TransferError::InvalidArgument` will be returned. This is a WinUSB
+    ///   limitation.
+    /// * On Windows, the timeout is currently fixed to 5 seconds and the
+    ///   timeout argument is ignored.
+    pub fn control_in_buffer(
+        &self,
+        data: ControlIn,
+        buffer: Buffer,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Completion> {
+        self.backend
+            .clone()
+            .control_in_buffer(data, buffer, timeout)
+    }
+
+    /// Submit a single **OUT (host-to-device)** transfer on the default
+    /// **control** endpoint.
+    ///
+    /// ### Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use futures_lite::future::block_on;
+    /// use nusb::transfer::{ ControlOut, ControlType, Recipient };
+    /// # use nusb::MaybeFuture;
+    /// # fn main() -> Result<(), std::io::Error> {
+    /// # let di = nusb::list_devices().wait().unwrap().next().unwrap();
+    /// # let device = di.open().wait().unwrap();
+    /// # let interface = device.claim_interface(0).wait().unwrap();
+    ///
+    /// interface.control_out(ControlOut {
+    ///     control_type: ControlType::Vendor,
+    ///     recipient: Recipient::Device,
+    ///     request: 0x32,
+    ///     value: 0x0,
+    ///     index: 0x0,
+    ///     data: &[0x01, 0x02, 0x03, 0x04],
+    /// }, Duration::from_millis(100)).wait()?;
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// ### Platform-specific details
+    /// * On Windows, if the `recipient` is `Interface`, the least significant
+    ///   byte of `index` must match the interface number, or
+    ///   `TransferError::InvalidArgument` will be returned. This is a WinUSB
+    ///   limitation.
+    /// * On Windows, the timeout is currently fixed to 5 seconds and the
+    ///   timeout argument is ignored.
+    pub fn control_out(
+        &self,
+        data: ControlOut,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Result<(), TransferError>> {
+        self.backend.clone().control_out(data, timeout)
+    }
+
+    /// Open an Interrupt IN endpoint as a [`Stream`][`futures_core::Stream`]
+    /// of received reports.
+    ///
+    /// `depth` transfers of the endpoint's maximum packet size are kept
+    /// pending and resubmitted as they complete, which continuously polls the
+    /// device as needed for HID-like devices. See [`InterruptStream`] for
+    /// details.
+    ///
+    /// Fails as [`Interface::endpoint`] does if the endpoint is not an
+    /// Interrupt IN endpoint of the current alternate setting or is already in
+    /// use.
+    ///
+    /// ### Panics
+    /// * If `depth` is zero.
+    pub fn interrupt_in_stream(&self, address: u8, depth: usize) -> Result<InterruptStream, Error> {
+        let endpoint = self.endpoint::<Interrupt, In>(address)?;
+        Ok(InterruptStream::new(endpoint, depth))
+    }
+
+    /// Create a [`ControlQueue`] for pipelining control transfers on the
+    /// default control endpoint through this interface.
+    pub fn control_queue(&self) -> ControlQueue {
+        ControlQueue::new(self.clone())
+    }
+
+    /// Get the interface number.
     pub fn interface_number(&self) -> u8 {
         self.backend.interface_number
     }
 
-    /// Get the interface descriptors for the alternate settings of this interface.
+    /// Get the interface descriptors for the alternate settings of this interface.
+    ///
+    /// This returns cached data and does not perform IO.
+    pub fn descriptors(&self) -> impl Iterator<Item = InterfaceDescriptor<'_>> {
+        let active = self.backend.device.active_configuration_value();
+
+        let configuration = self
+            .backend
+            .device
+            .configuration_descriptors()
+            .find(|c| c.configuration_value() == active);
+
+        configuration
+            .into_iter()
+            .flat_map(|i| i.interface_alt_settings())
+            .filter(|g| g.interface_number() == self.backend.interface_number)
+    }
+
+    /// Get the interface descriptor for the current alternate setting.
+    ///
+    /// This returns cached data and does not perform IO. The descriptor's
+    /// [`endpoints`][`InterfaceDescriptor::endpoints`] are the endpoints
+    /// available in the current alternate setting, selected with
+    /// [`set_alt_setting`][`Self::set_alt_setting`]. Returns `None` if the
+    /// device's active configuration no longer contains the interface, such
+    /// as after the configuration was changed.
+    ///
+    /// ```no_run
+    /// # fn example(interface: nusb::Interface) {
+    /// if let Some(desc) = interface.descriptor() {
+    ///     for endpoint in desc.endpoints() {
+    ///         println!("{:02x}: {:?}", endpoint.address(), endpoint.transfer_type());
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn descriptor(&self) -> Option<InterfaceDescriptor<'_>> {
+        self.descriptors()
+            .find(|i| i.alternate_setting() == self.get_alt_setting())
+    }
+
+    /// Get the descriptor of the endpoint with the given address in the
+    /// current alternate setting.
+    ///
+    /// The descriptor provides the endpoint's transfer type, direction,
+    /// maximum packet size, and polling interval, without opening it. Returns
+    /// `None` if the current alternate setting has no such endpoint.
+    pub fn endpoint_descriptor(&self, address: u8) -> Option<EndpointDescriptor<'_>> {
+        self.descriptor()?
+            .endpoints()
+            .find(|ep| ep.address() == address)
+    }
+
+    /// Open an endpoint.
+    ///
+    /// This claims exclusive access to the endpoint and returns an [`Endpoint`]
+    /// that can be used to submit transfers. The type-level `EndpointType` and
+    /// `EndpointDirection` parameters must match the endpoint type and
+    /// direction.
+    pub fn endpoint<EpType: EndpointType, Dir: EndpointDirection>(
+        &self,
+        address: u8,
+    ) -> Result<Endpoint<EpType, Dir>, Error> {
+        let Some(ep_desc) = self.endpoint_descriptor(address) else {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                "specified endpoint does not exist on this interface",
+            ));
+        };
+
+        if address & Direction::MASK != Dir::DIR as u8 {
+            return Err(Error::new(ErrorKind::Other, "incorrect endpoint direction"));
+        }
+
+        if ep_desc.transfer_type() != EpType::TYPE {
+            return Err(Error::new(ErrorKind::Other, "incorrect endpoint type"));
+        }
+
+        let interval = ep_desc.interval();
+        let packets_per_microframe = ep_desc.packets_per_microframe();
+        let max_bytes_per_interval = ep_desc.max_bytes_per_interval();
+        let backend = self.backend.endpoint(ep_desc)?;
+        Ok(Endpoint {
+            backend,
+            interval,
+            packets_per_microframe,
+            max_bytes_per_interval,
+            submitted: 0,
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            next_frame: None,
+            pending_bytes: 0,
+            short_packet: ShortPacketPolicy::default(),
+            splits: VecDeque::new(),
+            vectored: VecDeque::new(),
+            stats: None,
+            meta: VecDeque::new(),
+            timestamps: false,
+            submit_queue: None,
+            cancel_signal: None,
+            cancel_requested: false,
+            batch_interrupts: false,
+            max_pending: None,
+            auto_resubmit: None,
+            paused: false,
+            ep_type: PhantomData,
+            ep_dir: PhantomData,
+        })
+    }
+}
+
+/// *(Windows-only)* The file handle of the device interface that was opened
+/// to access this interface with WinUSB.
+///
+/// Use [`Interface::as_raw_winusb_handle`] to call WinUSB functions.
+#[cfg(target_os = "windows")]
+impl std::os::windows::io::AsRawHandle for Interface {
+    fn as_raw_handle(&self) -> std::os::windows::io::RawHandle {
+        self.backend.handle
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+impl Interface {
+    /// *(Windows-only)* Get the `WINUSB_INTERFACE_HANDLE` of this interface.
+    ///
+    /// The handle is valid as long as this `Interface` or a clone of it is
+    /// alive, and must not be freed by the caller.
+    #[cfg(target_os = "windows")]
+    pub fn as_raw_winusb_handle(&self) -> *mut c_void {
+        self.backend.winusb_handle
+    }
+
+    /// *(macOS-only)* Get the IOKit `IOUSBInterfaceInterface` used to access
+    /// this interface.
+    ///
+    /// The returned pointer is an `IOUSBInterfaceInterface **`, and is valid
+    /// as long as this `Interface` or a clone of it is alive. It should not
+    /// be released by the caller.
+    #[cfg(target_os = "macos")]
+    pub fn as_raw_iokit_interface(&self) -> *mut c_void {
+        self.backend.interface.raw as *mut c_void
+    }
+}
+
+impl Debug for Interface {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Interface")
+            .field("number", &self.backend.interface_number)
+            .finish()
+    }
+}
+
+/// Exclusive access to an endpoint of a USB device.
+///
+/// Obtain an `Endpoint` with the [`Interface::endpoint`] method.
+///
+/// An `Endpoint` manages a queue of pending transfers. Submitting a transfer is
+/// a non-blocking operation that adds the operation to the queue. Completed
+/// transfers can be popped from the queue synchronously or asynchronously.
+///
+/// This separation of submission and completion makes the API cancel-safe, and
+/// makes it easy to submit multiple transfers at once, regardless of whether
+/// you are using asynchronous or blocking waits.
+///
+/// To maximize throughput and minimize latency when streaming data, the host
+/// controller needs to attempt a transfer in every possible frame. That
+/// requires always having a transfer request pending with the kernel by
+/// submitting multiple transfer requests and re-submitting them as they
+/// complete.
+///
+/// When the `Endpoint` is dropped, any pending transfers are cancelled.
+///
+/// This type provides a low-level API letting you manage transfers directly.
+/// For a higher-level buffered API implementing [`std::io::Read`] and
+/// [`std::io::Write`], use the adapters from [`nusb::io`][`crate::io`]. See
+/// [`Self::reader`] and [`Self::writer`].
+pub struct Endpoint<EpType, Dir> {
+    backend: platform::Endpoint,
+
+    /// `bInterval` from the endpoint descriptor.
+    interval: u8,
+
+    /// Packets per microframe from the endpoint descriptor.
+    packets_per_microframe: u8,
+
+    /// Maximum bytes per service interval from the endpoint descriptors.
+    max_bytes_per_interval: usize,
+
+    /// Number of transfers ever submitted, used to assign `TransferId`s.
+    submitted: u64,
+
+    /// Frame following the last isochronous transfer returned, for
+    /// `current_frame`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    next_frame: Option<u32>,
+
+    /// Total length of pending transfers.
+    pending_bytes: usize,
+
+    /// How short packets complete IN transfers.
+    short_packet: ShortPacketPolicy,
+
+    /// Pending transfers that were split into multiple backend transfers, in
+    /// submission order.
+    splits: VecDeque<SplitTransfer>,
+
+    /// `TransferId` ranges of pending IN transfers submitted together with
+    /// `submit_vectored`, in submission order.
+    vectored: VecDeque<Range<u64>>,
+
+    /// Statistics, if enabled with `enable_stats`.
+    stats: Option<Box<StatsCollector>>,
+
+    /// Tag and submission time of each pending transfer, in submission order.
+    meta: VecDeque<TransferMeta>,
+
+    /// Whether to record timestamps, set with `set_timestamps`.
+    timestamps: bool,
+
+    /// Queue of transfers from `Submitter`s, if any were created.
+    submit_queue: Option<SubmitReceiver>,
+
+    /// Signal set with `set_cancel_signal`, until it fires.
+    cancel_signal: Option<Mutex<Box<dyn CancelSignal>>>,
+
+    /// Whether the cancel signal has fired.
+    cancel_requested: bool,
+
+    /// Whether bursts only request an interrupt for their final transfer.
+    batch_interrupts: bool,
+
+    /// Limit on pending transfers for `submit_when_ready`.
+    max_pending: Option<usize>,
+
+    /// Transfer count and buffers for auto-resubmit mode, while enabled.
+    auto_resubmit: Option<AutoResubmit>,
+
+    /// Whether the endpoint is paused with `pause`.
+    paused: bool,
+
+    ep_type: PhantomData<EpType>,
+    ep_dir: PhantomData<Dir>,
+}
+
+/// A Bulk IN [`Endpoint`].
+///
+/// The type and direction are validated against the endpoint descriptor by
+/// [`Interface::endpoint`], so only operations valid for the endpoint are
+/// available.
+pub type BulkIn = Endpoint<Bulk, In>;
+
+/// A Bulk OUT [`Endpoint`]. See [`BulkIn`].
+pub type BulkOut = Endpoint<Bulk, Out>;
+
+/// An Interrupt IN [`Endpoint`]. See [`BulkIn`].
+pub type InterruptIn = Endpoint<Interrupt, In>;
+
+/// An Interrupt OUT [`Endpoint`]. See [`BulkIn`].
+pub type InterruptOut = Endpoint<Interrupt, Out>;
+
+/// Per-transfer data returned in the transfer's [`Completion`].
+#[derive(Default)]
+struct TransferMeta {
+    tag: u64,
+    submitted_at: Option<Instant>,
+}
+
+/// State of an IN endpoint's auto-resubmit mode.
+struct AutoResubmit {
+    /// Number of transfers to keep pending.
+    num_transfers: usize,
+
+    /// Buffers returned with `recycle`, submitted before allocating new ones.
+    pool: BufferPool,
+}
+
+/// A transfer larger than the platform's maximum transfer size, submitted as
+/// multiple chunks and reassembled into one [`Completion`].
+struct SplitTransfer {
+    /// `TransferId` of the transfer.
+    id: u64,
+
+    /// Number of chunks still in the backend queue.
+    remaining: usize,
+
+    /// The buffer passed to `submit`, which receives the data of IN chunks.
+    ///
+    /// Each chunk's buffer holds a reference to it, so that if the endpoint
+    /// is dropped, it is freed once the backend has returned all chunks.
+    buffer: Arc<Buffer>,
+
+    /// Length of the initialized data in `buffer`.
+    len: usize,
+
+    actual_len: usize,
+
+    status: Result<(), TransferError>,
+
+    /// Set once a chunk ended with a short packet or an error, after which the
+    /// remaining chunks are cancelled and their data discarded.
+    ended: bool,
+}
+
+impl SplitTransfer {
+    /// Account for a completed chunk, returning `true` if it ended the
+    /// transfer so that the remaining chunks must be cancelled.
+    fn add_chunk(&mut self, chunk: &Completion, dir: Direction) -> bool {
+        self.remaining -= 1;
+        if self.ended {
+            return false;
+        }
+
+        if dir == Direction::In {
+            // The chunk received its data in place, directly after the data
+            // of the previous chunks, which were all full.
+            debug_assert_eq!(chunk.buffer.ptr, self.buffer.ptr.wrapping_add(self.len));
+            self.len += chunk.buffer.len();
+        }
+        self.actual_len += chunk.actual_len;
+
+        let short = dir == Direction::In && chunk.buffer.len() < chunk.buffer.requested_len();
+        if short || chunk.status.is_err() {
+            self.ended = true;
+            self.status = chunk.status;
+        }
+        self.ended
+    }
+}
+
+/// Methods for all endpoints.
+impl<EpType: EndpointType, Dir: EndpointDirection> Endpoint<EpType, Dir> {
+    /// Get the endpoint address.
+    pub fn endpoint_address(&self) -> u8 {
+        self.backend.endpoint_address()
+    }
+
+    /// Get the maximum packet size for this endpoint.
+    ///
+    /// Transfers can consist of multiple packets, but are split into packets
+    /// of this size on the bus.
+    pub fn max_packet_size(&self) -> usize {
+        self.backend.max_packet_size
+    }
+
+    /// Get the endpoint's transfer type.
+    pub fn transfer_type(&self) -> TransferType {
+        EpType::TYPE
+    }
+
+    /// Get the endpoint's direction.
+    pub fn direction(&self) -> Direction {
+        Dir::DIR
+    }
+
+    /// Get the `bInterval` field of the endpoint descriptor.
+    ///
+    /// For Interrupt and Isochronous endpoints, this is the polling interval
+    /// in frames or microframes, depending on the device speed.
+    pub fn interval(&self) -> u8 {
+        self.interval
+    }
+
+    /// For isochronous endpoints at high speed, get the number of packets per
+    /// microframe (1, 2, or 3).
+    pub fn packets_per_microframe(&self) -> u8 {
+        self.packets_per_microframe
+    }
+
+    /// Get the maximum number of bytes the endpoint can transfer per service
+    /// interval.
+    ///
+    /// See [`EndpointDescriptor::max_bytes_per_interval`]. For an isochronous
+    /// endpoint, this is the largest usable packet length, which exceeds the
+    /// [maximum packet size][`Self::max_packet_size`] for high-bandwidth and
+    /// SuperSpeed burst endpoints.
+    pub fn max_bytes_per_interval(&self) -> usize {
+        self.max_bytes_per_interval
+    }
+
+    /// Get the number of transfers that have been submitted with `submit` that
+    /// have not yet been returned from `next_complete`.
+    pub fn pending(&self) -> usize {
+        let chunks: usize = self.splits.iter().map(|s| s.remaining - 1).sum();
+        self.backend.pending() - chunks
+    }
+
+    /// Get the total number of bytes of transfers that have been submitted
+    /// and not yet returned from `next_complete`.
+    ///
+    /// This counts the buffer's `len` for OUT transfers and its
+    /// `requested_len` for IN transfers.
+    pub fn pending_bytes(&self) -> usize {
+        self.pending_bytes
+    }
+
+    /// Get the number of completed transfers that can be returned from
+    /// `next_complete` without waiting.
+    ///
+    /// Transfers are returned in the order they were submitted, so a transfer
+    /// that completed out of order is not counted until all transfers
+    /// submitted before it have also completed.
+    pub fn ready(&self) -> usize {
+        let backend_ready = self.backend.ready();
+        if self.splits.is_empty() {
+            return backend_ready;
+        }
+        split_ready(&self.splits, self.pending_ids(), backend_ready)
+    }
+
+    /// Request cancellation of all pending transfers.
+    ///
+    /// The transfers are cancelled asynchronously. Once cancelled, they will be
+    /// returned from calls to `next_complete` so you can tell which were
+    /// completed, partially-completed, or cancelled.
+    pub fn cancel_all(&mut self) {
+        self.backend.cancel_all()
+    }
+
+    /// Request cancellation of a single pending transfer.
+    ///
+    /// `id` is the value returned from `submit` when the transfer was
+    /// submitted. The transfer is cancelled asynchronously, and other
+    /// transfers on the endpoint are unaffected. Transfers are still returned
+    /// from `next_complete` in the order they were submitted, so the
+    /// cancelled transfer is returned in its place in the queue.
+    ///
+    /// Returns `false` if the transfer has already been returned from
+    /// `next_complete`, or if cancellation is not supported.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On macOS, IOKit can only cancel all transfers on an endpoint, so this
+    ///   always returns `false`. Use [`cancel_all`][`Self::cancel_all`]
+    ///   instead.
+    pub fn cancel(&mut self, id: TransferId) -> bool {
+        let Some((index, chunks)) = self.backend_index(id) else {
+            return false;
+        };
+
+        let mut cancelled = false;
+        for i in (index..index + chunks).rev() {
+            cancelled |= self.backend.cancel(i);
+        }
+        cancelled
+    }
+
+    /// Get the platform's native handle for a pending transfer.
+    ///
+    /// Returns `None` if the transfer has already been returned from
+    /// `next_complete`. For a transfer split across multiple OS requests
+    /// because it exceeds [`max_transfer_size`][`Self::max_transfer_size`],
+    /// this is the handle of the first pending request.
+    ///
+    /// This is an escape hatch for platform-specific tweaks not exposed by
+    /// nusb. The handle is:
+    ///
+    /// * On Linux, a `*mut usbdevfs_urb` for the URB submitted to usbfs.
+    /// * On Windows, the `*mut OVERLAPPED` passed to `WinUsb_ReadPipe` or
+    ///   `WinUsb_WritePipe`.
+    /// * On macOS, the `refcon` context pointer passed to the IOKit
+    ///   asynchronous pipe request and its completion callback.
+    ///
+    /// ### Safety
+    ///
+    /// The pointer is only valid while the transfer is pending, and the OS
+    /// or nusb's event thread may access the memory it points to
+    /// concurrently. Any use must be consistent with how nusb and the OS use
+    /// the request: it must not be freed, resubmitted, or modified in a way
+    /// that changes how its completion is reported.
+    pub unsafe fn native_transfer_handle(&self, id: TransferId) -> Option<*mut c_void> {
+        let (index, _) = self.backend_index(id)?;
+        self.backend.native_handle(index)
+    }
+
+    /// Find a transfer's position in the backend queue, and the number of
+    /// backend entries it occupies.
+    fn backend_index(&self, id: TransferId) -> Option<(usize, usize)> {
+        split_backend_index(&self.splits, self.pending_ids(), id.0)
+    }
+
+    /// `TransferId`s of the pending transfers.
+    fn pending_ids(&self) -> Range<u64> {
+        self.submitted - self.pending() as u64..self.submitted
+    }
+
+    /// Set a scheduling hint for transfers on this endpoint.
+    ///
+    /// Use [`TransferPriority::Latency`] for command and response traffic and
+    /// [`TransferPriority::Throughput`] for deep streaming queues, so that
+    /// completions of the former are not delayed behind the latter when both
+    /// complete at the same time. Control transfers always use
+    /// `TransferPriority::Latency`.
+    ///
+    /// This applies to transfers submitted after the call.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Linux, completions reaped from the kernel together are delivered
+    ///   in priority order.
+    /// * On Windows and macOS, this currently has no effect.
+    pub fn set_priority(&mut self, priority: TransferPriority) {
+        self.backend.set_priority(priority)
+    }
+
+    /// Get the largest transfer the OS accepts on this endpoint.
+    ///
+    /// Bulk and Interrupt transfers larger than this are automatically split
+    /// into multiple transfers of at most this size, rounded down to a
+    /// multiple of the [maximum packet size][`Self::max_packet_size`], and
+    /// returned as a single [`Completion`]. The chunks transfer data directly to
+    /// or from the submitted buffer, without copying.
+    ///
+    /// Splitting an IN transfer relies on the remaining chunks being
+    /// cancelled by the OS when one ends with a short packet, which is only
+    /// supported for bulk endpoints on Linux. Elsewhere, IN transfers larger
+    /// than this fail with [`TransferError::InvalidArgument`].
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Linux, this is 4 MiB, as usbfs limits the total memory of all
+    ///   pending transfers (16 MiB by default).
+    /// * On Windows, this is WinUSB's `MAXIMUM_TRANSFER_SIZE` for IN
+    ///   endpoints, and unlimited for OUT endpoints, which WinUSB splits
+    ///   itself.
+    /// * On macOS, this is unlimited.
+    pub fn max_transfer_size(&self) -> usize {
+        self.backend.max_transfer_size
+    }
+
+    /// Length of a transfer using `buf`.
+    fn transfer_len(buf: &Buffer) -> usize {
+        match Dir::DIR {
+            Direction::Out => buf.len(),
+            Direction::In => buf.requested_len(),
+        }
+    }
+
+    /// Start collecting transfer statistics, or restart if already enabled.
+    ///
+    /// Collection adds a small overhead to each transfer, so it is disabled by
+    /// default. Use [`stats()`][`Self::stats`] to get the statistics.
+    pub fn enable_stats(&mut self) {
+        self.stats = Some(Box::new(StatsCollector::new(self.pending())));
+    }
+
+    /// Stop collecting transfer statistics.
+    pub fn disable_stats(&mut self) {
+        self.stats = None;
+    }
+
+    /// Get a snapshot of the statistics collected since
+    /// [`enable_stats()`][`Self::enable_stats`] was called, or `None` if
+    /// collection is not enabled.
+    pub fn stats(&self) -> Option<TransferStats> {
+        self.stats.as_ref().map(|s| s.snapshot())
+    }
+
+    /// Enable or disable recording timestamps of transfers.
+    ///
+    /// When enabled, each [`Completion`] includes the time at which the
+    /// transfer was submitted in [`submitted_at`][`Completion::submitted_at`]
+    /// and the time at which nusb handled its completion from the OS in
+    /// [`completed_at`][`Completion::completed_at`]. This allows measuring
+    /// latency and jitter in the host stack separately from delays in the
+    /// application. Timestamps are disabled by default.
+    ///
+    /// Only transfers submitted after this is called record timestamps.
+    /// This does not apply to isochronous transfers, whose completion time
+    /// is always recorded in `IsoCompletion::completed_at`.
+    pub fn set_timestamps(&mut self, enabled: bool) {
+        self.timestamps = enabled;
+        self.backend.set_timestamps(enabled);
+    }
+
+    /// Account for a transfer about to be added to the queue.
+    fn start_transfer(&mut self, buf: &Buffer) -> TransferId {
+        let id = TransferId(self.submitted);
+        self.submitted += 1;
+        self.meta.push_back(TransferMeta {
+            tag: 0,
+            submitted_at: self.timestamps.then(Instant::now),
+        });
+        self.pending_bytes += Self::transfer_len(buf);
+        if let Some(stats) = &mut self.stats {
+            stats.on_submit();
+        }
+        id
+    }
+
+    /// Account for a transfer removed from the queue, returning its metadata.
+    fn finish_transfer(&mut self, buf: &Buffer, actual_len: usize, ok: bool) -> TransferMeta {
+        self.pending_bytes = self.pending_bytes.saturating_sub(Self::transfer_len(buf));
+        let pending = self.pending();
+        if let Some(stats) = &mut self.stats {
+            stats.on_complete(actual_len, ok, pending);
+        }
+        self.meta.pop_front().unwrap_or_default()
+    }
+
+    /// Allocate a buffer for use on this endpoint, zero-copy if possible.
+    ///
+    /// A zero-copy buffer allows the kernel to DMA directly to/from this
+    /// buffer for improved performance. However, because it is not allocated
+    /// with the system allocator, it cannot be converted to a [`Vec`] without
+    /// copying.
+    ///
+    /// This is a somewhat expensive operation, requiring a `mmap` system call,
+    /// so is likely only beneficial for buffers that will be used repeatedly.
+    /// Consider using [`Buffer::new`] for one-off transfers.
+    ///
+    /// This is currently only supported on Linux, falling back to [`Buffer::new`]
+    /// on other platforms, or if the memory allocation fails.
+    pub fn allocate(&self, len: usize) -> Buffer {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            if let Ok(b) = self.backend.allocate(len) {
+                return b;
+            }
+        }
+
+        Buffer::new(len)
+    }
+
+    /// Get the alignment, in bytes, that the platform prefers for the start
+    /// of transfer buffers.
+    ///
+    /// Buffers from [`allocate()`][`Self::allocate`] already meet this
+    /// alignment where zero-copy transfers are supported. To prepare buffers
+    /// some other way, use [`Buffer::new_aligned`] with this alignment, and
+    /// make IN buffers' `requested_len` a multiple of the
+    /// [maximum packet size][`Self::max_packet_size`].
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Linux, this is the page size. Buffers memory-mapped from usbfs by
+    ///   `allocate` are page-aligned, and other buffers are always copied by
+    ///   the kernel regardless of their alignment.
+    /// * On Windows, this is the 4 KiB page size, so that WinUSB locks the
+    ///   fewest pages to transfer the buffer.
+    /// * On macOS, this is the page size: 16 KiB on Apple silicon and 4 KiB
+    ///   on Intel.
+    pub fn buffer_alignment(&self) -> usize {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            rustix::param::page_size()
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            4096
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            if cfg!(target_arch = "aarch64") {
+                16384
+            } else {
+                4096
+            }
+        }
+    }
+
+    /// Create a [`BufferPool`] of `count` buffers of `buffer_size` bytes,
+    /// allocated with [`allocate()`][`Self::allocate`].
+    ///
+    /// Buffers taken from the pool and returned with [`BufferPool::put`] after
+    /// their transfer completes are reused, so steady-state streaming does not
+    /// allocate. If more than `count` buffers are taken at once, additional
+    /// buffers are allocated with [`Buffer::new`] and are not zero-copy.
+    pub fn buffer_pool(&self, buffer_size: usize, count: usize) -> BufferPool {
+        let mut pool = BufferPool::new(buffer_size);
+        pool.extend((0..count).map(|_| self.allocate(buffer_size)));
+        pool
+    }
+}
+
+impl<EpType: BulkOrInterrupt> Endpoint<EpType, Out> {
+    /// Create an [`EndpointWrite`] wrapping the given endpoint to provide a
+    /// high-level buffered API implementing [`std::io::Write`] and async
+    /// equivalents.
+    ///
+    /// See [`EndpointWrite::new`][`crate::io::EndpointWrite::new`] for details.
+    pub fn writer(self, buffer_size: usize) -> EndpointWrite<EpType> {
+        EndpointWrite::new(self, buffer_size)
+    }
+
+    /// Automatically end transfers with a zero-length packet.
+    ///
+    /// When enabled, an OUT transfer whose length is a nonzero multiple of the
+    /// endpoint's [maximum packet size][`Self::max_packet_size`] is followed by
+    /// a zero-length packet, which many protocols use to mark the end of a
+    /// message. This is done by the OS as part of the same transfer, unlike
+    /// submitting a separate empty transfer. Disabled by default.
+    ///
+    /// This applies to transfers submitted after the call.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Linux, this sets `USBDEVFS_URB_ZERO_PACKET` on each transfer. For
+    ///   [`submit_vectored()`][`Self::submit_vectored`], it applies to the last
+    ///   buffer only.
+    /// * On Windows, this sets WinUSB's `SHORT_PACKET_TERMINATE` pipe policy.
+    /// * On macOS, this is not supported and returns an error with
+    ///   [`ErrorKind::Unsupported`]. Submit an empty buffer instead.
+    pub fn set_auto_zlp(&mut self, enable: bool) -> Result<(), Error> {
+        self.backend.set_auto_zlp(enable)
+    }
+
+    /// Create an [`EndpointSink`] wrapping the given endpoint to submit
+    /// transfers through the `futures` [`Sink`][`futures_sink::Sink`] trait.
+    ///
+    /// See [`EndpointSink::new`][`crate::io::EndpointSink::new`] for details.
+    pub fn sink(self) -> EndpointSink<EpType> {
+        EndpointSink::new(self)
+    }
+
+    /// Send all of `data`, blocking for up to `timeout`.
+    ///
+    /// The data is sent as one transfer, which is transparently split if it
+    /// exceeds [`max_transfer_size()`][`Self::max_transfer_size`]. No
+    /// zero-length packet is sent after data that is a multiple of the
+    /// maximum packet size unless enabled with
+    /// [`set_auto_zlp()`][`Self::set_auto_zlp`].
+    ///
+    /// On error, the returned [`PartialTransferError`] contains the number of
+    /// bytes sent before the error. If the timeout is reached, the transfer
+    /// is cancelled and the error is [`TransferError::TimedOut`].
+    ///
+    /// ## Panics
+    ///  * if other transfers are already pending on the endpoint.
+    pub fn write_all(
+        &mut self,
+        data: &[u8],
+        timeout: Duration,
+    ) -> Result<(), PartialTransferError> {
+        let mut buf = self.allocate(data.len());
+        buf.extend_from_slice(data);
+        let c = self.transfer_blocking(buf, timeout);
+        c.status.map_err(|error| PartialTransferError {
+            error,
+            transferred: c.actual_len,
+        })
+    }
+}
+
+impl<EpType: BulkOrInterrupt> Endpoint<EpType, In> {
+    /// Create an [`EndpointRead`] wrapping the given endpoint to provide a
+    /// high-level buffered API implementing [`std::io::Read`] and  async
+    /// equivalents.
+    ///
+    /// See [`EndpointRead::new`][`crate::io::EndpointRead::new`] for details.
+    pub fn reader(self, buffer_size: usize) -> EndpointRead<EpType> {
+        EndpointRead::new(self, buffer_size)
+    }
+
+    /// Create an [`EndpointStream`] wrapping the given endpoint to receive
+    /// completed transfers through the `futures`
+    /// [`Stream`][`futures_core::Stream`] trait.
+    ///
+    /// See [`EndpointStream::new`][`crate::io::EndpointStream::new`] for details.
+    pub fn stream(self, transfer_size: usize) -> EndpointStream<EpType> {
+        EndpointStream::new(self, transfer_size)
+    }
+
+    /// Receive exactly `buf.len()` bytes, blocking for up to `timeout`.
+    ///
+    /// This performs as many transfers as needed, continuing after short
+    /// and zero-length packets, until `buf` is filled. Each transfer requests
+    /// the remaining length rounded up to a multiple of the maximum packet
+    /// size, so if the device sends more data than needed in the final
+    /// packet, the extra bytes are discarded.
+    ///
+    /// On error, the returned [`PartialTransferError`] contains the number of
+    /// bytes received into the start of `buf` before the error. If the
+    /// timeout is reached, the transfer is cancelled and the error is
+    /// [`TransferError::TimedOut`].
+    ///
+    /// ## Panics
+    ///  * if other transfers are already pending on the endpoint.
+    pub fn read_exact(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<(), PartialTransferError> {
+        let deadline = Instant::now().checked_add(timeout);
+        let packet_size = self.max_packet_size();
+        let mut transfer = self.allocate(buf.len().div_ceil(packet_size).max(1) * packet_size);
+        let mut pos = 0;
+        while pos < buf.len() {
+            let remaining = buf.len() - pos;
+            transfer.clear();
+            transfer.set_requested_len(remaining.div_ceil(packet_size) * packet_size);
+
+            let timeout = deadline.map_or(timeout, |d| d.saturating_duration_since(Instant::now()));
+            let c = self.transfer_blocking(transfer, timeout);
+            let n = c.buffer.len().min(remaining);
+            buf[pos..pos + n].copy_from_slice(&c.buffer[..n]);
+            pos += n;
+            if let Err(error) = c.status {
+                return Err(PartialTransferError {
+                    error,
+                    transferred: pos,
+                });
+            }
+            transfer = c.buffer;
+        }
+        Ok(())
+    }
+
+    /// Set how a short packet completes IN transfers.
+    ///
+    /// With the default [`ShortPacketPolicy::Complete`], a transfer that
+    /// ends with a packet shorter than the endpoint's maximum packet size
+    /// completes successfully with the data received. With
+    /// [`ShortPacketPolicy::Error`], it completes with
+    /// [`TransferError::ShortPacket`] instead.
+    ///
+    /// The policy is applied as transfers are returned from `next_complete`,
+    /// so it also affects transfers already pending. For transfers submitted
+    /// with [`submit_vectored()`][`Self::submit_vectored`], the buffer that
+    /// received the short packet completes with the error, and the remaining
+    /// buffers are cancelled, so that no data after the short packet is
+    /// received into them. A transfer split because it exceeds
+    /// [`max_transfer_size()`][`Self::max_transfer_size`] always ends at a
+    /// short packet.
+    ///
+    /// [`EndpointRead`] relies on short packets to delimit messages, so the
+    /// policy should be left at the default when using it.
+    pub fn set_short_packet_policy(&mut self, policy: ShortPacketPolicy) {
+        self.short_packet = policy;
+    }
+
+    /// Get the policy set with
+    /// [`set_short_packet_policy()`][`Self::set_short_packet_policy`].
+    pub fn short_packet_policy(&self) -> ShortPacketPolicy {
+        self.short_packet
+    }
+
+    /// Keep `num_transfers` transfers of `transfer_size` bytes pending,
+    /// resubmitting automatically as completions are taken.
+    ///
+    /// Transfers are submitted immediately until `num_transfers` are pending.
+    /// Each time a completion is returned from `next_complete` or similar,
+    /// a new transfer is submitted in its place, so the endpoint always has
+    /// a buffer ready to receive data and none is dropped while the consumer
+    /// is handling the previous one.
+    ///
+    /// The buffer in each [`Completion`] belongs to the caller. Pass it back
+    /// with [`recycle()`][`Self::recycle`] once its data has been used to
+    /// resubmit it instead of allocating a new buffer for the next transfer.
+    ///
+    /// Auto-resubmit stops with [`stop_auto_resubmit()`][`Self::stop_auto_resubmit`],
+    /// or automatically when a transfer completes with
+    /// [`TransferError::Stall`], [`TransferError::Disconnected`], or
+    /// [`TransferError::Cancelled`], so that [`Self::cancel_all`] or a
+    /// stalled endpoint does not resubmit in a loop. Call this again to
+    /// resume after handling the error.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use nusb::transfer::{Bulk, In};
+    /// # fn example(mut ep: nusb::Endpoint<Bulk, In>) {
+    /// ep.set_auto_resubmit(8, 4096);
+    /// while let Some(c) = ep.wait_next_complete(Duration::from_secs(1)) {
+    ///     if c.status.is_err() {
+    ///         break;
+    ///     }
+    ///     println!("received {} bytes", c.buffer.len());
+    ///     ep.recycle(c.buffer);
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// ## Panics
+    ///  * if `num_transfers` is zero.
+    ///  * if `transfer_size` is not a multiple of the endpoint's maximum packet
+    ///    size.
+    pub fn set_auto_resubmit(&mut self, num_transfers: usize, transfer_size: usize) {
+        assert!(num_transfers > 0, "num_transfers must be greater than zero");
+        assert!(
+            transfer_size > 0 && transfer_size % self.max_packet_size() == 0,
+            "transfer_size must be a non-zero multiple of max_packet_size"
+        );
+        let pool = match self.auto_resubmit.take() {
+            Some(auto) if auto.pool.buffer_size() == transfer_size => auto.pool,
+            _ => BufferPool::new(transfer_size),
+        };
+        self.auto_resubmit = Some(AutoResubmit {
+            num_transfers,
+            pool,
+        });
+        self.refill_auto_resubmit();
+    }
+
+    /// Stop submitting transfers automatically.
+    ///
+    /// Transfers already pending are not cancelled, and can be waited for
+    /// with `next_complete` or cancelled with [`Self::cancel_all`].
+    pub fn stop_auto_resubmit(&mut self) {
+        self.auto_resubmit = None;
+    }
+
+    /// Returns `true` if auto-resubmit mode is enabled with
+    /// [`set_auto_resubmit()`][`Self::set_auto_resubmit`].
+    pub fn is_auto_resubmit(&self) -> bool {
+        self.auto_resubmit.is_some()
+    }
+
+    /// Return a completed buffer to be resubmitted by auto-resubmit mode.
+    ///
+    /// The buffer is used for a later transfer instead of allocating a new
+    /// one. If auto-resubmit is not enabled, or the buffer is smaller than the
+    /// transfer size, the buffer is dropped.
+    pub fn recycle(&mut self, buf: Buffer) {
+        if let Some(auto) = &mut self.auto_resubmit {
+            auto.pool.put(buf);
+        }
+    }
+}
+
+/// Methods for Bulk and Interrupt endpoints.
+impl<EpType: BulkOrInterrupt, Dir: EndpointDirection> Endpoint<EpType, Dir> {
+    /// Begin a transfer on the endpoint.
+    ///
+    /// Submitted transfers are queued and completed in order. Once the transfer
+    /// completes, it will be returned from
+    /// [`next_complete()`][`Self::next_complete`]. Any error in submitting or
+    /// performing the transfer is deferred until `next_complete`.
+    ///
+    /// For an OUT transfer, the buffer's `len` is the number of bytes
+    /// initialized, which will be sent to the device.
+    ///
+    /// For an IN transfer, the buffer's `requested_len` is the number of bytes
+    /// requested. It must be a nonzero multiple of the endpoint's [maximum
+    /// packet size][`Self::max_packet_size`] or the transfer will fail with
+    /// `TransferError::InvalidArgument`. Up to `requested_len /
+    /// max_packet_size` packets will be received, ending early when any packet
+    /// is shorter than `max_packet_size`.
+    ///
+    /// A buffer larger than [`max_transfer_size()`][`Self::max_transfer_size`]
+    /// is transparently split into multiple transfers and returned as one
+    /// [`Completion`]. An IN transfer still ends at the first short packet or
+    /// error, and any remaining chunks are cancelled. IN transfers can only
+    /// be split on Linux bulk endpoints, and otherwise fail with
+    /// `TransferError::InvalidArgument`.
+    ///
+    /// Returns a [`TransferId`] that can be passed to
+    /// [`cancel()`][`Self::cancel`] to cancel this transfer.
+    pub fn submit(&mut self, buf: Buffer) -> TransferId {
+        let id = self.start_transfer(&buf);
+        if let Some(buf) = self.check_submit(buf) {
+            self.submit_inner(id, buf, None);
+        }
+        id
+    }
+
+    /// Begin a transfer on the endpoint, attaching a tag that is returned in
+    /// its [`Completion`].
+    ///
+    /// This behaves like [`submit()`][`Self::submit`], and the tag is returned
+    /// in [`Completion::tag`]. The tag can identify the protocol frame or
+    /// request that the transfer belongs to, without keeping a separate queue
+    /// in sync with the endpoint's queue. Every submitted transfer is returned
+    /// exactly once, including those that fail, so tags always match their
+    /// transfers.
+    pub fn submit_tagged(&mut self, buf: Buffer, tag: u64) -> TransferId {
+        let id = self.start_transfer(&buf);
+        self.meta.back_mut().unwrap().tag = tag;
+        if let Some(buf) = self.check_submit(buf) {
+            self.submit_inner(id, buf, None);
+        }
+        id
+    }
+
+    /// Begin a transfer on the endpoint that is cancelled if it does not
+    /// complete within `timeout`.
+    ///
+    /// This behaves like [`submit()`][`Self::submit`], except that if the
+    /// transfer is still pending when `timeout` elapses, it is cancelled and
+    /// completes with [`TransferError::TimedOut`]. Unlike wrapping
+    /// `next_complete` in an external timer, the transfer's buffer and any data
+    /// received before the timeout are returned in the [`Completion`].
+    ///
+    /// The timer starts when the transfer is submitted, not when the transfers
+    /// queued before it complete.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Linux, the timeout is implemented with an internal timer.
+    /// * On Windows, the timeout is set using WinUSB's `PIPE_TRANSFER_TIMEOUT`
+    ///   pipe policy, which also applies to any transfers already pending on
+    ///   the endpoint. Avoid mixing transfers with different timeouts.
+    /// * On macOS, the timeout uses the native IOKit timeout for bulk
+    ///   endpoints. IOKit does not support timeouts on interrupt endpoints,
+    ///   where the timeout is ignored.
+    pub fn submit_with_timeout(&mut self, buf: Buffer, timeout: Duration) -> TransferId {
+        let id = self.start_transfer(&buf);
+        if let Some(buf) = self.check_submit(buf) {
+            self.submit_inner(id, buf, Some(timeout));
+        }
+        id
+    }
+
+    /// Submit a validated buffer, splitting it if it exceeds
+    /// [`max_transfer_size`][`Self::max_transfer_size`].
+    fn submit_inner(&mut self, id: TransferId, buf: Buffer, timeout: Option<Duration>) {
+        let len = Self::transfer_len(&buf);
+        let chunk_size = self.chunk_size();
+
+        if len <= chunk_size {
+            match timeout {
+                Some(timeout) => self.backend.submit_timeout(buf, timeout),
+                None => self.backend.submit(buf),
+            }
+            return;
+        }
+
+        if let Some(err) = self.split_error(&buf) {
+            self.backend.submit_err(buf, err);
+            return;
+        }
+
+        // Each chunk transfers directly to or from its part of `buf`, which is
+        // kept in `splits` and freed once all chunks have been dropped.
+        let mut buf = buf;
+        if Dir::DIR == Direction::In {
+            buf.clear();
+        }
+        let init_len = buf.len();
+        let buffer = Arc::new(buf);
+        let num_chunks = len.div_ceil(chunk_size);
+        for (i, offset) in (0..len).step_by(chunk_size).enumerate() {
+            let chunk_len = chunk_size.min(len - offset);
+            let init_len = match Dir::DIR {
+                Direction::Out => chunk_len,
+                Direction::In => 0,
+            };
+            // SAFETY: `offset + chunk_len` is within the buffer's capacity
+            // and the chunks don't overlap.
+            let mut chunk =
+                unsafe { Buffer::from_raw_parts(buffer.ptr.add(offset), init_len, chunk_len) };
+            chunk.allocator = Allocator::Chunk(buffer.clone());
+            let (first, last) = (i == 0, i == num_chunks - 1);
+            self.submit_chunk(chunk, first, last, timeout);
+        }
+
+        self.splits.push_back(SplitTransfer {
+            id: id.0,
+            remaining: num_chunks,
+            buffer,
+            len: init_len,
+            actual_len: 0,
+            status: Ok(()),
+            ended: false,
+        });
+    }
+
+    /// The error that a buffer is completed with if it would need to be split
+    /// into IN chunks that can't be submitted ahead of time.
+    ///
+    /// Without usbfs continuation URBs, a chunk queued after one that ends
+    /// with a short packet would receive the device's next message.
+    fn split_error(&self, buf: &Buffer) -> Option<TransferError> {
+        let continuation = cfg!(any(target_os = "linux", target_os = "android"))
+            && EpType::TYPE == TransferType::Bulk;
+        let len = Self::transfer_len(buf);
+        if Dir::DIR == Direction::In && !continuation && len > self.chunk_size() {
+            warn!(
+                "Submitting transfer with length {len} which exceeds max transfer size {} on IN endpoint {:02x}",
+                self.max_transfer_size(),
+                self.endpoint_address(),
+            );
+            return Some(TransferError::InvalidArgument);
+        }
+        None
+    }
+
+    /// Largest multiple of the max packet size that fits in one transfer.
+    fn chunk_size(&self) -> usize {
+        let mps = self.max_packet_size();
+        (self.max_transfer_size() / mps).max(1) * mps
+    }
+
+    /// Submit one buffer of a transfer split across multiple buffers.
+    #[cfg_attr(
+        not(any(target_os = "linux", target_os = "android")),
+        allow(unused_variables)
+    )]
+    fn submit_chunk(&mut self, buf: Buffer, first: bool, last: bool, timeout: Option<Duration>) {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if EpType::TYPE == crate::descriptors::TransferType::Bulk {
+            self.backend
+                .submit_bulk_continuation(buf, first, last, timeout);
+            return;
+        }
+
+        match timeout {
+            Some(timeout) => self.backend.submit_timeout(buf, timeout),
+            None => self.backend.submit(buf),
+        }
+    }
+
+    /// Begin a single logical transfer split across multiple buffers.
+    ///
+    /// Each buffer is submitted as a separate transfer and returned separately
+    /// from [`next_complete()`][`Self::next_complete`], in order. This allows
+    /// a large transfer to be sent from or received into multiple buffers
+    /// without assembling it into one contiguous allocation. Each IN buffer's
+    /// `requested_len` must be a nonzero multiple of the endpoint's maximum
+    /// packet size, as with [`submit()`][`Self::submit`].
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Linux, the buffers of a bulk transfer are submitted as usbfs
+    ///   continuation URBs. If a short packet is received or an error occurs,
+    ///   the transfer ends: the buffer that received the short packet
+    ///   completes successfully and the remaining buffers complete with
+    ///   [`TransferError::Cancelled`] and no data.
+    /// * On other platforms, and for interrupt endpoints, the buffers are
+    ///   submitted as independent transfers, so receiving into subsequent
+    ///   buffers continues after a short packet, unless the
+    ///   [short packet policy][`Self::set_short_packet_policy`] is
+    ///   [`ShortPacketPolicy::Error`], in which case they are cancelled when
+    ///   the short buffer is returned from `next_complete`. On macOS, which
+    ///   can't cancel individual transfers, they are not cancelled.
+    ///
+    /// Unlike `submit`, buffers larger than
+    /// [`max_transfer_size()`][`Self::max_transfer_size`] are not split.
+    pub fn submit_vectored(&mut self, bufs: impl IntoIterator<Item = Buffer>) {
+        let mut bufs = bufs.into_iter().peekable();
+        let start = self.submitted;
+        let mut first = true;
+        while let Some(buf) = bufs.next() {
+            let last = bufs.peek().is_none();
+            self.set_no_interrupt(!last);
+            self.start_transfer(&buf);
+            if let Some(buf) = self.check_submit(buf) {
+                self.submit_chunk(buf, first, last, None);
+            }
+            first = false;
+        }
+        self.set_no_interrupt(false);
+        if Dir::DIR == Direction::In && self.submitted - start > 1 {
+            self.vectored.push_back(start..self.submitted);
+        }
+    }
+
+    /// Begin multiple independent transfers on the endpoint.
+    ///
+    /// Each buffer is submitted as with [`submit()`][`Self::submit`] and
+    /// returned separately from [`next_complete()`][`Self::next_complete`], in
+    /// order. Unlike [`submit_vectored()`][`Self::submit_vectored`], a short
+    /// packet in one transfer does not affect the others. This is intended for
+    /// refilling a deep queue of IN transfers, for example after a stall or
+    /// [`pause()`][`Self::pause`]: the endpoint's queue is grown once for the
+    /// whole batch, and with [`set_batch_interrupts()`][`Self::set_batch_interrupts`]
+    /// only the last transfer of the batch requests an interrupt.
+    ///
+    /// Returns the number of transfers accepted by the OS. Every buffer is
+    /// still returned from `next_complete`, and those that were rejected
+    /// complete with an error. Buffers larger than
+    /// [`max_transfer_size()`][`Self::max_transfer_size`] are split as with
+    /// `submit`, and are counted as accepted.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Linux, usbfs has no call to submit several URBs at once, so each
+    ///   transfer is still one `USBDEVFS_SUBMITURB` ioctl.
+    pub fn submit_all(&mut self, bufs: impl IntoIterator<Item = Buffer>) -> usize {
+        let chunk_size = self.chunk_size();
+        let mut batch = Vec::new();
+        let mut accepted = 0;
+        for buf in bufs {
+            let id = self.start_transfer(&buf);
+            let rejection = self.submit_error(&buf).or_else(|| self.split_error(&buf));
+            if rejection.is_none() && Self::transfer_len(&buf) <= chunk_size {
+                batch.push(buf);
+                continue;
+            }
+
+            // Submit the batch so far to keep completions in order.
+            if !batch.is_empty() {
+                accepted += self
+                    .backend
+                    .submit_batch(std::mem::take(&mut batch), self.batch_interrupts);
+            }
+            match rejection {
+                Some(err) => self.backend.submit_err(buf, err),
+                None => {
+                    self.submit_inner(id, buf, None);
+                    accepted += 1;
+                }
+            }
+        }
+        if !batch.is_empty() {
+            accepted += self.backend.submit_batch(batch, self.batch_interrupts);
+        }
+        accepted
+    }
+
+    /// Request an interrupt only for the final transfer of each burst.
+    ///
+    /// When enabled, all but the last transfer submitted by one call to
+    /// [`submit_vectored()`][`Self::submit_vectored`] or
+    /// [`submit_all()`][`Self::submit_all`] are flagged as not
+    /// needing an interrupt on completion. The host controller may then
+    /// report the earlier completions together with the last one, reducing
+    /// CPU use when keeping a deep queue of transfers at high request rates.
+    /// Completions are still returned in order, but may be delayed until the
+    /// burst completes.
+    ///
+    /// This is only a hint, and is disabled by default. It does not affect
+    /// transfers submitted individually with [`submit()`][`Self::submit`].
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Linux, this sets `USBDEVFS_URB_NO_INTERRUPT`. Host controller
+    ///   drivers may ignore it.
+    /// * On Windows and macOS, this has no effect.
+    pub fn set_batch_interrupts(&mut self, enable: bool) {
+        self.batch_interrupts = enable;
+    }
+
+    /// Set the limit on pending transfers applied by
+    /// [`submit_when_ready()`][`Self::submit_when_ready`].
+    ///
+    /// `None` (default) means no limit. This does not affect other methods of
+    /// submitting transfers.
+    ///
+    /// ### Panics
+    /// * If the limit is `Some(0)`.
+    pub fn set_max_pending(&mut self, limit: Option<usize>) {
+        assert!(limit != Some(0), "max_pending must be greater than zero");
+        self.max_pending = limit;
+    }
+
+    /// Get the limit set with [`set_max_pending()`][`Self::set_max_pending`].
+    pub fn max_pending(&self) -> Option<usize> {
+        self.max_pending
+    }
+
+    /// Return a `Future` that submits a transfer once fewer than
+    /// [`max_pending`][`Self::set_max_pending`] transfers are pending.
+    ///
+    /// This applies backpressure to a producer so that memory use stays
+    /// bounded when the device is slower than the producer. If the limit is
+    /// reached, the future waits for the next transfer to complete, submits
+    /// `buf`, and resolves to the completion of the transfer it waited for,
+    /// which must be handled like a completion from
+    /// [`next_complete()`][`Self::next_complete`]. Otherwise, `buf` is
+    /// submitted immediately and the future resolves to `None`.
+    ///
+    /// If the future is dropped before it resolves, `buf` is dropped without
+    /// being submitted, and no completion is lost.
+    ///
+    /// ```no_run
+    /// use nusb::transfer::{Buffer, Bulk, Out};
+    /// # async fn example(mut ep: nusb::Endpoint<Bulk, Out>, chunks: Vec<Vec<u8>>) {
+    /// ep.set_max_pending(Some(4));
+    /// for chunk in chunks {
+    ///     if let Some(c) = ep.submit_when_ready(chunk.into()).await {
+    ///         c.status.expect("transfer failed");
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn submit_when_ready(
+        &mut self,
+        buf: Buffer,
+    ) -> impl Future<Output = Option<Completion>> + Send + Sync + '_ {
+        let mut buf = Some(buf);
+        poll_fn(move |cx| {
+            let c = match self.max_pending {
+                Some(limit) if self.pending() >= limit => Some(ready!(self.poll_next_complete(cx))),
+                _ => None,
+            };
+            self.submit(buf.take().expect("polled after completion"));
+            Poll::Ready(c)
+        })
+    }
+
+    fn set_no_interrupt(&mut self, no_interrupt: bool) {
+        if self.batch_interrupts {
+            self.backend.set_no_interrupt(no_interrupt);
+        }
+    }
+
+    /// Validate a buffer before submission, completing it with an error and
+    /// returning `None` if it is invalid.
+    fn check_submit(&mut self, buf: Buffer) -> Option<Buffer> {
+        match self.submit_error(&buf) {
+            Some(err) => {
+                self.backend.submit_err(buf, err);
+                None
+            }
+            None => Some(buf),
+        }
+    }
+
+    /// The error that a buffer would be completed with instead of being
+    /// submitted, if any.
+    fn submit_error(&self, buf: &Buffer) -> Option<TransferError> {
+        if let Some(err) = submit_rejection(self.cancel_requested, self.paused) {
+            return Some(err);
+        }
+
+        if Dir::DIR == Direction::In {
+            let req_len = buf.requested_len();
+            if req_len == 0 || req_len % self.max_packet_size() != 0 {
+                warn!(
+                    "Submitting transfer with length {req_len} which is not a multiple of max packet size {} on IN endpoint {:02x}",
+                    self.max_packet_size(),
+                    self.endpoint_address(),
+                );
+                return Some(TransferError::InvalidArgument);
+            }
+        }
+
+        None
+    }
+
+    /// Return a `Future` that waits for the next pending transfer to complete.
     ///
-    /// This returns cached data and does not perform IO.
-    pub fn descriptors(&self) -> impl Iterator<Item = InterfaceDescriptor> {
-        let active = self.backend.device.active_configuration_value();
+    /// This future is cancel-safe: it can be cancelled and re-created without
+    /// side effects, enabling its use in `select!{}` or similar. Dropping it
+    /// does not cancel or lose the transfer, which stays queued on the
+    /// endpoint. Call `next_complete` again to retrieve its buffer, or
+    /// [`cancel_all`][`Self::cancel_all`] first to stop it early and recover
+    /// any partial data.
+    ///
+    /// ```no_run
+    /// use futures_lite::future;
+    /// use nusb::transfer::{Buffer, Bulk, In};
+    /// # async fn example(mut ep: nusb::Endpoint<Bulk, In>, stop: impl std::future::Future<Output = ()>) {
+    /// ep.submit(Buffer::new(4096));
+    ///
+    /// let completion = future::or(async { Some(ep.next_complete().await) }, async {
+    ///     stop.await;
+    ///     None
+    /// })
+    /// .await;
+    ///
+    /// let completion = match completion {
+    ///     Some(c) => c,
+    ///     None => {
+    ///         // The transfer is still pending. Cancel it and collect the buffer.
+    ///         ep.cancel_all();
+    ///         ep.next_complete().await
+    ///     }
+    /// };
+    /// println!("received {:?}", &completion.buffer[..]);
+    /// # }
+    /// ```
+    ///
+    /// An OUT transfer completes when the specified data has been sent or an
+    /// error occurs. An IN transfer completes when a packet smaller than
+    /// `max_packet_size` is received, the full `requested_len` is received
+    /// (without waiting for or consuming any subsequent zero-length packet), or
+    /// an error occurs.
+    ///
+    /// ## Panics
+    /// * if there are no transfers pending (that is, if [`Self::pending()`]
+    ///   would return 0).
+    pub fn next_complete(&mut self) -> impl Future<Output = Completion> + Send + Sync + '_ {
+        poll_fn(|cx| self.poll_next_complete(cx))
+    }
 
-        let configuration = self
-            .backend
-            .device
-            .configuration_descriptors()
-            .find(|c| c.configuration_value() == active);
+    /// Return a `Future` that waits up to `timeout` for the next pending
+    /// transfer to complete.
+    ///
+    /// Resolves to `None` if the timeout is reached. The transfer is not
+    /// cancelled after the timeout, and can still be returned from a
+    /// subsequent call. Like [`next_complete`][`Self::next_complete`], this
+    /// future is cancel-safe.
+    ///
+    /// The timeout uses a timer thread internal to nusb, so it works with
+    /// any async runtime.
+    ///
+    /// ## Panics
+    /// * if there are no transfers pending (that is, if [`Self::pending()`]
+    ///   would return 0).
+    pub fn next_complete_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> impl Future<Output = Option<Completion>> + Send + Sync + '_ {
+        with_timeout(timeout, move |cx| self.poll_next_complete(cx))
+    }
 
-        configuration
-            .into_iter()
-            .flat_map(|i| i.interface_alt_settings())
-            .filter(|g| g.interface_number() == self.backend.interface_number)
+    /// Poll for a pending transfer completion.
+    ///
+    /// Returns a completed transfer if one is available, or arranges for the
+    /// context's waker to be notified when a transfer completes.
+    ///
+    /// Transfers queued by a [`Submitter`] are submitted first. If a
+    /// `Submitter` has been created, this returns `Poll::Pending` instead of
+    /// panicking when no transfers are pending, and the waker is also
+    /// notified when a transfer is queued.
+    ///
+    /// ## Panics
+    ///  * if there are no transfers pending (that is, if [`Self::pending()`]
+    ///    would return 0) and no `Submitter` has been created.
+    pub fn poll_next_complete(&mut self, cx: &mut Context<'_>) -> Poll<Completion> {
+        self.poll_cancel_signal(cx);
+        if self.paused && self.submit_queue.is_some() && self.pending() == 0 {
+            return Poll::Pending;
+        }
+        if let Some(rx) = self.submit_queue.as_ref().filter(|_| !self.paused) {
+            let queued = rx.take(Some(cx.waker()));
+            self.submit_queued(queued);
+            if self.pending() == 0 {
+                return Poll::Pending;
+            }
+        }
+        let mut c = if self.next_is_split() {
+            loop {
+                let chunk = ready!(self.backend.poll_next_complete(cx));
+                if let Some(c) = self.merge_chunk(chunk) {
+                    break c;
+                }
+            }
+        } else {
+            ready!(self.backend.poll_next_complete(cx))
+        };
+        self.finish_completion(&mut c);
+        Poll::Ready(c)
     }
 
-    /// Get the interface descriptor for the current alternate setting.
-    pub fn descriptor(&self) -> Option<InterfaceDescriptor> {
-        self.descriptors()
-            .find(|i| i.alternate_setting() == self.get_alt_setting())
+    /// Wait for a pending transfer completion.
+    ///
+    /// Blocks for up to `timeout` waiting for a transfer to complete, or
+    /// returns `None` if the timeout is reached.
+    ///
+    /// Note that the transfer is not cancelled after the timeout, and can still
+    /// be returned from a subsequent call.
+    ///
+    /// Transfers queued by a [`Submitter`] are submitted before blocking. If
+    /// none are pending, this waits for one to be queued. Transfers queued
+    /// while this is blocked waiting for a completion are not submitted until
+    /// the next call.
+    ///
+    /// ## Panics
+    ///  * if there are no transfers pending (that is, if [`Self::pending()`]
+    ///    would return 0) and no `Submitter` has been created.
+    pub fn wait_next_complete(&mut self, mut timeout: Duration) -> Option<Completion> {
+        if self.cancel_signal.is_some() {
+            block_on_timeout(Duration::ZERO, |cx| {
+                self.poll_cancel_signal(cx);
+                Poll::<()>::Pending
+            });
+        }
+        if self.paused && self.submit_queue.is_some() && self.pending() == 0 {
+            return None;
+        }
+        if let Some(rx) = self.submit_queue.as_ref().filter(|_| !self.paused) {
+            let start = Instant::now();
+            let mut queued = rx.take(None);
+            if queued.is_empty() && self.pending() == 0 {
+                queued = rx.wait(timeout);
+            }
+            self.submit_queued(queued);
+            if self.pending() == 0 {
+                return None;
+            }
+            timeout = timeout.saturating_sub(start.elapsed());
+        }
+        let mut c = if self.next_is_split() {
+            let deadline = Instant::now().checked_add(timeout);
+            loop {
+                let timeout =
+                    deadline.map_or(timeout, |d| d.saturating_duration_since(Instant::now()));
+                let chunk = self.backend.wait_next_complete(timeout)?;
+                if let Some(c) = self.merge_chunk(chunk) {
+                    break c;
+                }
+            }
+        } else {
+            self.backend.wait_next_complete(timeout)?
+        };
+        self.finish_completion(&mut c);
+        Some(c)
     }
 
-    /// Open an endpoint.
+    /// Return a `Future` that waits for the next pending transfer to complete,
+    /// and then collects up to `max` completed transfers into `out`.
     ///
-    /// This claims exclusive access to the endpoint and returns an [`Endpoint`]
-    /// that can be used to submit transfers. The type-level `EndpointType` and
-    /// `EndpointDirection` parameters must match the endpoint type and
-    /// direction.
-    pub fn endpoint<EpType: EndpointType, Dir: EndpointDirection>(
-        &self,
-        address: u8,
-    ) -> Result<Endpoint<EpType, Dir>, Error> {
-        let intf_desc = self.descriptor();
-        let ep_desc =
-            intf_desc.and_then(|desc| desc.endpoints().find(|ep| ep.address() == address));
-        let Some(ep_desc) = ep_desc else {
-            return Err(Error::new(
-                ErrorKind::NotFound,
-                "specified endpoint does not exist on this interface",
-            ));
+    /// Resolves to the number of completions appended to `out`, which is at
+    /// least 1 unless `max` is 0. This is equivalent to calling
+    /// [`next_complete()`][`Self::next_complete`] followed by taking the
+    /// transfers counted by [`ready()`][`Self::ready`], but with a single
+    /// wakeup, which reduces overhead when streaming at high data rates.
+    ///
+    /// Like `next_complete`, this future is cancel-safe.
+    ///
+    /// ## Panics
+    /// * if there are no transfers pending (that is, if [`Self::pending()`]
+    ///   would return 0).
+    pub fn next_completes<'a>(
+        &'a mut self,
+        max: usize,
+        out: &'a mut Vec<Completion>,
+    ) -> impl Future<Output = usize> + Send + Sync + 'a {
+        poll_fn(move |cx| self.poll_next_completes(cx, max, out))
+    }
+
+    /// Poll for pending transfer completions, collecting up to `max` completed
+    /// transfers into `out`.
+    ///
+    /// Returns the number of completions appended to `out` if at least one is
+    /// available, or arranges for the context's waker to be notified when a
+    /// transfer completes.
+    ///
+    /// ## Panics
+    ///  * if there are no transfers pending (that is, if [`Self::pending()`]
+    ///    would return 0).
+    pub fn poll_next_completes(
+        &mut self,
+        cx: &mut Context<'_>,
+        max: usize,
+        out: &mut Vec<Completion>,
+    ) -> Poll<usize> {
+        if max == 0 {
+            return Poll::Ready(0);
+        }
+        out.push(ready!(self.poll_next_complete(cx)));
+        let mut n = 1;
+        while n < max && self.ready() > 0 {
+            let Poll::Ready(c) = self.poll_next_complete(cx) else {
+                break;
+            };
+            out.push(c);
+            n += 1;
+        }
+        Poll::Ready(n)
+    }
+
+    /// Wait for pending transfer completions, collecting up to `max` completed
+    /// transfers into `out`.
+    ///
+    /// Blocks for up to `timeout` waiting for a transfer to complete, and then
+    /// takes any other transfers that have already completed without waiting
+    /// further. Returns the number of completions appended to `out`, which is
+    /// 0 if the timeout is reached.
+    ///
+    /// ## Panics
+    ///  * if there are no transfers pending (that is, if [`Self::pending()`]
+    ///    would return 0).
+    pub fn wait_next_completes(
+        &mut self,
+        max: usize,
+        timeout: Duration,
+        out: &mut Vec<Completion>,
+    ) -> usize {
+        if max == 0 {
+            return 0;
+        }
+        let Some(c) = self.wait_next_complete(timeout) else {
+            return 0;
         };
+        out.push(c);
+        let mut n = 1;
+        while n < max && self.ready() > 0 {
+            let Some(c) = self.wait_next_complete(Duration::ZERO) else {
+                break;
+            };
+            out.push(c);
+            n += 1;
+        }
+        n
+    }
 
-        if address & Direction::MASK != Dir::DIR as u8 {
-            return Err(Error::new(ErrorKind::Other, "incorrect endpoint direction"));
+    /// Whether the next transfer to complete was split into chunks.
+    fn next_is_split(&self) -> bool {
+        let next = self.submitted - self.pending() as u64;
+        self.splits.front().is_some_and(|s| s.id == next)
+    }
+
+    /// Add a completed chunk to the split transfer at the front of the queue,
+    /// returning the transfer's completion once all of its chunks are done.
+    fn merge_chunk(&mut self, chunk: Completion) -> Option<Completion> {
+        let s = self.splits.front_mut().unwrap();
+        if s.add_chunk(&chunk, Dir::DIR) {
+            for i in (0..s.remaining).rev() {
+                self.backend.cancel(i);
+            }
+        }
+
+        if s.remaining > 0 {
+            return None;
+        }
+
+        let s = self.splits.pop_front().unwrap();
+        let completed_at = chunk.completed_at;
+        drop(chunk);
+        let mut buffer = Arc::into_inner(s.buffer).expect("split chunk buffer still in use");
+        buffer.len = s.len as u32;
+        Some(Completion {
+            buffer,
+            actual_len: s.actual_len,
+            status: s.status,
+            tag: 0,
+            endpoint: 0,
+            submitted_at: None,
+            completed_at,
+        })
+    }
+
+    /// Account for a completed transfer and apply the short packet policy.
+    fn finish_completion(&mut self, c: &mut Completion) {
+        if Dir::DIR == Direction::In
+            && self.short_packet == ShortPacketPolicy::Error
+            && c.status.is_ok()
+            && c.buffer.len() < c.buffer.requested_len()
+        {
+            c.status = Err(TransferError::ShortPacket);
+        }
+        if !self.vectored.is_empty() {
+            let id = self.submitted - self.pending() as u64 - 1;
+            for i in end_vectored(&mut self.vectored, id, c.status.is_err()).rev() {
+                self.cancel(TransferId(i));
+            }
+        }
+        let meta = self.finish_transfer(&c.buffer, c.actual_len, c.status.is_ok());
+        c.tag = meta.tag;
+        c.endpoint = self.endpoint_address();
+        c.submitted_at = meta.submitted_at;
+        if !self.timestamps {
+            c.completed_at = None;
+        }
+        if self.auto_resubmit.is_some() {
+            if auto_resubmit_stops(&c.status, self.paused) {
+                self.auto_resubmit = None;
+            } else {
+                self.refill_auto_resubmit();
+            }
+        }
+    }
+
+    /// Submit transfers in auto-resubmit mode until enough are pending.
+    fn refill_auto_resubmit(&mut self) {
+        let Some(mut auto) = self.auto_resubmit.take() else {
+            return;
+        };
+        let count = auto_resubmit_count(auto.num_transfers, self.pending(), self.paused);
+        for _ in 0..count {
+            let buf = auto
+                .pool
+                .take()
+                .unwrap_or_else(|| self.allocate(auto.pool.buffer_size()));
+            self.submit(buf);
+        }
+        self.auto_resubmit = Some(auto);
+    }
+
+    /// Perform a single transfer, blocking until it completes.
+    ///
+    /// This submits `buf` as with [`submit()`][`Self::submit`] and waits up to
+    /// `timeout` for it to complete. If the timeout is reached, the transfer
+    /// is cancelled and its completion is returned with
+    /// [`TransferError::TimedOut`], including any data transferred before it
+    /// was cancelled.
+    ///
+    /// This is a convenience for programs that do not use async and perform
+    /// one transfer at a time. To keep multiple transfers in flight, use
+    /// `submit` and [`wait_next_complete()`][`Self::wait_next_complete`].
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use nusb::transfer::{Buffer, Bulk, In};
+    /// # fn example(mut ep: nusb::Endpoint<Bulk, In>) {
+    /// let c = ep.transfer_blocking(Buffer::new(512), Duration::from_secs(1));
+    /// match c.status {
+    ///     Ok(()) => println!("received {:?}", &c.buffer[..]),
+    ///     Err(e) => println!("transfer failed: {e}"),
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// ## Panics
+    ///  * if other transfers are already pending on the endpoint.
+    pub fn transfer_blocking(&mut self, buf: Buffer, timeout: Duration) -> Completion {
+        assert_eq!(
+            self.pending(),
+            0,
+            "transfer_blocking called with transfers pending"
+        );
+        self.submit(buf);
+
+        if let Some(c) = self.wait_next_complete(timeout) {
+            return c;
+        }
+
+        self.cancel_all();
+        let mut c = self
+            .wait_next_complete(Duration::MAX)
+            .expect("transfer should complete after cancellation");
+        if c.status == Err(TransferError::Cancelled) {
+            c.status = Err(TransferError::TimedOut);
+        }
+        c
+    }
+
+    /// Wait for all pending transfers to complete, cancelling any that have
+    /// not completed by `deadline`.
+    ///
+    /// Blocks until every pending transfer has been returned, and returns all
+    /// of their completions in order. Transfers cancelled at the deadline are
+    /// returned with [`TransferError::Cancelled`] and include any data
+    /// transferred before they were cancelled.
+    ///
+    /// This is intended for shutting down a streaming pipeline: stop
+    /// submitting new transfers, then call `drain` to collect the data still
+    /// in flight. Since it borrows the endpoint mutably, no transfers can be
+    /// submitted until it returns.
+    pub fn drain(&mut self, deadline: Instant) -> Vec<Completion> {
+        let mut out = Vec::with_capacity(self.pending());
+        while self.pending() > 0 {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let Some(c) = self.wait_next_complete(timeout) else {
+                break;
+            };
+            out.push(c);
+        }
+
+        if self.pending() > 0 {
+            self.cancel_all();
+            while self.pending() > 0 {
+                out.push(
+                    self.wait_next_complete(Duration::MAX)
+                        .expect("transfer should complete after cancellation"),
+                );
+            }
+        }
+        out
+    }
+
+    /// Cancel the next pending transfer and wait for its completion.
+    ///
+    /// This cancels the transfer that [`next_complete()`][`Self::next_complete`]
+    /// would return next, and returns a `Future` that resolves to its
+    /// [`Completion`]. Unlike dropping a `next_complete` future, which leaves
+    /// the transfer running, this stops the transfer and recovers its buffer
+    /// and any data transferred before it was cancelled. If the transfer
+    /// completed before it could be cancelled, it is returned with its
+    /// original status instead of [`TransferError::Cancelled`].
+    ///
+    /// Other pending transfers are not affected, except as noted below.
+    ///
+    /// ```no_run
+    /// use futures_lite::future;
+    /// use nusb::transfer::{Buffer, Bulk, In};
+    /// # async fn example(mut ep: nusb::Endpoint<Bulk, In>, stop: impl std::future::Future<Output = ()>) {
+    /// ep.submit(Buffer::new(4096));
+    /// let c = match future::or(async { Some(ep.next_complete().await) }, async {
+    ///     stop.await;
+    ///     None
+    /// })
+    /// .await
+    /// {
+    ///     Some(c) => c,
+    ///     None => ep.cancel_next().await,
+    /// };
+    /// println!("received {} bytes", c.buffer.len());
+    /// # }
+    /// ```
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On macOS, IOKit can only cancel all transfers on an endpoint, so all
+    ///   pending transfers are cancelled, as with
+    ///   [`cancel_all()`][`Self::cancel_all`].
+    ///
+    /// ## Panics
+    /// * if there are no transfers pending (that is, if [`Self::pending()`]
+    ///   would return 0).
+    pub fn cancel_next(&mut self) -> impl Future<Output = Completion> + Send + Sync + '_ {
+        self.cancel_front();
+        self.next_complete()
+    }
+
+    /// Cancel the next pending transfer and block until it completes.
+    ///
+    /// This is the blocking equivalent of
+    /// [`cancel_next()`][`Self::cancel_next`].
+    ///
+    /// ## Panics
+    /// * if there are no transfers pending (that is, if [`Self::pending()`]
+    ///   would return 0).
+    pub fn cancel_next_blocking(&mut self) -> Completion {
+        self.cancel_front();
+        self.wait_next_complete(Duration::MAX)
+            .expect("transfer should complete after cancellation")
+    }
+
+    /// Request cancellation of the transfer at the front of the queue.
+    fn cancel_front(&mut self) {
+        assert!(self.pending() > 0, "no transfers pending");
+        let id = TransferId(self.submitted - self.pending() as u64);
+        if !self.cancel(id) && cfg!(target_os = "macos") {
+            self.cancel_all();
+        }
+    }
+
+    /// Clear the endpoint's halt / stall condition.
+    ///
+    /// Sends a `CLEAR_FEATURE` `ENDPOINT_HALT` control transfer to tell the
+    /// device to reset the endpoint's data toggle and clear the halt / stall
+    /// condition, and resets the host-side data toggle.
+    ///
+    /// Use this after receiving
+    /// [`TransferError::Stall`][crate::transfer::TransferError::Stall] to clear
+    /// the error and resume use of the endpoint.
+    ///
+    /// This should not be called when transfers are pending on the endpoint.
+    pub fn clear_halt(&mut self) -> impl MaybeFuture<Output = Result<(), Error>> {
+        self.backend.clear_halt()
+    }
+
+    /// Query whether the endpoint is halted.
+    ///
+    /// This sends a standard `GET_STATUS` request to the endpoint and returns
+    /// the state of its `ENDPOINT_HALT` feature. Use this after an error to
+    /// check whether [`clear_halt()`][`Self::clear_halt`] is needed.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Windows, the request is sent through the endpoint's interface,
+    ///   and `timeout` is ignored in favor of WinUSB's fixed control
+    ///   transfer timeout.
+    pub fn is_halted(
+        &self,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Result<bool, TransferError>> {
+        use crate::transfer::{ControlType, Recipient};
+        const GET_STATUS: u8 = 0x00;
+        self.backend
+            .interface()
+            .clone()
+            .control_in(
+                ControlIn {
+                    control_type: ControlType::Standard,
+                    recipient: Recipient::Endpoint,
+                    request: GET_STATUS,
+                    value: 0,
+                    index: self.endpoint_address() as u16,
+                    length: 2,
+                },
+                timeout,
+            )
+            .map(|res| match res?.first() {
+                Some(status) => Ok(status & 0x01 != 0),
+                None => Err(TransferError::ShortPacket),
+            })
+    }
+
+    /// Get a [`Submitter`] handle for submitting transfers on this endpoint
+    /// from other threads or tasks.
+    ///
+    /// All `Submitter`s created for an endpoint share one queue, and fail to
+    /// submit once the endpoint is dropped. See [`Submitter`] for how queued
+    /// transfers are submitted and ordered.
+    pub fn submitter(&mut self) -> Submitter {
+        self.submit_queue
+            .get_or_insert_with(SubmitReceiver::new)
+            .submitter()
+    }
+
+    /// Submit buffers queued by a `Submitter`.
+    fn submit_queued(&mut self, queued: VecDeque<(Buffer, u64)>) {
+        for (buf, tag) in queued {
+            self.submit_tagged(buf, tag);
         }
+    }
+
+    /// Cancel the endpoint's transfers when an external signal fires.
+    ///
+    /// Once `signal` fires, all pending transfers are cancelled as with
+    /// [`cancel_all()`][`Self::cancel_all`] and complete with
+    /// [`TransferError::Cancelled`], and any transfers submitted afterwards
+    /// complete immediately with `TransferError::Cancelled`. This allows one
+    /// signal, such as a shutdown token, to stop several endpoints.
+    ///
+    /// The signal is observed by
+    /// [`poll_next_complete()`][`Self::poll_next_complete`] and the futures
+    /// built on it, so it must be awaited through one of them. The blocking
+    /// `wait_next_complete` only checks whether the signal has already
+    /// fired.
+    ///
+    /// Setting a new signal replaces the previous one, and re-enables
+    /// submission if the previous signal had fired.
+    ///
+    /// ```no_run
+    /// use nusb::transfer::{Buffer, Bulk, In};
+    /// # async fn example(mut ep: nusb::Endpoint<Bulk, In>, token: std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send>>) {
+    /// // e.g. `Box::pin(cancellation_token.cancelled_owned())`
+    /// ep.set_cancel_signal(token);
+    /// ep.submit(Buffer::new(4096));
+    /// let c = ep.next_complete().await;
+    /// # }
+    /// ```
+    pub fn set_cancel_signal(&mut self, signal: impl CancelSignal + 'static) {
+        self.cancel_signal = Some(Mutex::new(Box::new(signal)));
+        self.cancel_requested = false;
+    }
+
+    /// Returns `true` if the signal set with
+    /// [`set_cancel_signal()`][`Self::set_cancel_signal`] has fired.
+    pub fn is_cancel_requested(&self) -> bool {
+        self.cancel_requested
+    }
+
+    /// Deliver completions to a callback instead of waiting for them with
+    /// `next_complete`.
+    ///
+    /// `callback` is invoked with each completion, in order, usually on
+    /// nusb's event thread. It is passed the endpoint to allow resubmitting
+    /// transfers from the callback. See [`CallbackEndpoint`] for the
+    /// restrictions on what the callback may do.
+    ///
+    /// ```no_run
+    /// use nusb::transfer::{Buffer, Bulk, In};
+    /// # fn example(ep: nusb::Endpoint<Bulk, In>) {
+    /// let ep = ep.on_complete(|ep, c| {
+    ///     println!("received {:?}", &c.buffer[..]);
+    ///     if c.status.is_ok() {
+    ///         ep.submit(c.buffer);
+    ///     }
+    /// });
+    /// ep.submit(Buffer::new(512));
+    /// # }
+    /// ```
+    pub fn on_complete(
+        self,
+        callback: impl FnMut(&mut Endpoint<EpType, Dir>, Completion) + Send + 'static,
+    ) -> CallbackEndpoint<EpType, Dir>
+    where
+        EpType: 'static,
+        Dir: 'static,
+    {
+        CallbackEndpoint::new(self, Box::new(callback))
+    }
 
-        if ep_desc.transfer_type() != EpType::TYPE {
-            return Err(Error::new(ErrorKind::Other, "incorrect endpoint type"));
+    pub(crate) fn set_notify_callback(&self, callback: Option<Arc<dyn Fn() + Send + Sync>>) {
+        self.backend.set_notify_callback(callback);
+    }
+
+    /// Stop host-side traffic on the endpoint until [`resume()`][`Self::resume`].
+    ///
+    /// While paused, transfers submitted with `submit` and similar methods
+    /// are not sent to the OS, and complete immediately with
+    /// [`TransferError::Cancelled`], returning their buffer. Transfers queued
+    /// by a [`Submitter`] are held in its queue and submitted after the
+    /// endpoint is resumed, and [auto-resubmit](Self::set_auto_resubmit)
+    /// stops submitting new transfers.
+    ///
+    /// If `cancel` is `true`, transfers already pending are cancelled as with
+    /// [`cancel_all()`][`Self::cancel_all`]. Otherwise, they are left to
+    /// complete. Either way, they are still returned from `next_complete`
+    /// with their buffers and any data transferred, so wait for them before
+    /// sending the device a command that requires the endpoint to be idle.
+    ///
+    /// Pausing does not change the endpoint's configuration: the short packet
+    /// policy, pending limit, statistics, and auto-resubmit mode are kept, and
+    /// cancelling transfers while paused does not stop auto-resubmit.
+    ///
+    /// `next_complete` and similar methods do not wait for transfers to be
+    /// queued by a `Submitter` while paused. If no transfers are pending,
+    /// `wait_next_complete` returns `None` immediately and
+    /// `poll_next_complete` returns `Poll::Pending`.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use nusb::transfer::{Bulk, In};
+    /// # fn example(mut ep: nusb::Endpoint<Bulk, In>) {
+    /// ep.set_auto_resubmit(4, 4096);
+    /// // ...
+    /// ep.pause(true);
+    /// while ep.pending() > 0 {
+    ///     let c = ep.wait_next_complete(Duration::from_secs(1)).unwrap();
+    ///     ep.recycle(c.buffer);
+    /// }
+    /// // The endpoint is idle, so the device can be told to change modes.
+    /// ep.resume();
+    /// # }
+    /// ```
+    pub fn pause(&mut self, cancel: bool) {
+        self.paused = true;
+        if cancel {
+            self.cancel_all();
         }
+    }
 
-        let backend = self.backend.endpoint(ep_desc)?;
-        Ok(Endpoint {
-            backend,
-            ep_type: PhantomData,
-            ep_dir: PhantomData,
-        })
+    /// Resume an endpoint paused with [`pause()`][`Self::pause`].
+    ///
+    /// If auto-resubmit is enabled, transfers are submitted immediately to
+    /// refill the queue. Transfers held by a `Submitter` are submitted on the
+    /// next call to `next_complete` or similar.
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.refill_auto_resubmit();
     }
-}
 
-impl Debug for Interface {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Interface")
-            .field("number", &self.backend.interface_number)
-            .finish()
+    /// Returns `true` if the endpoint is paused with [`pause()`][`Self::pause`].
+    pub fn is_paused(&self) -> bool {
+        self.paused
     }
-}
 
-/// Exclusive access to an endpoint of a USB device.
-///
-/// Obtain an `Endpoint` with the [`Interface::endpoint`] method.
-///
-/// An `Endpoint` manages a queue of pending transfers. Submitting a transfer is
-/// a non-blocking operation that adds the operation to the queue. Completed
-/// transfers can be popped from the queue synchronously or asynchronously.
-///
-/// This separation of submission and completion makes the API cancel-safe, and
-/// makes it easy to submit multiple transfers at once, regardless of whether
-/// you are using asynchronous or blocking waits.
-///
-/// To maximize throughput and minimize latency when streaming data, the host
-/// controller needs to attempt a transfer in every possible frame. That
-/// requires always having a transfer request pending with the kernel by
-/// submitting multiple transfer requests and re-submitting them as they
-/// complete.
-///
-/// When the `Endpoint` is dropped, any pending transfers are cancelled.
-///
-/// This type provides a low-level API letting you manage transfers directly.
-/// For a higher-level buffered API implementing [`std::io::Read`] and
-/// [`std::io::Write`], use the adapters from [`nusb::io`][`crate::io`]. See
-/// [`Self::reader`] and [`Self::writer`].
-pub struct Endpoint<EpType, Dir> {
-    backend: platform::Endpoint,
-    ep_type: PhantomData<EpType>,
-    ep_dir: PhantomData<Dir>,
+    /// Poll the cancel signal, and cancel all transfers if it fired.
+    fn poll_cancel_signal(&mut self, cx: &mut Context<'_>) {
+        let Some(signal) = &mut self.cancel_signal else {
+            return;
+        };
+        if signal.get_mut().unwrap().poll_cancelled(cx).is_ready() {
+            self.cancel_signal = None;
+            self.cancel_requested = true;
+            self.cancel_all();
+        }
+    }
 }
 
-/// Methods for all endpoints.
-impl<EpType: EndpointType, Dir: EndpointDirection> Endpoint<EpType, Dir> {
-    /// Get the endpoint address.
-    pub fn endpoint_address(&self) -> u8 {
-        self.backend.endpoint_address()
+/// Methods for SuperSpeed bulk streams.
+impl<Dir: EndpointDirection> Endpoint<Bulk, Dir> {
+    /// Allocate USB 3.x bulk streams on this endpoint.
+    ///
+    /// Requests `num_streams` streams and returns the number actually
+    /// allocated, which may be fewer depending on the endpoint descriptor's
+    /// `bMaxStreams` and the host controller. Stream IDs from 1 to the
+    /// returned count can then be used with
+    /// [`submit_stream()`][`Self::submit_stream`].
+    ///
+    /// This should not be called when transfers are pending on the endpoint.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Windows, WinUSB does not support bulk streams, and this returns an
+    ///   error with [`ErrorKind::Unsupported`].
+    /// * On macOS, bulk streams are not yet implemented, and this returns an
+    ///   error with [`ErrorKind::Unsupported`].
+    pub fn alloc_streams(
+        &mut self,
+        num_streams: u32,
+    ) -> impl MaybeFuture<Output = Result<u32, Error>> {
+        self.backend.alloc_streams(num_streams)
     }
 
-    /// Get the maximum packet size for this endpoint.
+    /// Free the bulk streams previously allocated with
+    /// [`alloc_streams()`][`Self::alloc_streams`].
     ///
-    /// Transfers can consist of multiple packets, but are split into packets
-    /// of this size on the bus.
-    pub fn max_packet_size(&self) -> usize {
-        self.backend.max_packet_size
+    /// Streams are also freed when the interface is released.
+    pub fn free_streams(&mut self) -> impl MaybeFuture<Output = Result<(), Error>> {
+        self.backend.free_streams()
     }
 
-    /// Get the number of transfers that have been submitted with `submit` that
-    /// have not yet been returned from `next_complete`.
-    pub fn pending(&self) -> usize {
-        self.backend.pending()
+    /// Begin a transfer on the bulk stream with ID `stream_id`.
+    ///
+    /// This behaves like [`submit()`][`Self::submit`], but the transfer is
+    /// tagged with a stream ID allocated by
+    /// [`alloc_streams()`][`Self::alloc_streams`]. Transfers on different
+    /// streams may complete in any order on the bus, but are still returned
+    /// from `next_complete` in the order they were submitted.
+    ///
+    /// Because of this, a transfer on a stream that the device is not
+    /// servicing delays the completions of all transfers submitted after it,
+    /// on every stream. When streams progress independently, as with the
+    /// data streams of UASP, avoid submitting far ahead on a stream that may
+    /// not complete soon, or [cancel][`Self::cancel`] such a transfer to
+    /// unblock the queue.
+    ///
+    /// Where [`alloc_streams()`][`Self::alloc_streams`] is unsupported, the
+    /// transfer fails with [`TransferError::InvalidArgument`].
+    pub fn submit_stream(&mut self, buf: Buffer, stream_id: u32) -> TransferId {
+        let id = self.start_transfer(&buf);
+        if let Some(buf) = self.check_submit(buf) {
+            self.backend.submit_stream(buf, stream_id)
+        }
+        id
     }
+}
 
-    /// Request cancellation of all pending transfers.
+impl Endpoint<Bulk, Out> {
+    /// Create a [`BulkOutPipe`][`crate::io::BulkOutPipe`] wrapping the given
+    /// endpoint to continuously send data from `source` with `num_transfers`
+    /// transfers of up to `transfer_size` bytes pending.
     ///
-    /// The transfers are cancelled asynchronously. Once cancelled, they will be
-    /// returned from calls to `next_complete` so you can tell which were
-    /// completed, partially-completed, or cancelled.
-    pub fn cancel_all(&mut self) {
-        self.backend.cancel_all()
+    /// See [`BulkOutPipe::new`][`crate::io::BulkOutPipe::new`] for details.
+    pub fn pipe(
+        self,
+        num_transfers: usize,
+        transfer_size: usize,
+        source: impl FnMut(&mut Buffer) + Send + 'static,
+    ) -> crate::io::BulkOutPipe {
+        crate::io::BulkOutPipe::new(self, num_transfers, transfer_size, source)
     }
 }
 
-impl<EpType: BulkOrInterrupt> Endpoint<EpType, Out> {
-    /// Create an [`EndpointWrite`] wrapping the given endpoint to provide a
-    /// high-level buffered API implementing [`std::io::Write`] and async
-    /// equivalents.
+impl Endpoint<Bulk, In> {
+    /// Create a [`BulkInPipe`][`crate::io::BulkInPipe`] wrapping the given
+    /// endpoint to continuously receive data with `num_transfers` transfers
+    /// of `transfer_size` bytes pending.
     ///
-    /// See [`EndpointWrite::new`][`crate::io::EndpointWrite::new`] for details.
-    pub fn writer(self, buffer_size: usize) -> EndpointWrite<EpType> {
-        EndpointWrite::new(self, buffer_size)
+    /// See [`BulkInPipe::new`][`crate::io::BulkInPipe::new`] for details.
+    pub fn pipe(self, num_transfers: usize, transfer_size: usize) -> crate::io::BulkInPipe {
+        crate::io::BulkInPipe::new(self, num_transfers, transfer_size)
     }
 }
 
-impl<EpType: BulkOrInterrupt> Endpoint<EpType, In> {
-    /// Create an [`EndpointRead`] wrapping the given endpoint to provide a
-    /// high-level buffered API implementing [`std::io::Read`] and  async
-    /// equivalents.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Endpoint<Isochronous, In> {
+    /// Create an [`IsoStream`][`crate::io::IsoStream`] wrapping the given
+    /// endpoint to receive packets through the `futures`
+    /// [`Stream`][`futures_core::Stream`] trait.
     ///
-    /// See [`EndpointRead::new`][`crate::io::EndpointRead::new`] for details.
-    pub fn reader(self, buffer_size: usize) -> EndpointRead<EpType> {
-        EndpointRead::new(self, buffer_size)
+    /// See [`IsoStream::new`][`crate::io::IsoStream::new`] for details.
+    pub fn iso_stream(
+        self,
+        packet_len: usize,
+        packets_per_transfer: usize,
+    ) -> crate::io::IsoStream {
+        crate::io::IsoStream::new(self, packet_len, packets_per_transfer)
     }
 }
 
-/// Methods for Bulk and Interrupt endpoints.
-impl<EpType: BulkOrInterrupt, Dir: EndpointDirection> Endpoint<EpType, Dir> {
-    /// Allocate a buffer for use on this endpoint, zero-copy if possible.
+/// Methods for Isochronous endpoints.
+///
+/// *Supported on Linux and Android only.*
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<Dir: EndpointDirection> Endpoint<Isochronous, Dir> {
+    /// Begin an isochronous transfer split into packets of `packet_len` bytes.
     ///
-    /// A zero-copy buffer allows the kernel to DMA directly to/from this
-    /// buffer for improved performance. However, because it is not allocated
-    /// with the system allocator, it cannot be converted to a [`Vec`] without
-    /// copying.
+    /// For an OUT transfer, the buffer's `len` bytes are sent in packets of
+    /// `packet_len` bytes, with the last packet possibly shorter.
     ///
-    /// This is a somewhat expensive operation, requiring a `mmap` system call,
-    /// so is likely only beneficial for buffers that will be used repeatedly.
-    /// Consider using [`Buffer::new`] for one-off transfers.
+    /// For an IN transfer, the buffer's `requested_len` must be a nonzero
+    /// multiple of `packet_len`, and each packet will receive up to
+    /// `packet_len` bytes.
     ///
-    /// This is currently only supported on Linux, falling back to [`Buffer::new`]
-    /// on other platforms, or if the memory allocation fails.
-    pub fn allocate(&self, len: usize) -> Buffer {
-        #[cfg(any(target_os = "linux", target_os = "android"))]
-        {
-            if let Ok(b) = self.backend.allocate(len) {
-                return b;
-            }
+    /// `packet_len` should not exceed the endpoint's [maximum bytes per
+    /// interval][`Self::max_bytes_per_interval`], which accounts for the
+    /// additional transactions per microframe of high-bandwidth endpoints.
+    /// A transfer may contain at most 128 packets. Invalid arguments cause the
+    /// transfer to fail with `TransferError::InvalidArgument`.
+    ///
+    /// Returns a [`TransferId`] that can be passed to
+    /// [`cancel()`][`Self::cancel`] to cancel this transfer.
+    pub fn submit(&mut self, buf: Buffer, packet_len: usize) -> TransferId {
+        let total = Self::transfer_len(&buf);
+
+        if packet_len == 0 || (Dir::DIR == Direction::In && total % packet_len != 0) {
+            warn!(
+                "Submitting isochronous transfer with length {total} which is not a multiple of packet length {packet_len} on endpoint {:02x}",
+                self.endpoint_address(),
+            );
+            let id = self.start_transfer(&buf);
+            self.backend.submit_err(buf, TransferError::InvalidArgument);
+            return id;
         }
 
-        Buffer::new(len)
+        if packet_len > self.max_bytes_per_interval {
+            warn!(
+                "Isochronous packet length {packet_len} exceeds maximum of {} bytes per interval on endpoint {:02x}",
+                self.max_bytes_per_interval,
+                self.endpoint_address(),
+            );
+        }
+
+        let num_packets = total.div_ceil(packet_len);
+        let packets: Vec<usize> = (0..num_packets)
+            .map(|i| packet_len.min(total - i * packet_len))
+            .collect();
+        self.submit_packets(buf, &packets)
     }
 
-    /// Begin a transfer on the endpoint.
+    /// Begin an isochronous transfer with explicitly specified packet lengths.
     ///
-    /// Submitted transfers are queued and completed in order. Once the transfer
-    /// completes, it will be returned from
-    /// [`next_complete()`][`Self::next_complete`]. Any error in submitting or
-    /// performing the transfer is deferred until `next_complete`.
+    /// The packets are laid out contiguously in the buffer. The sum of
+    /// `packet_lengths` must equal the buffer's `len` for an OUT transfer, or
+    /// its `requested_len` for an IN transfer. Between 1 and 128 packets may be
+    /// specified. Otherwise the transfer fails with
+    /// `TransferError::InvalidArgument`.
     ///
-    /// For an OUT transfer, the buffer's `len` is the number of bytes
-    /// initialized, which will be sent to the device.
+    /// Returns a [`TransferId`] that can be passed to
+    /// [`cancel()`][`Self::cancel`] to cancel this transfer.
+    pub fn submit_packets(&mut self, buf: Buffer, packet_lengths: &[usize]) -> TransferId {
+        self.submit_packets_scheduled(buf, packet_lengths, IsoSchedule::Asap)
+    }
+
+    /// Begin an isochronous transfer with explicitly specified packet lengths,
+    /// starting at the specified (micro)frame.
     ///
-    /// For an IN transfer, the buffer's `requested_len` is the number of bytes
-    /// requested. It must be a nonzero multiple of the endpoint's [maximum
-    /// packet size][`Self::max_packet_size`] or the transfer will fail with
-    /// `TransferError::InvalidArgument`. Up to `requested_len /
-    /// max_packet_size` packets will be received, ending early when any packet
-    /// is shorter than `max_packet_size`.
-    pub fn submit(&mut self, buf: Buffer) {
-        if Dir::DIR == Direction::In {
-            let req_len = buf.requested_len();
-            if req_len == 0 || req_len % self.max_packet_size() != 0 {
-                warn!(
-                    "Submitting transfer with length {req_len} which is not a multiple of max packet size {} on IN endpoint {:02x}",
-                    self.max_packet_size(),
-                    self.endpoint_address(),
-                );
+    /// This behaves like [`submit_packets()`][`Self::submit_packets`], which
+    /// uses [`IsoSchedule::Asap`]. Scheduling a transfer at a specific frame
+    /// allows an output stream to be aligned with a clock, or restarted at a
+    /// deterministic point after an underrun.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Linux, the frame is passed as the URB's `start_frame`. Some host
+    ///   controller drivers ignore the requested frame.
+    pub fn submit_packets_scheduled(
+        &mut self,
+        buf: Buffer,
+        packet_lengths: &[usize],
+        schedule: IsoSchedule,
+    ) -> TransferId {
+        let id = self.start_transfer(&buf);
+        let total = Self::transfer_len(&buf);
 
-                return self.backend.submit_err(buf, TransferError::InvalidArgument);
-            }
+        if packet_lengths.is_empty()
+            || packet_lengths.len() > platform::MAX_ISO_PACKETS
+            || packet_lengths.iter().sum::<usize>() != total
+        {
+            warn!(
+                "Invalid packet lengths for isochronous transfer of length {total} on endpoint {:02x}",
+                self.endpoint_address(),
+            );
+            self.backend.submit_err(buf, TransferError::InvalidArgument);
+            return id;
         }
 
-        self.backend.submit(buf)
+        self.backend.submit_iso(buf, packet_lengths, schedule);
+        id
     }
 
     /// Return a `Future` that waits for the next pending transfer to complete.
@@ -698,16 +3848,14 @@ impl<EpType: BulkOrInterrupt, Dir: EndpointDirection> Endpoint<EpType, Dir> {
     /// This future is cancel-safe: it can be cancelled and re-created without
     /// side effects, enabling its use in `select!{}` or similar.
     ///
-    /// An OUT transfer completes when the specified data has been sent or an
-    /// error occurs. An IN transfer completes when a packet smaller than
-    /// `max_packet_size` is received, the full `requested_len` is received
-    /// (without waiting for or consuming any subsequent zero-length packet), or
-    /// an error occurs.
+    /// The returned [`IsoCompletion`] contains the status and length of each
+    /// packet. For an IN transfer, packets that were not fully received leave
+    /// zero-filled gaps in the buffer.
     ///
     /// ## Panics
     /// * if there are no transfers pending (that is, if [`Self::pending()`]
     ///   would return 0).
-    pub fn next_complete(&mut self) -> impl Future<Output = Completion> + Send + Sync + '_ {
+    pub fn next_complete(&mut self) -> impl Future<Output = IsoCompletion> + Send + Sync + '_ {
         poll_fn(|cx| self.poll_next_complete(cx))
     }
 
@@ -719,8 +3867,12 @@ impl<EpType: BulkOrInterrupt, Dir: EndpointDirection> Endpoint<EpType, Dir> {
     /// ## Panics
     ///  * if there are no transfers pending (that is, if [`Self::pending()`]
     ///    would return 0).
-    pub fn poll_next_complete(&mut self, cx: &mut Context<'_>) -> Poll<Completion> {
-        self.backend.poll_next_complete(cx)
+    pub fn poll_next_complete(&mut self, cx: &mut Context<'_>) -> Poll<IsoCompletion> {
+        let mut res = self.backend.poll_next_complete_iso(cx);
+        if let Poll::Ready(c) = &mut res {
+            self.finish_iso_completion(c);
+        }
+        res
     }
 
     /// Wait for a pending transfer completion.
@@ -728,33 +3880,49 @@ impl<EpType: BulkOrInterrupt, Dir: EndpointDirection> Endpoint<EpType, Dir> {
     /// Blocks for up to `timeout` waiting for a transfer to complete, or
     /// returns `None` if the timeout is reached.
     ///
-    /// Note that the transfer is not cancelled after the timeout, and can still
-    /// be returned from a subsequent call.
-    ///
     /// ## Panics
     ///  * if there are no transfers pending (that is, if [`Self::pending()`]
     ///    would return 0).
-    pub fn wait_next_complete(&mut self, timeout: Duration) -> Option<Completion> {
-        self.backend.wait_next_complete(timeout)
+    pub fn wait_next_complete(&mut self, timeout: Duration) -> Option<IsoCompletion> {
+        let mut res = self.backend.wait_next_complete_iso(timeout);
+        if let Some(c) = &mut res {
+            self.finish_iso_completion(c);
+        }
+        res
     }
 
-    /// Clear the endpoint's halt / stall condition.
+    /// Get an estimate of the current (micro)frame number, for scheduling
+    /// transfers with [`IsoSchedule::Frame`].
     ///
-    /// Sends a `CLEAR_FEATURE` `ENDPOINT_HALT` control transfer to tell the
-    /// device to reset the endpoint's data toggle and clear the halt / stall
-    /// condition, and resets the host-side data toggle.
+    /// This is the frame following the last packet of the most recently
+    /// returned transfer, computed from its
+    /// [`start_frame`][`IsoCompletion::start_frame`], assuming one packet per
+    /// service interval of `2^(bInterval - 1)` (micro)frames. While transfers
+    /// are kept pending, it trails the bus by the transfers that have not
+    /// yet been returned. Returns `None` if no transfer has completed yet.
     ///
-    /// Use this after receiving
-    /// [`TransferError::Stall`][crate::transfer::TransferError::Stall] to clear
-    /// the error and resume use of the endpoint.
+    /// ### Platform-specific notes
     ///
-    /// This should not be called when transfers are pending on the endpoint.
-    pub fn clear_halt(&mut self) -> impl MaybeFuture<Output = Result<(), Error>> {
-        self.backend.clear_halt()
+    /// * On Linux, usbfs does not provide a way to query the host controller's
+    ///   frame counter, so this relies on the frame numbers reported in
+    ///   completions. The units of `start_frame` depend on the host
+    ///   controller driver, which may not match the service interval.
+    pub fn current_frame(&self) -> Option<u32> {
+        self.next_frame
+    }
+
+    fn finish_iso_completion(&mut self, c: &mut IsoCompletion) {
+        c.endpoint = self.endpoint_address();
+        self.finish_transfer(&c.buffer, c.actual_len(), c.status.is_ok());
+        if c.status.is_ok() {
+            let service_interval = 1u32 << self.interval.clamp(1, 16).saturating_sub(1);
+            let duration = (c.packets.len() as u32).saturating_mul(service_interval);
+            self.next_frame = Some(c.start_frame.wrapping_add(duration));
+        }
     }
 }
 
-impl<EpType: BulkOrInterrupt, Dir: EndpointDirection> Debug for Endpoint<EpType, Dir> {
+impl<EpType: EndpointType, Dir: EndpointDirection> Debug for Endpoint<EpType, Dir> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Endpoint")
             .field(
@@ -778,4 +3946,309 @@ fn assert_send_sync() {
     require_send_sync::<Endpoint<Bulk, Out>>();
     require_send_sync::<Endpoint<Interrupt, In>>();
     require_send_sync::<Endpoint<Interrupt, Out>>();
+    require_send_sync::<Endpoint<crate::transfer::Isochronous, In>>();
+    require_send_sync::<Endpoint<crate::transfer::Isochronous, Out>>();
+}
+
+/// Update the `submit_vectored` ranges in `groups` for the completion of
+/// transfer `id`, returning the ids of the remaining transfers in its range
+/// to cancel if `ended` is set.
+fn end_vectored(groups: &mut VecDeque<Range<u64>>, id: u64, ended: bool) -> Range<u64> {
+    while groups.front().is_some_and(|g| g.end <= id) {
+        groups.pop_front();
+    }
+    match groups.front() {
+        Some(g) if g.contains(&id) && (ended || id + 1 == g.end) => {
+            let g = groups.pop_front().unwrap();
+            if ended {
+                id + 1..g.end
+            } else {
+                id..id
+            }
+        }
+        _ => id..id,
+    }
+}
+
+/// Find the position of transfer `id` in the backend queue, and the number of
+/// backend entries it occupies, where `pending` are the ids of the pending
+/// transfers and each split transfer occupies one entry per remaining chunk.
+fn split_backend_index(
+    splits: &VecDeque<SplitTransfer>,
+    pending: Range<u64>,
+    id: u64,
+) -> Option<(usize, usize)> {
+    if !pending.contains(&id) {
+        return None;
+    }
+
+    let mut index = (id - pending.start) as usize;
+    let mut chunks = 1;
+    for s in splits {
+        if s.id < id {
+            index += s.remaining - 1;
+        } else {
+            if s.id == id {
+                chunks = s.remaining;
+            }
+            break;
+        }
+    }
+    Some((index, chunks))
+}
+
+/// Count the pending transfers in `pending` whose backend entries are all
+/// among the first `backend_ready` completed entries.
+fn split_ready(
+    splits: &VecDeque<SplitTransfer>,
+    pending: Range<u64>,
+    mut backend_ready: usize,
+) -> usize {
+    let mut splits = splits.iter().peekable();
+    let mut ready = 0;
+    for id in pending {
+        let chunks = match splits.next_if(|s| s.id == id) {
+            Some(s) => s.remaining,
+            None => 1,
+        };
+        if backend_ready < chunks {
+            break;
+        }
+        backend_ready -= chunks;
+        ready += 1;
+    }
+    ready
+}
+
+/// Error completing transfers submitted after the cancel signal fired or
+/// while the endpoint is paused, instead of submitting them.
+fn submit_rejection(cancel_requested: bool, paused: bool) -> Option<TransferError> {
+    (cancel_requested || paused).then_some(TransferError::Cancelled)
+}
+
+/// Whether a completion with `status` ends auto-resubmit mode.
+///
+/// Transfers cancelled by `pause` keep the mode enabled so that `resume`
+/// restarts it.
+fn auto_resubmit_stops(status: &Result<(), TransferError>, paused: bool) -> bool {
+    match status {
+        Err(TransferError::Cancelled) => !paused,
+        Err(TransferError::Stall | TransferError::Disconnected) => true,
+        _ => false,
+    }
+}
+
+/// Number of transfers to submit to keep `num_transfers` pending in
+/// auto-resubmit mode.
+fn auto_resubmit_count(num_transfers: usize, pending: usize, paused: bool) -> usize {
+    if paused {
+        0
+    } else {
+        num_transfers.saturating_sub(pending)
+    }
+}
+
+#[test]
+fn vectored_short_packet_cancels_rest() {
+    let mut groups = VecDeque::from([2..5, 5..7]);
+
+    // A transfer outside any range, then a range completing normally.
+    assert!(end_vectored(&mut groups, 1, true).is_empty());
+    assert!(end_vectored(&mut groups, 2, false).is_empty());
+    assert!(end_vectored(&mut groups, 3, false).is_empty());
+    assert!(end_vectored(&mut groups, 4, false).is_empty());
+    assert_eq!(groups.front(), Some(&(5..7)));
+
+    // A short packet in the first buffer cancels the second, whose own
+    // completion then doesn't cancel anything.
+    assert_eq!(end_vectored(&mut groups, 5, true), 6..7);
+    assert!(groups.is_empty());
+    assert!(end_vectored(&mut groups, 6, true).is_empty());
+}
+
+#[test]
+fn device_status_bits() {
+    let status = DeviceStatus(0x0003);
+    assert!(status.self_powered());
+    assert!(status.remote_wakeup());
+    assert!(!status.u1_enabled());
+
+    let status = DeviceStatus(0x001c);
+    assert!(!status.self_powered());
+    assert!(status.u1_enabled());
+    assert!(status.u2_enabled());
+    assert!(status.ltm_enabled());
+}
+
+#[cfg(test)]
+fn test_split(id: u64, remaining: usize) -> SplitTransfer {
+    SplitTransfer {
+        id,
+        remaining,
+        buffer: Arc::new(Buffer::new(0)),
+        len: 0,
+        actual_len: 0,
+        status: Ok(()),
+        ended: false,
+    }
+}
+
+#[test]
+fn split_backend_index_and_ready() {
+    // Backend queue: 2 | 3 3 3 | 4 | 5 5 | 6
+    let splits = VecDeque::from([test_split(3, 3), test_split(5, 2)]);
+    let index = |id| split_backend_index(&splits, 2..7, id);
+    assert_eq!(index(1), None);
+    assert_eq!(index(2), Some((0, 1)));
+    assert_eq!(index(3), Some((1, 3)));
+    assert_eq!(index(4), Some((4, 1)));
+    assert_eq!(index(5), Some((5, 2)));
+    assert_eq!(index(6), Some((7, 1)));
+    assert_eq!(index(7), None);
+
+    let ready: Vec<usize> = (0..=8).map(|n| split_ready(&splits, 2..7, n)).collect();
+    assert_eq!(ready, [0, 1, 1, 1, 2, 3, 3, 4, 5]);
+}
+
+#[test]
+fn cancel_next_covers_remaining_chunks() {
+    // `cancel_next` cancels the front transfer, which may be a split
+    // transfer with some of its chunks already returned.
+    let splits = VecDeque::from([test_split(4, 2)]);
+    assert_eq!(split_backend_index(&splits, 4..6, 4), Some((0, 2)));
+    assert_eq!(split_backend_index(&splits, 4..6, 5), Some((2, 1)));
+
+    let splits = VecDeque::from([test_split(5, 3)]);
+    assert_eq!(split_backend_index(&splits, 4..6, 4), Some((0, 1)));
+    assert_eq!(split_backend_index(&splits, 4..6, 5), Some((1, 3)));
+}
+
+#[test]
+fn split_in_merges_chunks_until_short() {
+    let mut buf = Buffer::new(3 * 512);
+    let ptr = buf.as_mut_ptr();
+    let mut s = test_split(0, 3);
+    s.buffer = Arc::new(buf);
+
+    let chunk = |offset: usize, len: usize, status| {
+        // SAFETY: the chunks are within the buffer, and are only used to
+        // compare pointers and lengths.
+        let mut buffer = unsafe { Buffer::from_raw_parts(ptr.add(offset), 0, 512) };
+        buffer.len = len as u32;
+        Completion {
+            buffer,
+            actual_len: len,
+            status,
+            tag: 0,
+            endpoint: 0,
+            submitted_at: None,
+            completed_at: None,
+        }
+    };
+
+    assert!(!s.add_chunk(&chunk(0, 512, Ok(())), Direction::In));
+    assert!(s.add_chunk(&chunk(512, 100, Ok(())), Direction::In));
+    assert!(!s.add_chunk(&chunk(612, 0, Err(TransferError::Cancelled)), Direction::In));
+    assert_eq!(s.remaining, 0);
+    assert_eq!(s.len, 612);
+    assert_eq!(s.actual_len, 612);
+    assert_eq!(s.status, Ok(()));
+}
+
+#[test]
+fn split_out_error_ends_transfer() {
+    let mut s = test_split(0, 3);
+    let chunk = |actual_len, status| Completion {
+        buffer: Buffer::new(0),
+        actual_len,
+        status,
+        tag: 0,
+        endpoint: 0,
+        submitted_at: None,
+        completed_at: None,
+    };
+
+    assert!(!s.add_chunk(&chunk(512, Ok(())), Direction::Out));
+    assert!(s.add_chunk(&chunk(64, Err(TransferError::Stall)), Direction::Out));
+    assert!(!s.add_chunk(&chunk(0, Err(TransferError::Cancelled)), Direction::Out));
+    assert_eq!(s.actual_len, 576);
+    assert_eq!(s.status, Err(TransferError::Stall));
+}
+
+#[test]
+fn split_buffer_freed_with_last_chunk() {
+    let buffer = Arc::new(Buffer::new(1024));
+    let weak = Arc::downgrade(&buffer);
+    // SAFETY: the chunk is within the buffer.
+    let mut chunk = unsafe { Buffer::from_raw_parts(buffer.ptr, 0, 512) };
+    chunk.allocator = Allocator::Chunk(buffer.clone());
+
+    // The endpoint is dropped while the chunk is still pending.
+    drop(buffer);
+    assert!(weak.upgrade().is_some());
+
+    // The backend frees the chunk once it is reaped.
+    drop(chunk);
+    assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn auto_resubmit_pause_and_resume() {
+    assert!(!auto_resubmit_stops(&Ok(()), false));
+    assert!(!auto_resubmit_stops(&Err(TransferError::Fault(0)), false));
+    assert!(auto_resubmit_stops(&Err(TransferError::Stall), false));
+    assert!(auto_resubmit_stops(&Err(TransferError::Disconnected), true));
+
+    // Cancellation stops the mode, unless it was caused by `pause`.
+    assert!(auto_resubmit_stops(&Err(TransferError::Cancelled), false));
+    assert!(!auto_resubmit_stops(&Err(TransferError::Cancelled), true));
+
+    // Nothing is submitted while paused, and `resume` refills the queue.
+    assert_eq!(auto_resubmit_count(4, 1, false), 3);
+    assert_eq!(auto_resubmit_count(4, 1, true), 0);
+    assert_eq!(auto_resubmit_count(4, 0, false), 4);
+    assert_eq!(auto_resubmit_count(2, 3, false), 0);
+}
+
+#[test]
+fn queued_transfers_after_split() {
+    // Transfers from a `Submitter` get the next ids in queue order, after a
+    // split transfer submitted directly on the endpoint.
+    let rx = SubmitReceiver::new();
+    let submitter = rx.submitter();
+    submitter.submit_tagged(Buffer::new(64), 10).unwrap();
+    submitter.submit_tagged(Buffer::new(64), 11).unwrap();
+
+    let splits = VecDeque::from([test_split(0, 4)]);
+    let queued = rx.take(None);
+    let ids = 1..1 + queued.len() as u64;
+    let indexes: Vec<_> = ids
+        .zip(&queued)
+        .map(|(id, (_, tag))| (*tag, split_backend_index(&splits, 0..3, id)))
+        .collect();
+    assert_eq!(indexes, [(10, Some((4, 1))), (11, Some((5, 1)))]);
+    assert_eq!(split_ready(&splits, 0..3, 5), 2);
+}
+
+#[test]
+fn submit_after_cancel_signal() {
+    assert_eq!(submit_rejection(false, false), None);
+    assert_eq!(
+        submit_rejection(true, false),
+        Some(TransferError::Cancelled)
+    );
+}
+
+#[test]
+fn submit_while_paused() {
+    // Transfers submitted while paused complete with `Cancelled` instead of
+    // being submitted, without stopping auto-resubmit.
+    let err = submit_rejection(false, true);
+    assert_eq!(err, Some(TransferError::Cancelled));
+    assert!(!auto_resubmit_stops(&Err(err.unwrap()), true));
+    assert_eq!(auto_resubmit_count(4, 0, true), 0);
+
+    // After `resume`, transfers are submitted again.
+    assert_eq!(submit_rejection(false, false), None);
+    assert_eq!(auto_resubmit_count(4, 0, false), 4);
 }
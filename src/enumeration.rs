@@ -1,15 +1,159 @@
 #[cfg(target_os = "windows")]
 use std::ffi::{OsStr, OsString};
 
-#[cfg(any(target_os = "linux"))]
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+use std::{collections::HashMap, sync::Arc};
+
+#[cfg(target_os = "linux")]
 use crate::platform::SysfsPath;
 
 use crate::{Device, Error, MaybeFuture};
 
 /// Opaque device identifier
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeviceId(pub(crate) crate::platform::DeviceId);
 
+/// Persistent identifier for the physical port where a device is connected.
+///
+/// Unlike [`DeviceId`], which changes every time the device is connected or
+/// re-enumerates, a `PortId` is derived from the bus ID and
+/// [port chain][`DeviceInfo::port_chain`], and stays the same as long as the
+/// device is connected to the same port. Identical devices connected to
+/// different ports have different `PortId`s, so it can be used to remember a
+/// particular device, such as one unit of a test fixture.
+///
+/// A `PortId` can be stored as a string with its `Display` implementation,
+/// which produces the same string as [`DeviceInfo::port_path`], and parsed
+/// back with [`FromStr`][`std::str::FromStr`]. The format is
+/// platform-specific, and a `PortId` may change if the host controller is
+/// moved or the OS reassigns bus IDs, for example after adding hardware.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortId {
+    bus_id: String,
+    port_chain: Vec<u8>,
+}
+
+impl PortId {
+    /// Identifier for the bus / host controller.
+    pub fn bus_id(&self) -> &str {
+        &self.bus_id
+    }
+
+    /// Path of port numbers from the root hub.
+    pub fn port_chain(&self) -> &[u8] {
+        &self.port_chain
+    }
+}
+
+impl std::fmt::Display for PortId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.bus_id)?;
+        for (i, port) in self.port_chain.iter().enumerate() {
+            write!(f, "{}{port}", if i == 0 { '-' } else { '.' })?;
+        }
+        Ok(())
+    }
+}
+
+/// A string descriptor value cached by the OS, returned by the string methods
+/// of [`DeviceInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CachedString<'a> {
+    /// The string's value.
+    Value(&'a str),
+
+    /// The device does not have this string: its index in the device
+    /// descriptor is zero.
+    Absent,
+
+    /// The device may have this string, but the OS did not cache it or nusb
+    /// could not read it. Open the device to read the string descriptor.
+    Unknown,
+}
+
+impl<'a> CachedString<'a> {
+    /// Get the string's value, or `None` if it is absent or unknown.
+    pub fn value(self) -> Option<&'a str> {
+        match self {
+            CachedString::Value(s) => Some(s),
+            CachedString::Absent | CachedString::Unknown => None,
+        }
+    }
+}
+
+/// Owned storage for a [`CachedString`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum StoredString {
+    Value(String),
+    Absent,
+    Unknown,
+}
+
+impl StoredString {
+    /// Combine a string read from the OS with the string's index from the
+    /// device descriptor, if known.
+    pub(crate) fn new(value: Option<String>, index: Option<u8>) -> Self {
+        match (value, index) {
+            (_, Some(0)) => StoredString::Absent,
+            (Some(value), _) => StoredString::Value(value),
+            (None, _) => StoredString::Unknown,
+        }
+    }
+
+    fn get(&self) -> CachedString<'_> {
+        match self {
+            StoredString::Value(s) => CachedString::Value(s),
+            StoredString::Absent => CachedString::Absent,
+            StoredString::Unknown => CachedString::Unknown,
+        }
+    }
+}
+
+/// Error parsing a [`PortId`] from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePortIdError;
+
+impl std::fmt::Display for ParsePortIdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid port ID")
+    }
+}
+
+impl std::error::Error for ParsePortIdError {}
+
+impl std::str::FromStr for PortId {
+    type Err = ParsePortIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_chain = |chain: &str| {
+            chain
+                .split('.')
+                .map(|p| p.parse::<u8>().ok())
+                .collect::<Option<Vec<u8>>>()
+        };
+
+        let (bus_id, port_chain) = match s.rsplit_once('-') {
+            Some((bus, chain)) => match parse_chain(chain) {
+                Some(chain) => (bus, chain),
+                None => (s, Vec::new()),
+            },
+            None => (s, Vec::new()),
+        };
+
+        if bus_id.is_empty() {
+            return Err(ParsePortIdError);
+        }
+
+        Ok(PortId {
+            bus_id: bus_id.to_owned(),
+            port_chain,
+        })
+    }
+}
+
 /// Information about a device that can be obtained without opening it.
 ///
 /// `DeviceInfo` is returned by [`list_devices`][crate::list_devices].
@@ -18,9 +162,11 @@ pub struct DeviceId(pub(crate) crate::platform::DeviceId);
 ///
 /// * Some fields are platform-specific
 ///     * Linux: `sysfs_path`, `busnum`
-///     * Windows: `instance_id`, `parent_instance_id`, `port_number`, `driver`
+///     * Windows: `instance_id`, `parent_instance_id`, `port_number`, `driver`,
+///       `container_id`, `device_paths`
 ///     * macOS: `registry_id`, `location_id`
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeviceInfo {
     #[cfg(target_os = "linux")]
     pub(crate) path: SysfsPath,
@@ -34,6 +180,12 @@ pub struct DeviceInfo {
     #[cfg(target_os = "windows")]
     pub(crate) location_paths: Vec<OsString>,
 
+    #[cfg(target_os = "windows")]
+    pub(crate) container_id: Option<String>,
+
+    #[cfg(target_os = "windows")]
+    pub(crate) device_paths: Vec<OsString>,
+
     #[cfg(target_os = "windows")]
     pub(crate) parent_instance_id: OsString,
 
@@ -79,9 +231,9 @@ pub struct DeviceInfo {
 
     pub(crate) speed: Option<Speed>,
 
-    pub(crate) manufacturer_string: Option<String>,
-    pub(crate) product_string: Option<String>,
-    pub(crate) serial_number: Option<String>,
+    pub(crate) manufacturer_string: StoredString,
+    pub(crate) product_string: StoredString,
+    pub(crate) serial_number: StoredString,
 
     pub(crate) interfaces: Vec<InterfaceInfo>,
 }
@@ -108,6 +260,53 @@ impl DeviceInfo {
         }
     }
 
+    /// Check whether the device could likely be opened and used, without
+    /// opening it or claiming any interfaces.
+    ///
+    /// Returns `Ok(())` if no problem was found, or an [`Error`] whose
+    /// [`kind`][`Error::kind`] and message describe why opening or claiming
+    /// interfaces would fail. This is useful for showing the user why a
+    /// device can't be used, e.g. that a udev rule or driver is missing,
+    /// before trying to open it. An `Ok` result does not guarantee that
+    /// opening will succeed, for example because another program may have
+    /// exclusive access.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Linux, this checks the permissions of the device node in
+    ///   `/dev/bus/usb` for the effective user and groups of the process.
+    ///   If it is readable but not writable, a `PermissionDenied` error is
+    ///   returned, but [`open_read_only`][Self::open_read_only] would
+    ///   succeed.
+    /// * On Windows, this checks that the device, or at least one interface
+    ///   of a composite device, is bound to WinUSB, and otherwise returns an
+    ///   `Unsupported` error. Other drivers allow opening the device, but not
+    ///   claiming interfaces.
+    /// * On macOS, there is no user permission for USB access, but a
+    ///   sandboxed app needs the `com.apple.security.device.usb`
+    ///   entitlement. This returns a `PermissionDenied` error if the process
+    ///   is sandboxed without it.
+    ///
+    /// All platforms return a `Disconnected` error if the device is no longer
+    /// connected.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    pub fn check_access(&self) -> Result<(), Error> {
+        #[cfg(target_os = "linux")]
+        {
+            crate::platform::check_access(self.busnum, self.device_address)
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            crate::platform::check_access(self.devinst)
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            crate::platform::check_access(self.registry_id)
+        }
+    }
+
     /// *(Linux-only)* Sysfs path for the device.
     #[cfg(target_os = "linux")]
     pub fn sysfs_path(&self) -> &std::path::Path {
@@ -117,17 +316,111 @@ impl DeviceInfo {
     /// *(Linux-only)* Bus number.
     ///
     /// On Linux, the `bus_id` is an integer and this provides the value as `u8`.
-    #[cfg(any(target_os = "linux"))]
+    #[cfg(target_os = "linux")]
     pub fn busnum(&self) -> u8 {
         self.busnum
     }
 
+    /// *(Linux-only)* Read a sysfs attribute of the device.
+    ///
+    /// Returns the contents of the file `name` in the device's
+    /// [sysfs directory][Self::sysfs_path], without the trailing newline.
+    /// Errors with [`ErrorKind::NotFound`][`crate::ErrorKind::NotFound`] if
+    /// the attribute does not exist.
+    #[cfg(target_os = "linux")]
+    pub fn sysfs_attribute(&self, name: &str) -> Result<String, Error> {
+        self.path.read_attr_string(name)
+    }
+
+    /// *(Linux-only)* Get the udev properties of the device.
+    ///
+    /// This includes the properties reported by the kernel, such as
+    /// `DEVNAME` and `PRODUCT`, and those added by udev builtins and rules,
+    /// such as `ID_MODEL`, `ID_SERIAL`, and variables set with `ENV{...}`.
+    /// The properties are read directly from sysfs and the udev database in
+    /// `/run/udev/data`, without linking libudev. If udev is not running,
+    /// only the kernel's properties are available.
+    ///
+    /// The properties are read when this is called, not when the device was
+    /// listed.
+    #[cfg(target_os = "linux")]
+    pub fn udev_properties(&self) -> Result<Vec<(String, String)>, Error> {
+        self.path.udev_properties()
+    }
+
+    /// *(Linux-only)* Get a udev property of the device by name.
+    ///
+    /// Returns `None` if the property is not set or could not be read. See
+    /// [`udev_properties`][Self::udev_properties].
+    #[cfg(target_os = "linux")]
+    pub fn udev_property(&self, name: &str) -> Option<String> {
+        self.path
+            .udev_properties()
+            .ok()?
+            .into_iter()
+            .find_map(|(k, v)| (k == name).then_some(v))
+    }
+
+    /// *(Linux-only)* Whether the device is authorized to be used.
+    ///
+    /// This reads the `authorized` sysfs attribute. An unauthorized device
+    /// is connected and enumerated, but is not configured and its interfaces
+    /// are not bound to drivers.
+    #[cfg(target_os = "linux")]
+    pub fn is_authorized(&self) -> Result<bool, Error> {
+        self.path
+            .read_attr_checked::<u8>("authorized", "failed to read authorized")
+            .map(|v| v != 0)
+    }
+
+    /// *(Linux-only)* Authorize or de-authorize the device.
+    ///
+    /// This writes the `authorized` sysfs attribute, which usually requires
+    /// root privileges. De-authorizing a device unbinds its drivers and
+    /// unconfigures it, and authorizing it again configures it and probes
+    /// drivers, similar to reconnecting it. Use
+    /// `BusInfo::set_authorized_default` to control whether newly connected
+    /// devices are authorized.
+    #[cfg(target_os = "linux")]
+    pub fn set_authorized(&self, authorized: bool) -> Result<(), Error> {
+        let value = if authorized { "1" } else { "0" };
+        self.path
+            .write_attr_checked("authorized", value, "failed to set authorized")
+    }
+
     /// *(Windows-only)* Instance ID path of this device
+    ///
+    /// This is the device instance ID of the USB device node, like
+    /// `USB\VID_1234&PID_5678\0123456`. The device nodes of the functions
+    /// of a composite device are its children.
     #[cfg(target_os = "windows")]
     pub fn instance_id(&self) -> &OsStr {
         &self.instance_id
     }
 
+    /// *(Windows-only)* Container ID of the device
+    ///
+    /// Windows groups all device nodes belonging to the same physical device
+    /// into a container, so the container ID can be used to find the COM
+    /// ports, disks, and other device nodes created for this device. It is
+    /// formatted like `{00000000-0000-0000-0000-000000000000}`.
+    ///
+    /// Returns `None` if the property could not be read.
+    #[cfg(target_os = "windows")]
+    pub fn container_id(&self) -> Option<&str> {
+        self.container_id.as_deref()
+    }
+
+    /// *(Windows-only)* Device interface paths of the device
+    ///
+    /// These are the paths registered for the device node under
+    /// `GUID_DEVINTERFACE_USB_DEVICE`, which can be passed to `CreateFile`
+    /// to open the device with other APIs. Usually there is exactly one.
+    #[cfg(target_os = "windows")]
+    pub fn device_paths(&self) -> &[OsString] {
+        &self.device_paths
+    }
+
     /// *(Windows-only)* Location paths property
     #[cfg(target_os = "windows")]
     pub fn location_paths(&self) -> &[OsString] {
@@ -158,6 +451,33 @@ impl DeviceInfo {
         &self.port_chain
     }
 
+    /// Physical location of the device as a string of the
+    /// [bus ID][`Self::bus_id`] and [port chain][`Self::port_chain`].
+    ///
+    /// The port chain follows the bus ID as in Linux sysfs device names: for
+    /// example, a device on port 2 of a hub on port 4 of a hub on port 1 of
+    /// bus `003` is `003-1.4.2`. This is suitable for mapping devices to
+    /// physical connectors, but the bus ID is formatted as by
+    /// [`bus_id`][`Self::bus_id`], which is platform-specific. In particular,
+    /// it is zero-padded on Linux, so the sysfs name of the same device is
+    /// `3-1.4.2`. For a root hub, which has an empty port chain, this is only
+    /// the bus ID.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    pub fn port_path(&self) -> String {
+        self.port_id().to_string()
+    }
+
+    /// Persistent identifier for the port where the device is connected.
+    ///
+    /// See [`PortId`].
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    pub fn port_id(&self) -> PortId {
+        PortId {
+            bus_id: self.bus_id.clone(),
+            port_chain: self.port_chain.clone(),
+        }
+    }
+
     /// *(Windows-only)* Driver associated with the device as a whole
     #[cfg(target_os = "windows")]
     pub fn driver(&self) -> Option<&str> {
@@ -165,17 +485,35 @@ impl DeviceInfo {
     }
 
     /// *(macOS-only)* IOKit Location ID
+    ///
+    /// This is the `locationID` property, which `system_profiler
+    /// SPUSBDataType` shows as "Location ID".
     #[cfg(target_os = "macos")]
     pub fn location_id(&self) -> u32 {
         self.location_id
     }
 
     /// *(macOS-only)* IOKit [Registry Entry ID](https://developer.apple.com/documentation/iokit/1514719-ioregistryentrygetregistryentryi?language=objc)
+    ///
+    /// This identifies the device's entry in the IORegistry, and can be
+    /// passed to `IORegistryEntryIDMatching` to find the device from other
+    /// code, such as the client of a DriverKit extension.
     #[cfg(target_os = "macos")]
     pub fn registry_entry_id(&self) -> u64 {
         self.registry_id
     }
 
+    /// *(macOS-only)* Read a property of the device's IORegistry entry.
+    ///
+    /// The property is read from the registry when this is called. Returns
+    /// `None` if the entry does not have the property, or if its type is not
+    /// supported by [`IoRegistryProperty`]. Run `ioreg -p IOUSB -l` to see
+    /// the available properties.
+    #[cfg(target_os = "macos")]
+    pub fn ioregistry_property(&self, name: &str) -> Result<Option<IoRegistryProperty>, Error> {
+        crate::platform::get_registry_property(self.registry_id, name)
+    }
+
     /// Identifier for the bus / host controller where the device is connected.
     #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
     pub fn bus_id(&self) -> &str {
@@ -234,31 +572,64 @@ impl DeviceInfo {
     }
 
     /// Connection speed
+    ///
+    /// This is the speed at which the device is operating, which is limited
+    /// by the slowest of the device, its port, and any hubs in between.
+    /// `Speed` is ordered, so this can be used to skip devices that are
+    /// plugged into a USB 2.0 port:
+    ///
+    /// ```no_run
+    /// use nusb::{MaybeFuture, Speed};
+    /// let fast_devices = nusb::list_devices().wait().unwrap()
+    ///     .filter(|d| d.speed().is_some_and(|s| s >= Speed::Super));
+    /// ```
+    ///
+    /// ### Platform-specific notes
+    ///  * On Windows, [`Speed::SuperPlusX2`] is not distinguished from
+    ///    [`Speed::SuperPlus`].
     #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
     pub fn speed(&self) -> Option<Speed> {
         self.speed
     }
 
-    /// Manufacturer string, if available without device IO.
+    /// Manufacturer string, as cached by the OS.
+    ///
+    /// The string methods return the values cached by the OS when the device
+    /// was enumerated, so they can be used to display a list of devices
+    /// without opening them. The [`CachedString`] distinguishes a device
+    /// that has no such string from one whose string the OS did not cache,
+    /// which can be read by opening the device.
     ///
     /// ### Platform-specific notes
-    ///  * Windows: Windows does not cache the manufacturer string, and
-    ///    this will return `None` regardless of whether a descriptor exists.
+    ///  * Windows: Windows does not cache the manufacturer string, so this
+    ///    is [`CachedString::Unknown`] if the device has one.
     #[doc(alias = "iManufacturer")]
-    pub fn manufacturer_string(&self) -> Option<&str> {
-        self.manufacturer_string.as_deref()
+    pub fn manufacturer_string(&self) -> CachedString<'_> {
+        self.manufacturer_string.get()
     }
 
-    /// Product string, if available without device IO.
+    /// Product string, as cached by the OS.
+    ///
+    /// See [`manufacturer_string`][`Self::manufacturer_string`].
+    ///
+    /// ### Platform-specific notes
+    ///  * Windows: this is the bus-reported device description, which is
+    ///    the product string if the device provides one.
     #[doc(alias = "iProduct")]
-    pub fn product_string(&self) -> Option<&str> {
-        self.product_string.as_deref()
+    pub fn product_string(&self) -> CachedString<'_> {
+        self.product_string.get()
     }
 
-    /// Serial number string, if available without device IO.
+    /// Serial number string, as cached by the OS.
+    ///
+    /// See [`manufacturer_string`][`Self::manufacturer_string`].
+    ///
+    /// ### Platform-specific notes
+    ///  * Windows: this is read from the descriptor cached by the hub
+    ///    driver, assuming the US English language ID.
     #[doc(alias = "iSerial")]
-    pub fn serial_number(&self) -> Option<&str> {
-        self.serial_number.as_deref()
+    pub fn serial_number(&self) -> CachedString<'_> {
+        self.serial_number.get()
     }
 
     /// Iterator over the device's interfaces.
@@ -271,21 +642,135 @@ impl DeviceInfo {
     /// configuration descriptor after opening the device by calling
     /// [`Device::active_configuration`].
     ///
+    /// For example, to find devices with a vendor-specific interface:
+    ///
+    /// ```no_run
+    /// use nusb::MaybeFuture;
+    /// let devices = nusb::list_devices().wait().unwrap()
+    ///     .filter(|d| d.interfaces().any(|i| i.class() == 0xff));
+    /// ```
+    ///
     /// ### Platform-specific notes:
-    ///   * Windows: this is only available for composite devices bound to the
-    ///     `usbccgp` driver, and will be empty if the entire device is bound to
-    ///     a specific driver.
-    ///   * Windows: When interfaces are grouped by an interface
-    ///     association descriptor, this returns details from the interface
-    ///     association descriptor and does not include each of the associated
-    ///     interfaces.
+    ///   * Windows: the interfaces are read from the configuration descriptor
+    ///     cached by the hub driver, and are only included if it is for the
+    ///     active configuration.
+    ///   * Windows: interface strings are only available for composite
+    ///     devices bound to the `usbccgp` driver.
     pub fn interfaces(&self) -> impl Iterator<Item = &InterfaceInfo> {
         self.interfaces.iter()
     }
 
     /// Open the device
     pub fn open(&self) -> impl MaybeFuture<Output = Result<Device, Error>> {
-        Device::open(self)
+        Device::open(self, false)
+    }
+
+    /// Open the device for inspection, without requesting write or
+    /// exclusive access.
+    ///
+    /// This is intended for tools like `lsusb` that read descriptors,
+    /// strings, and status without disturbing drivers that are using the
+    /// device, and works with the more restrictive permissions that are
+    /// commonly granted for reading device nodes. Descriptors are available
+    /// as with [`open`][`Self::open`], but operations that modify the device
+    /// may fail with [`ErrorKind::PermissionDenied`][`crate::ErrorKind::PermissionDenied`].
+    ///
+    /// ### Platform-specific notes
+    /// * On Linux, this opens the usbfs device node read-only, so it only
+    ///   requires read permission on the node. Whether control transfers are
+    ///   allowed on a read-only node depends on the kernel.
+    /// * On macOS, this does not attempt to open the device for exclusive
+    ///   access, which is instead requested when an operation such as
+    ///   [`Device::set_configuration`] needs it.
+    /// * On Windows, opening a device never requires exclusive access, so
+    ///   this is the same as [`open`][`Self::open`].
+    pub fn open_read_only(&self) -> impl MaybeFuture<Output = Result<Device, Error>> {
+        Device::open(self, true)
+    }
+
+    /// Find the bus that the device is connected to.
+    ///
+    /// The bus is found by listing buses and matching the
+    /// [`bus_id`][`Self::bus_id`]. Returns `None` if the bus is no longer
+    /// present.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    pub fn bus(&self) -> impl MaybeFuture<Output = Result<Option<BusInfo>, Error>> {
+        let bus_id = self.bus_id.clone();
+        crate::list_buses().map(move |buses| Ok(buses?.find(|b| b.bus_id() == bus_id)))
+    }
+
+    /// Find the hub that the device is connected to.
+    ///
+    /// The hub is found by listing devices and matching the
+    /// [`bus_id`][`Self::bus_id`] and [`port_chain`][`Self::port_chain`].
+    /// Call this repeatedly on the result to walk up the topology, such as
+    /// to find devices that share the bandwidth or power of a hub. Returns
+    /// `None` if the device is a root hub, or the hub is no longer connected.
+    ///
+    /// ### Platform-specific details
+    ///
+    /// * On Linux, the parent hub of a device connected directly to a root
+    ///   port is the root hub, which is also returned by `BusInfo::root_hub`.
+    /// * On Windows and macOS, root hubs are not listed as devices, so this
+    ///   returns `None` for a device connected directly to a root port. Use
+    ///   [`list_buses`][crate::list_buses] to find its host controller.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    pub fn parent_hub(&self) -> impl MaybeFuture<Output = Result<Option<DeviceInfo>, Error>> {
+        let bus_id = self.bus_id.clone();
+        let parent_chain = self.port_chain.split_last().map(|(_, p)| p.to_vec());
+        crate::list_devices().map(move |devices| {
+            let Some(parent_chain) = parent_chain else {
+                return Ok(None);
+            };
+
+            #[cfg(target_os = "linux")]
+            if parent_chain.is_empty() {
+                return Ok(crate::platform::list_root_hubs()?.find(|d| d.bus_id == bus_id));
+            }
+
+            Ok(devices?.find(|d| d.bus_id == bus_id && d.port_chain == parent_chain))
+        })
+    }
+
+    /// Wait for the device to disconnect and re-enumerate, and return the
+    /// new `DeviceInfo`.
+    ///
+    /// This is intended for workflows such as DFU or switching to a
+    /// bootloader, where a device is reset or detaches itself and returns as
+    /// a new device on the same port, possibly with a different vendor and
+    /// product ID. The new device is matched by its [`port_id`][`Self::port_id`].
+    ///
+    /// If the device is still connected, this waits for it to disconnect
+    /// first. If it has already disconnected, a device on the same port is
+    /// returned immediately if there is one. Fails with
+    /// [`ErrorKind::Disconnected`][`crate::ErrorKind::Disconnected`] if no
+    /// device appears within `timeout`.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use nusb::MaybeFuture;
+    /// # fn example(info: nusb::DeviceInfo, device: nusb::Device) -> Result<(), nusb::Error> {
+    /// // ... send a request asking the device to switch to its bootloader ...
+    /// drop(device);
+    /// let bootloader = info.wait_for_reconnect(Duration::from_secs(5)).wait()?.open().wait()?;
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// ### Platform-specific notes
+    /// * On Windows, a device that re-enumerates with the same identity
+    ///   keeps its [`id`][`Self::id`]. If it disconnects and reconnects
+    ///   before this is called, this waits for another disconnection, so
+    ///   call this before the device is expected to disconnect.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    pub fn wait_for_reconnect(
+        &self,
+        timeout: std::time::Duration,
+    ) -> impl MaybeFuture<Output = Result<DeviceInfo, Error>> {
+        let id = self.id();
+        let port_id = self.port_id();
+        crate::maybe_future::blocking::Blocking::new(move || {
+            crate::hotplug::wait_for(|d| d.port_id() == port_id, Some(id), timeout)
+        })
     }
 }
 
@@ -313,9 +798,9 @@ impl std::fmt::Debug for DeviceInfo {
             .field("subclass", &format_args!("0x{:02X}", self.subclass))
             .field("protocol", &format_args!("0x{:02X}", self.protocol))
             .field("speed", &self.speed)
-            .field("manufacturer_string", &self.manufacturer_string)
-            .field("product_string", &self.product_string)
-            .field("serial_number", &self.serial_number);
+            .field("manufacturer_string", &self.manufacturer_string())
+            .field("product_string", &self.product_string())
+            .field("serial_number", &self.serial_number());
 
         #[cfg(target_os = "linux")]
         {
@@ -327,6 +812,8 @@ impl std::fmt::Debug for DeviceInfo {
             s.field("instance_id", &self.instance_id);
             s.field("parent_instance_id", &self.parent_instance_id);
             s.field("location_paths", &self.location_paths);
+            s.field("container_id", &self.container_id);
+            s.field("device_paths", &self.device_paths);
             s.field("port_number", &self.port_number);
             s.field("driver", &self.driver);
         }
@@ -346,8 +833,311 @@ impl std::fmt::Debug for DeviceInfo {
     }
 }
 
+/// *(macOS-only)* Value of an IORegistry property.
+///
+/// Returned by [`DeviceInfo::ioregistry_property`].
+#[cfg(target_os = "macos")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IoRegistryProperty {
+    /// `CFString` value
+    String(String),
+
+    /// `CFNumber` value that fits in an `i64`
+    Number(i64),
+
+    /// `CFBoolean` value
+    Bool(bool),
+
+    /// `CFData` value
+    Data(Vec<u8>),
+}
+
+/// Criteria for selecting devices when listing them.
+///
+/// A device matches if it matches all of the criteria that are set. Use
+/// [`list`][`Self::list`] to list the matching devices:
+///
+/// ```no_run
+/// use nusb::{DeviceFilter, MaybeFuture};
+/// let devices = DeviceFilter::new()
+///     .with_vendor_id(0x1234)
+///     .with_class(0xff)
+///     .list()
+///     .wait()
+///     .unwrap();
+/// ```
+///
+/// Where the platform allows it, devices with a different vendor or
+/// product ID are skipped before reading the rest of their properties,
+/// which is faster than filtering the result of
+/// [`list_devices`][`crate::list_devices`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceFilter {
+    pub(crate) vendor_id: Option<u16>,
+    pub(crate) product_id: Option<u16>,
+    class: Option<u8>,
+    serial_number: Option<String>,
+    driver: Option<String>,
+
+    #[cfg(target_os = "windows")]
+    pub(crate) interface_guid: Option<u128>,
+}
+
+impl DeviceFilter {
+    /// Create a filter that matches all devices.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match devices with the specified vendor ID.
+    pub fn with_vendor_id(mut self, vendor_id: u16) -> Self {
+        self.vendor_id = Some(vendor_id);
+        self
+    }
+
+    /// Match devices with the specified product ID.
+    pub fn with_product_id(mut self, product_id: u16) -> Self {
+        self.product_id = Some(product_id);
+        self
+    }
+
+    /// Match devices with the specified class code, either as the device
+    /// class or as the class of any of its interfaces.
+    ///
+    /// Composite devices usually declare their class per interface, with a
+    /// device class of `0x00` or `0xEF`, so this matches them by the class of
+    /// their functions.
+    pub fn with_class(mut self, class: u8) -> Self {
+        self.class = Some(class);
+        self
+    }
+
+    /// Match devices with the specified serial number.
+    pub fn with_serial_number(mut self, serial_number: impl Into<String>) -> Self {
+        self.serial_number = Some(serial_number.into());
+        self
+    }
+
+    /// Match devices with an interface bound to the specified driver, as
+    /// reported by [`InterfaceInfo::driver`], or on Windows, with the device
+    /// as a whole bound to it.
+    ///
+    /// Driver names are compared case-insensitively, because Windows service
+    /// names are case-insensitive.
+    ///
+    /// ```no_run
+    /// use nusb::{DeviceFilter, MaybeFuture};
+    /// for device in DeviceFilter::new().with_driver("cdc_acm").list().wait().unwrap() {
+    ///     let interfaces = device
+    ///         .interfaces()
+    ///         .filter(|i| i.driver() == Some("cdc_acm"))
+    ///         .map(|i| i.interface_number());
+    ///     println!("{:?}: {:?}", device.id(), interfaces.collect::<Vec<_>>());
+    /// }
+    /// ```
+    pub fn with_driver(mut self, driver: impl Into<String>) -> Self {
+        self.driver = Some(driver.into());
+        self
+    }
+
+    /// *(Windows-only)* Match devices that have a device interface of the
+    /// specified interface class, such as the GUID from the
+    /// `DeviceInterfaceGUIDs` registry value of a WinUSB device, either on the
+    /// device itself or on one of its composite functions.
+    ///
+    /// The GUID is written as a `u128` in the same order as its string
+    /// form, e.g. `0xa5dcbf10_6530_11d2_901f_00c04fb951ed`.
+    #[cfg(target_os = "windows")]
+    pub fn with_interface_guid(mut self, guid: u128) -> Self {
+        self.interface_guid = Some(guid);
+        self
+    }
+
+    /// Returns `true` if the device matches the filter.
+    pub fn matches(&self, device: &DeviceInfo) -> bool {
+        #[cfg(target_os = "windows")]
+        if let Some(guid) = self.interface_guid {
+            if !crate::platform::has_device_interface(device.devinst, guid) {
+                return false;
+            }
+        }
+
+        self.vendor_id.map_or(true, |v| device.vendor_id() == v)
+            && self.product_id.map_or(true, |p| device.product_id() == p)
+            && self.class.map_or(true, |c| {
+                device.class() == c || device.interfaces().any(|i| i.class() == c)
+            })
+            && self
+                .serial_number
+                .as_deref()
+                .map_or(true, |s| device.serial_number().value() == Some(s))
+            && self.driver.as_deref().map_or(true, |name| {
+                let is = |d: Option<&str>| d.is_some_and(|d| d.eq_ignore_ascii_case(name));
+                #[cfg(target_os = "windows")]
+                if is(device.driver()) {
+                    return true;
+                }
+                device.interfaces().any(|i| is(i.driver()))
+            })
+    }
+
+    /// List the connected devices that match the filter.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    pub fn list(
+        &self,
+    ) -> impl MaybeFuture<Output = Result<impl Iterator<Item = DeviceInfo>, Error>> {
+        let filter = self.clone();
+        crate::platform::list_devices_by_id(self.vendor_id, self.product_id)
+            .map(move |devices| Ok(devices?.filter(move |d| filter.matches(d))))
+    }
+
+    /// Watch for connection and disconnection of matching devices.
+    ///
+    /// This is like [`watch_devices`][`crate::watch_devices`], but where
+    /// the platform allows it, events for other devices are discarded
+    /// before reading the device's properties, or are not delivered to the
+    /// process at all. `Connected` events are only returned for devices that
+    /// match all of the criteria.
+    ///
+    /// The class and serial number of a device are not known once it is
+    /// disconnected, so `Disconnected` events are returned for all devices
+    /// with a matching vendor and product ID (and interface GUID on
+    /// Windows), even if they did not match the other criteria.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On macOS, the vendor and product ID are added to the IOKit matching
+    ///   dictionary, so notifications for other devices are not delivered.
+    /// * On Windows, the interface GUID is used as the class of the
+    ///   `CM_Register_Notification` filter, so notifications for other
+    ///   devices are not delivered. The vendor and product ID are checked
+    ///   from the instance ID before reading other properties.
+    /// * On Linux, all uevents are received from the netlink socket, but the
+    ///   vendor and product ID are checked from the uevent before reading
+    ///   the device's properties from sysfs.
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    pub fn watch(&self) -> Result<crate::hotplug::HotplugWatch, Error> {
+        crate::hotplug::HotplugWatch::filtered(self.clone())
+    }
+}
+
+/// A snapshot of the connected devices that can be refreshed incrementally.
+///
+/// [`refresh`][Self::refresh] lists the connected devices again, but only
+/// reads the properties of devices that were not in the previous snapshot.
+/// This makes polling for changes much cheaper than calling
+/// [`list_devices`][`crate::list_devices`] repeatedly on systems with many
+/// devices. Use [`changes`][Self::changes] to find which devices were
+/// connected or disconnected between two snapshots:
+///
+/// ```no_run
+/// use std::{thread, time::Duration};
+/// use nusb::{hotplug::HotplugEvent, DeviceList, MaybeFuture};
+/// let mut devices = DeviceList::new().refresh().wait().unwrap();
+/// loop {
+///     thread::sleep(Duration::from_secs(1));
+///     let new = devices.refresh().wait().unwrap();
+///     for event in new.changes(&devices) {
+///         match event {
+///             HotplugEvent::Connected(d) => println!("connected: {d:?}"),
+///             HotplugEvent::Disconnected(id) => println!("disconnected: {id:?}"),
+///         }
+///     }
+///     devices = new;
+/// }
+/// ```
+///
+/// A device's [`DeviceInfo`] is kept from the snapshot in which it first
+/// appeared, so properties that can change while it remains connected, such
+/// as its cached strings, are not updated. Use
+/// [`watch_devices`][`crate::watch_devices`] to be notified of changes
+/// without polling.
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+#[derive(Debug, Clone, Default)]
+pub struct DeviceList {
+    devices: HashMap<DeviceId, Arc<DeviceInfo>>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+impl DeviceList {
+    /// Create an empty list.
+    ///
+    /// Call [`refresh`][Self::refresh] to list the connected devices.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the devices in the list, in no particular order.
+    pub fn devices(&self) -> impl Iterator<Item = &DeviceInfo> {
+        self.devices.values().map(|d| &**d)
+    }
+
+    /// Get the device with the specified ID, if it is in the list.
+    pub fn get(&self, id: DeviceId) -> Option<&DeviceInfo> {
+        self.devices.get(&id).map(|d| &**d)
+    }
+
+    /// Get the number of devices in the list.
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    /// Returns `true` if the list contains no devices.
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+
+    /// List the connected devices, reusing the information of devices that
+    /// are already in this list.
+    ///
+    /// The information of devices already in this list is shared with the
+    /// new list rather than copied.
+    pub fn refresh(&self) -> impl MaybeFuture<Output = Result<DeviceList, Error>> {
+        let mut known = self.devices.clone();
+        crate::platform::scan_devices(known.keys().copied().collect()).map(move |scanned| {
+            let devices = scanned?
+                .into_iter()
+                .filter_map(|(id, info)| {
+                    let info = match info {
+                        Some(info) => Arc::new(info),
+                        None => known.remove(&id)?,
+                    };
+                    Some((id, info))
+                })
+                .collect();
+            Ok(DeviceList { devices })
+        })
+    }
+
+    /// Get the changes from an `older` list to this one.
+    ///
+    /// Yields [`Disconnected`][`crate::hotplug::HotplugEvent::Disconnected`]
+    /// for devices that are only in `older`, followed by
+    /// [`Connected`][`crate::hotplug::HotplugEvent::Connected`] for devices
+    /// that are only in `self`.
+    pub fn changes<'a>(
+        &'a self,
+        older: &'a DeviceList,
+    ) -> impl Iterator<Item = crate::hotplug::HotplugEvent> + 'a {
+        use crate::hotplug::HotplugEvent;
+        let disconnected = older
+            .devices
+            .keys()
+            .filter(|id| !self.devices.contains_key(id))
+            .map(|&id| HotplugEvent::Disconnected(id));
+        let connected = self
+            .devices
+            .iter()
+            .filter(|(id, _)| !older.devices.contains_key(id))
+            .map(|(_, d)| HotplugEvent::Connected(DeviceInfo::clone(d)));
+        disconnected.chain(connected)
+    }
+}
+
 /// USB connection speed
 #[derive(Copy, Clone, Eq, PartialOrd, Ord, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum Speed {
     /// Low speed (1.5 Mbit)
@@ -362,8 +1152,11 @@ pub enum Speed {
     /// Super speed (5000 Mbit)
     Super,
 
-    /// Super speed (10000 Mbit)
+    /// SuperSpeed Plus (10000 Mbit)
     SuperPlus,
+
+    /// SuperSpeed Plus using two lanes, also known as Gen 2x2 (20000 Mbit)
+    SuperPlusX2,
 }
 
 impl Speed {
@@ -375,6 +1168,7 @@ impl Speed {
             "high" | "480" => Some(Speed::High),
             "super" | "5000" => Some(Speed::Super),
             "super+" | "10000" => Some(Speed::SuperPlus),
+            "20000" => Some(Speed::SuperPlusX2),
             _ => None,
         }
     }
@@ -382,12 +1176,14 @@ impl Speed {
 
 /// Summary information about a device's interface, available before opening a device.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InterfaceInfo {
     pub(crate) interface_number: u8,
     pub(crate) class: u8,
     pub(crate) subclass: u8,
     pub(crate) protocol: u8,
     pub(crate) interface_string: Option<String>,
+    pub(crate) driver: Option<String>,
 }
 
 impl InterfaceInfo {
@@ -415,6 +1211,22 @@ impl InterfaceInfo {
     pub fn interface_string(&self) -> Option<&str> {
         self.interface_string.as_deref()
     }
+
+    /// Name of the driver bound to the interface when the device was
+    /// listed, if any.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// * On Linux, this is the kernel driver, like `cdc_acm` or
+    ///   `usb-storage`, or `usbfs` if the interface is claimed by a program
+    ///   using nusb or libusb.
+    /// * On Windows, this is the service name of the driver of the composite
+    ///   function's device node, like `WinUSB` or `usbser`. For a device
+    ///   that is not composite, it is the driver of the whole device.
+    /// * On macOS, this is always `None`.
+    pub fn driver(&self) -> Option<&str> {
+        self.driver.as_deref()
+    }
 }
 
 // Not derived so that we can format some fields in hex
@@ -426,12 +1238,14 @@ impl std::fmt::Debug for InterfaceInfo {
             .field("subclass", &format_args!("0x{:02X}", self.subclass))
             .field("protocol", &format_args!("0x{:02X}", self.protocol))
             .field("interface_string", &self.interface_string)
+            .field("driver", &self.driver)
             .finish()
     }
 }
 
 /// USB host controller type
 #[derive(Copy, Clone, Eq, PartialOrd, Ord, PartialEq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum UsbControllerType {
     /// xHCI controller (USB 3.0+)
@@ -469,6 +1283,25 @@ impl UsbControllerType {
     }
 }
 
+/// *(Linux-only)* Default authorization of devices connected to a bus.
+///
+/// See [`BusInfo::authorized_default`].
+#[cfg(target_os = "linux")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AuthorizedDefault {
+    /// New devices are not authorized, and must be authorized with
+    /// [`DeviceInfo::set_authorized`] before they can be used.
+    None,
+
+    /// New devices are authorized. This is the kernel's default.
+    All,
+
+    /// Only devices connected to internal ports, as reported by the
+    /// platform firmware, are authorized. Requires Linux 5.11 or later.
+    Internal,
+}
+
 /// Information about a system USB bus.
 ///
 /// Platform-specific fields:
@@ -476,15 +1309,16 @@ impl UsbControllerType {
 /// * Windows: `instance_id`, `parent_instance_id`, `location_paths`, `devinst`, `root_hub_description`
 /// * macOS: `registry_id`, `location_id`, `name`, `provider_class_name`, `class_name`
 #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BusInfo {
-    #[cfg(any(target_os = "linux"))]
+    #[cfg(target_os = "linux")]
     pub(crate) path: SysfsPath,
 
     /// The phony root hub device
-    #[cfg(any(target_os = "linux"))]
+    #[cfg(target_os = "linux")]
     pub(crate) root_hub: DeviceInfo,
 
-    #[cfg(any(target_os = "linux"))]
+    #[cfg(target_os = "linux")]
     pub(crate) busnum: u8,
 
     #[cfg(target_os = "windows")]
@@ -529,7 +1363,7 @@ pub struct BusInfo {
 #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
 impl BusInfo {
     /// *(Linux-only)* Sysfs path for the bus.
-    #[cfg(any(target_os = "linux"))]
+    #[cfg(target_os = "linux")]
     pub fn sysfs_path(&self) -> &std::path::Path {
         &self.path.0
     }
@@ -537,17 +1371,59 @@ impl BusInfo {
     /// *(Linux-only)* Bus number.
     ///
     /// On Linux, the `bus_id` is an integer and this provides the value as `u8`.
-    #[cfg(any(target_os = "linux"))]
+    #[cfg(target_os = "linux")]
     pub fn busnum(&self) -> u8 {
         self.busnum
     }
 
     /// *(Linux-only)* The root hub [`DeviceInfo`] representing the bus.
-    #[cfg(any(target_os = "linux"))]
+    #[cfg(target_os = "linux")]
     pub fn root_hub(&self) -> &DeviceInfo {
         &self.root_hub
     }
 
+    /// *(Linux-only)* Whether devices connected to the bus are authorized by
+    /// default.
+    ///
+    /// This reads the `authorized_default` sysfs attribute of the root hub.
+    #[cfg(target_os = "linux")]
+    pub fn authorized_default(&self) -> Result<AuthorizedDefault, Error> {
+        let v = self
+            .path
+            .read_attr_checked::<i32>("authorized_default", "failed to read authorized_default")?;
+        match v {
+            0 => Ok(AuthorizedDefault::None),
+            1 => Ok(AuthorizedDefault::All),
+            2 => Ok(AuthorizedDefault::Internal),
+            _ => Err(Error::new(
+                crate::ErrorKind::Other,
+                "unknown authorized_default value",
+            )),
+        }
+    }
+
+    /// *(Linux-only)* Set whether devices connected to the bus are authorized
+    /// by default.
+    ///
+    /// This writes the `authorized_default` sysfs attribute of the root hub,
+    /// which usually requires root privileges. It affects devices connected
+    /// afterwards, and does not change the authorization of devices that are
+    /// already connected. Use [`DeviceInfo::set_authorized`] to authorize
+    /// them individually.
+    #[cfg(target_os = "linux")]
+    pub fn set_authorized_default(&self, default: AuthorizedDefault) -> Result<(), Error> {
+        let value = match default {
+            AuthorizedDefault::None => "0",
+            AuthorizedDefault::All => "1",
+            AuthorizedDefault::Internal => "2",
+        };
+        self.path.write_attr_checked(
+            "authorized_default",
+            value,
+            "failed to set authorized_default",
+        )
+    }
+
     /// *(Windows-only)* Instance ID path of this device
     #[cfg(target_os = "windows")]
     pub fn instance_id(&self) -> &OsStr {
@@ -627,9 +1503,9 @@ impl BusInfo {
     /// * macOS: The [IONameMatched](https://developer.apple.com/documentation/bundleresources/information_property_list/ionamematch) key of the IOService entry.
     /// * Windows: Description field of the root hub device. How the bus will appear in Device Manager.
     pub fn system_name(&self) -> Option<&str> {
-        #[cfg(any(target_os = "linux"))]
+        #[cfg(target_os = "linux")]
         {
-            self.root_hub.product_string()
+            self.root_hub.product_string().value()
         }
 
         #[cfg(target_os = "windows")]
@@ -649,7 +1525,7 @@ impl std::fmt::Debug for BusInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut s = f.debug_struct("BusInfo");
 
-        #[cfg(any(target_os = "linux"))]
+        #[cfg(target_os = "linux")]
         {
             s.field("sysfs_path", &self.path);
             s.field("busnum", &self.busnum);
@@ -681,3 +1557,31 @@ impl std::fmt::Debug for BusInfo {
         s.finish()
     }
 }
+
+#[test]
+fn stored_string_index() {
+    let s = |v: Option<&str>, i| StoredString::new(v.map(String::from), i);
+    assert_eq!(s(Some("Acme"), Some(1)).get(), CachedString::Value("Acme"));
+    assert_eq!(s(Some("Acme"), None).get(), CachedString::Value("Acme"));
+    assert_eq!(s(None, Some(0)).get(), CachedString::Absent);
+    assert_eq!(s(None, Some(2)).get(), CachedString::Unknown);
+    assert_eq!(s(None, None).get().value(), None);
+}
+
+#[test]
+fn port_id_string() {
+    let id: PortId = "003-1.4.2".parse().unwrap();
+    assert_eq!(id.bus_id(), "003");
+    assert_eq!(id.port_chain(), &[1, 4, 2]);
+    assert_eq!(id.to_string(), "003-1.4.2");
+
+    let root: PortId = "003".parse().unwrap();
+    assert_eq!(root.port_chain(), &[] as &[u8]);
+    assert_eq!(root.to_string(), "003");
+
+    let windows: PortId = "PCIROOT(0)#PCI(1400)#USBROOT(0)-2.1".parse().unwrap();
+    assert_eq!(windows.bus_id(), "PCIROOT(0)#PCI(1400)#USBROOT(0)");
+    assert_eq!(windows.port_chain(), &[2, 1]);
+
+    assert!("".parse::<PortId>().is_err());
+}
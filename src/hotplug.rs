@@ -1,17 +1,63 @@
 //! Types for receiving notifications when USB devices are connected or
-//! disconnected from the system.
+//! disconnected from the system, or when the state of an open device
+//! changes.
 //!
 //! See [`super::watch_devices`] for a usage example.
 
+use std::{
+    collections::{HashSet, VecDeque},
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
 use futures_core::Stream;
+use log::{debug, warn};
 
-use crate::{DeviceId, DeviceInfo};
+use crate::{
+    platform,
+    timer::{block_on_timeout, Sleep},
+    DeviceFilter, DeviceId, DeviceInfo, Error, ErrorKind, MaybeFuture,
+};
 
 /// Stream of device connection / disconnection events.
 ///
 /// Call [`super::watch_devices`] to begin watching device
-/// events and create a `HotplugWatch`.
-pub struct HotplugWatch(pub(crate) crate::platform::HotplugWatch);
+/// events and create a `HotplugWatch`, or
+/// [`super::list_and_watch_devices`] to also list the connected devices.
+/// Use [`DeviceFilter::watch`] to only watch for matching devices.
+pub struct HotplugWatch {
+    inner: crate::platform::HotplugWatch,
+
+    /// Criteria for `Connected` events, if created with
+    /// [`DeviceFilter::watch`].
+    filter: Option<DeviceFilter>,
+
+    /// Devices known to be connected, if the watch was created along with a
+    /// device list. Used to skip events that are already reflected in the
+    /// list.
+    known: Option<HashSet<DeviceId>>,
+}
+
+impl HotplugWatch {
+    pub(crate) fn new() -> Result<Self, Error> {
+        Ok(HotplugWatch {
+            inner: platform::HotplugWatch::new(&DeviceFilter::new())?,
+            filter: None,
+            known: None,
+        })
+    }
+
+    pub(crate) fn filtered(filter: DeviceFilter) -> Result<Self, Error> {
+        Ok(HotplugWatch {
+            inner: platform::HotplugWatch::new(&filter)?,
+            filter: Some(filter),
+            known: None,
+        })
+    }
+}
 
 impl Stream for HotplugWatch {
     type Item = HotplugEvent;
@@ -20,7 +66,32 @@ impl Stream for HotplugWatch {
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
-        self.0.poll_next(cx).map(Some)
+        let this = &mut *self;
+        loop {
+            let event = ready!(this.inner.poll_next(cx));
+            if let (Some(filter), HotplugEvent::Connected(d)) = (&this.filter, &event) {
+                if !filter.matches(d) {
+                    continue;
+                }
+            }
+            let Some(known) = &mut this.known else {
+                return Poll::Ready(Some(event));
+            };
+            let is_new = match &event {
+                HotplugEvent::Connected(d) => known.insert(d.id()),
+                HotplugEvent::Disconnected(id) => known.remove(id),
+            };
+            if is_new {
+                return Poll::Ready(Some(event));
+            }
+            debug!("Skipping hotplug event already reflected in device list: {event:?}");
+        }
+    }
+}
+
+impl std::fmt::Debug for HotplugWatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HotplugWatch").finish_non_exhaustive()
     }
 }
 
@@ -35,8 +106,230 @@ pub enum HotplugEvent {
     Disconnected(DeviceId),
 }
 
+/// List the connected devices and start watching for changes, such that
+/// every change after the list is reported exactly once.
+pub(crate) fn list_and_watch() -> Result<(Vec<DeviceInfo>, HotplugWatch), Error> {
+    // Start watching before listing so that a device connected in between is
+    // not missed. Events for changes already seen by the list are skipped.
+    let mut watch = HotplugWatch::new()?;
+    let devices: Vec<DeviceInfo> = crate::list_devices().wait()?.collect();
+    watch.known = Some(devices.iter().map(|d| d.id()).collect());
+    Ok((devices, watch))
+}
+
+/// Block until a device matching `wanted` is connected.
+///
+/// If `previous` is connected, devices are only matched once it has
+/// disconnected, so that the old instance of a re-enumerating device is not
+/// mistaken for the new one.
+pub(crate) fn wait_for(
+    mut wanted: impl FnMut(&DeviceInfo) -> bool,
+    previous: Option<DeviceId>,
+    timeout: Duration,
+) -> Result<DeviceInfo, Error> {
+    // Start watching before listing so that a device connected in between is
+    // not missed.
+    let mut watch = platform::HotplugWatch::new(&DeviceFilter::new())?;
+
+    let mut disconnected = true;
+    for device in crate::list_devices().wait()? {
+        if Some(device.id()) == previous {
+            disconnected = false;
+        } else if wanted(&device) {
+            return Ok(device);
+        }
+    }
+
+    block_on_timeout(timeout, |cx| loop {
+        match watch.poll_next(cx) {
+            Poll::Ready(HotplugEvent::Connected(device)) if disconnected && wanted(&device) => {
+                return Poll::Ready(device);
+            }
+            Poll::Ready(HotplugEvent::Disconnected(id)) if Some(id) == previous => {
+                debug!("Previous device {id:?} disconnected");
+                disconnected = true;
+            }
+            Poll::Ready(_) => {}
+            Poll::Pending => return Poll::Pending,
+        }
+    })
+    .ok_or_else(|| {
+        Error::new(
+            ErrorKind::Disconnected,
+            "device was not connected before the timeout",
+        )
+    })
+}
+
+/// Stream of state changes of an open device.
+///
+/// Call [`Device::watch_state`][`crate::Device::watch_state`] to create a
+/// `DeviceStateWatch`.
+///
+/// The OS does not notify applications of most of these changes, so the
+/// device's state is polled at an [interval][`Self::with_interval`], one
+/// second by default. Polling does not perform IO on the bus, so it does not
+/// wake a suspended device. Changes that are reverted between two polls are
+/// not reported.
+///
+/// The stream ends after returning [`DeviceStateEvent::Disconnected`].
+pub struct DeviceStateWatch {
+    device: Arc<platform::Device>,
+    interval: Duration,
+    last: Option<DeviceState>,
+    sleep: Option<Sleep>,
+    pending: VecDeque<DeviceStateEvent>,
+    done: bool,
+}
+
+/// Snapshot of the state of a device, as reported by the platform backend.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct DeviceState {
+    /// Whether the device is suspended, or `None` if unknown.
+    pub(crate) suspended: Option<bool>,
+
+    /// The active configuration value, or `None` if unknown.
+    pub(crate) configuration: Option<u8>,
+}
+
+impl DeviceStateWatch {
+    pub(crate) fn new(device: Arc<platform::Device>) -> Self {
+        DeviceStateWatch {
+            device,
+            interval: Duration::from_secs(1),
+            last: None,
+            sleep: None,
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Set the interval at which the device's state is polled.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    fn update(&mut self) {
+        let state = match self.device.state() {
+            Ok(state) => state,
+            Err(e) if e.kind() == ErrorKind::Disconnected => {
+                self.pending.push_back(DeviceStateEvent::Disconnected);
+                self.done = true;
+                return;
+            }
+            Err(e) => {
+                warn!("Failed to query device state: {e}");
+                return;
+            }
+        };
+
+        if let Some(last) = self.last {
+            self.pending.extend(state_changes(last, state));
+        }
+        self.last = Some(state);
+    }
+}
+
+fn state_changes(last: DeviceState, state: DeviceState) -> impl Iterator<Item = DeviceStateEvent> {
+    let power = match (last.suspended, state.suspended) {
+        (Some(false), Some(true)) => Some(DeviceStateEvent::Suspended),
+        (Some(true), Some(false)) => Some(DeviceStateEvent::Resumed),
+        _ => None,
+    };
+
+    let configuration = match (last.configuration, state.configuration) {
+        (Some(a), Some(b)) if a != b => Some(DeviceStateEvent::ConfigurationChanged(b)),
+        _ => None,
+    };
+
+    power.into_iter().chain(configuration)
+}
+
+impl Stream for DeviceStateWatch {
+    type Item = DeviceStateEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Poll::Ready(Some(event));
+            }
+
+            if self.done {
+                return Poll::Ready(None);
+            }
+
+            if let Some(sleep) = self.sleep.as_mut() {
+                if Pin::new(sleep).poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+            }
+
+            self.sleep = Some(Sleep::new(Instant::now() + self.interval));
+            self.update();
+        }
+    }
+}
+
+impl std::fmt::Debug for DeviceStateWatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceStateWatch")
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Event returned from the [`DeviceStateWatch`] stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DeviceStateEvent {
+    /// The device was suspended by the OS, for example by runtime power
+    /// management.
+    Suspended,
+
+    /// The device was resumed after being suspended.
+    Resumed,
+
+    /// The active configuration was changed by another program or driver.
+    ///
+    /// Contains the new configuration value, or 0 if the device is
+    /// unconfigured. Interfaces claimed in the previous configuration are
+    /// no longer usable.
+    ConfigurationChanged(u8),
+
+    /// The device was disconnected or removed by the OS.
+    Disconnected,
+}
+
 #[test]
 fn assert_send_sync() {
     fn require_send_sync<T: Send + Sync>() {}
     require_send_sync::<HotplugWatch>();
+    require_send_sync::<DeviceStateWatch>();
+}
+
+#[test]
+fn device_state_changes() {
+    let state = |suspended, configuration| DeviceState {
+        suspended,
+        configuration,
+    };
+
+    let changes = |a, b| state_changes(a, b).collect::<Vec<_>>();
+    assert_eq!(
+        changes(state(Some(false), Some(1)), state(Some(false), Some(1))),
+        []
+    );
+    assert_eq!(
+        changes(state(Some(false), Some(1)), state(Some(true), Some(1))),
+        [DeviceStateEvent::Suspended]
+    );
+    assert_eq!(
+        changes(state(Some(true), Some(1)), state(Some(false), Some(2))),
+        [
+            DeviceStateEvent::Resumed,
+            DeviceStateEvent::ConfigurationChanged(2)
+        ]
+    );
+    assert_eq!(changes(state(None, None), state(Some(true), Some(2))), []);
 }
@@ -0,0 +1,409 @@
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    future::Future,
+    ops::Range,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures_core::Stream;
+
+use crate::{
+    timer::Sleep,
+    transfer::{Buffer, BufferPool, In, IsoCompletion, Isochronous, TransferError},
+    Endpoint,
+};
+
+/// A packet received by an [`IsoStream`].
+///
+/// The packet's data is not copied: it refers to the buffer of the transfer
+/// it was received in, which is shared by all packets of that transfer. The
+/// buffer is reused for a new transfer once all of its packets are dropped,
+/// so avoid holding on to packets for longer than necessary.
+#[derive(Clone)]
+pub struct IsoPacket {
+    buffer: Arc<Buffer>,
+    range: Range<usize>,
+
+    /// Index of this packet among all packets requested by the stream,
+    /// counting dropped packets.
+    ///
+    /// Gaps in the sequence indicate dropped packets, which allows the
+    /// consumer to keep track of time, for example by inserting silence in an
+    /// audio stream.
+    pub sequence: u64,
+
+    /// Estimated time at which the packet was received.
+    ///
+    /// This is smoothed from the [completion times][IsoCompletion::completed_at]
+    /// of the stream's transfers, spacing packets by the packet period
+    /// measured over the life of the stream, so that it does not include
+    /// the scheduling jitter of individual completions.
+    pub timestamp: Instant,
+}
+
+impl IsoPacket {
+    /// Data received in the packet.
+    pub fn data(&self) -> &[u8] {
+        &self.buffer[self.range.clone()]
+    }
+}
+
+impl Debug for IsoPacket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IsoPacket")
+            .field("data", &self.data())
+            .field("sequence", &self.sequence)
+            .field("timestamp", &self.timestamp)
+            .finish()
+    }
+}
+
+/// Wrapper for an Isochronous IN [`Endpoint`](crate::Endpoint) implementing
+/// [`Stream`] of received packets.
+///
+/// This keeps a configurable number of transfers pending, each consisting of
+/// a fixed number of packets, and resubmits them as they complete, reusing
+/// their buffers. The packets of each completed transfer are yielded in order,
+/// one [`IsoPacket`] per packet received successfully.
+///
+/// By default, packets are yielded as soon as their transfer completes, in
+/// bursts of one transfer. With a [latency](Self::set_latency), each packet is
+/// instead held until that long after its smoothed
+/// [timestamp](IsoPacket::timestamp), so that packets are yielded at the rate
+/// they were received despite jitter in when transfers complete.
+///
+/// Packets that fail, for example due to a missed service interval or a CRC
+/// error, are skipped and counted by
+/// [`dropped_packets`](Self::dropped_packets). An error affecting a transfer
+/// as a whole is yielded as an `Err` item. The stream ends after yielding
+/// [`TransferError::Disconnected`], or once all transfers have completed
+/// after [`cancel_all`](Self::cancel_all).
+///
+/// Only available on Linux, like isochronous transfers.
+pub struct IsoStream {
+    endpoint: Endpoint<Isochronous, In>,
+    packet_len: usize,
+    packets_per_transfer: usize,
+    num_transfers: usize,
+    pool: BufferPool,
+    ready: VecDeque<IsoPacket>,
+    shared: Vec<Arc<Buffer>>,
+    latency: Duration,
+    clock: Option<IsoClock>,
+    sleep: Option<Sleep>,
+    next_sequence: u64,
+    dropped: u64,
+    ended: bool,
+}
+
+impl IsoStream {
+    /// Create a new `IsoStream` wrapping the given endpoint.
+    ///
+    /// Each transfer requests `packets_per_transfer` packets of up to
+    /// `packet_len` bytes. More packets per transfer reduces overhead, but
+    /// increases latency.
+    ///
+    /// `packet_len` is typically the endpoint's
+    /// [`max_bytes_per_interval`][Endpoint::max_bytes_per_interval], so that
+    /// high-bandwidth endpoints receive all transactions of each microframe.
+    ///
+    /// ### Panics
+    /// * If `packet_len` is 0, or `packets_per_transfer` is not between 1 and
+    ///   128.
+    pub fn new(
+        endpoint: Endpoint<Isochronous, In>,
+        packet_len: usize,
+        packets_per_transfer: usize,
+    ) -> Self {
+        assert!(packet_len > 0, "packet_len must be greater than zero");
+        assert!(
+            (1..=crate::platform::MAX_ISO_PACKETS).contains(&packets_per_transfer),
+            "packets_per_transfer must be between 1 and 128"
+        );
+
+        Self {
+            pool: BufferPool::new(packet_len * packets_per_transfer),
+            endpoint,
+            packet_len,
+            packets_per_transfer,
+            num_transfers: 2,
+            ready: VecDeque::new(),
+            shared: Vec::new(),
+            latency: Duration::ZERO,
+            clock: None,
+            sleep: None,
+            next_sequence: 0,
+            dropped: 0,
+            ended: false,
+        }
+    }
+
+    /// Set the number of concurrent transfers.
+    ///
+    /// Isochronous packets are lost if no transfer is pending during their
+    /// service interval, so at least 2 transfers (default) are needed to
+    /// avoid gaps while the application processes a completed transfer.
+    /// More transfers absorb longer scheduling delays in the application.
+    ///
+    /// Transfers are submitted when the stream is next polled. Decreasing the
+    /// number does not cancel pending transfers.
+    ///
+    /// ### Panics
+    /// * If `num_transfers` is zero.
+    pub fn set_num_transfers(&mut self, num_transfers: usize) {
+        assert!(num_transfers > 0, "num_transfers must be greater than zero");
+        self.num_transfers = num_transfers;
+    }
+
+    /// Set the number of concurrent transfers.
+    ///
+    /// See [Self::set_num_transfers] (this version is for method chaining).
+    pub fn with_num_transfers(mut self, num_transfers: usize) -> Self {
+        self.set_num_transfers(num_transfers);
+        self
+    }
+
+    /// Set the de-jitter latency.
+    ///
+    /// Each packet is held until `latency` after its
+    /// [timestamp](IsoPacket::timestamp). This should exceed the duration of
+    /// one transfer plus the expected delay in completing it; packets that
+    /// arrive later than that are yielded immediately. Higher latency absorbs
+    /// more jitter, and requires [more transfers](Self::set_num_transfers) to
+    /// avoid dropping packets while they are held.
+    ///
+    /// The default is zero, which yields packets as soon as their transfer
+    /// completes.
+    pub fn set_latency(&mut self, latency: Duration) {
+        self.latency = latency;
+    }
+
+    /// Set the de-jitter latency.
+    ///
+    /// See [Self::set_latency] (this version is for method chaining).
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.set_latency(latency);
+        self
+    }
+
+    /// Get the number of packets that were not received successfully.
+    pub fn dropped_packets(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Cancel all pending transfers.
+    ///
+    /// No further transfers will be submitted. Packets already received are
+    /// still yielded without waiting for the latency, and then the stream
+    /// ends.
+    pub fn cancel_all(&mut self) {
+        self.ended = true;
+        self.endpoint.cancel_all();
+    }
+
+    /// Destroy this `IsoStream` and return the underlying [`Endpoint`].
+    ///
+    /// Any pending transfers are not cancelled, and packets received but not
+    /// yet yielded are discarded.
+    pub fn into_inner(self) -> Endpoint<Isochronous, In> {
+        self.endpoint
+    }
+
+    fn submit_one(&mut self) {
+        self.reclaim_shared();
+        let buf = self
+            .pool
+            .take()
+            .unwrap_or_else(|| self.endpoint.allocate(self.pool.buffer_size()));
+        self.endpoint.submit(buf, self.packet_len);
+    }
+
+    /// Return buffers whose packets have all been dropped to the pool.
+    fn reclaim_shared(&mut self) {
+        let mut i = 0;
+        while i < self.shared.len() {
+            if Arc::strong_count(&self.shared[i]) == 1 {
+                if let Ok(buf) = Arc::try_unwrap(self.shared.swap_remove(i)) {
+                    self.pool.put(buf);
+                }
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    fn handle_completion(&mut self, c: IsoCompletion) -> Result<(), TransferError> {
+        let completed_at = c.completed_at.unwrap_or_else(Instant::now);
+        let sequence = self.next_sequence;
+        self.next_sequence += self.packets_per_transfer as u64;
+
+        let res = match c.status {
+            Ok(()) => {
+                let clock = self
+                    .clock
+                    .get_or_insert_with(|| IsoClock::new(completed_at, self.next_sequence));
+                clock.update(completed_at, self.next_sequence);
+                let buffer = Arc::new(c.buffer);
+                for (i, packet) in c.packets.iter().enumerate() {
+                    if packet.status.is_ok() {
+                        self.ready.push_back(IsoPacket {
+                            buffer: buffer.clone(),
+                            range: packet.offset..packet.offset + packet.actual_len,
+                            sequence: sequence + i as u64,
+                            timestamp: clock.time(sequence + i as u64),
+                        });
+                    } else {
+                        self.dropped += 1;
+                    }
+                }
+                self.shared.push(buffer);
+                return Ok(());
+            }
+            Err(TransferError::Cancelled) => Ok(()),
+            Err(e) => {
+                // The schedule restarts after the error.
+                self.clock = None;
+                self.dropped += self.packets_per_transfer as u64;
+                if e == TransferError::Disconnected {
+                    self.ended = true;
+                }
+                Err(e)
+            }
+        };
+
+        self.pool.put(c.buffer);
+        res
+    }
+}
+
+impl Stream for IsoStream {
+    type Item = Result<IsoPacket, TransferError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = Pin::into_inner(self);
+
+        loop {
+            let due = match this.ready.front() {
+                Some(p) if this.ended || p.timestamp + this.latency <= Instant::now() => {
+                    this.sleep = None;
+                    return Poll::Ready(this.ready.pop_front().map(Ok));
+                }
+                Some(p) => Some(p.timestamp + this.latency),
+                None => None,
+            };
+
+            if !this.ended {
+                while this.endpoint.pending() < this.num_transfers {
+                    this.submit_one();
+                }
+            }
+
+            if this.endpoint.pending() == 0 {
+                return Poll::Ready(None);
+            }
+
+            let c = match (this.endpoint.poll_next_complete(cx), due) {
+                (Poll::Ready(c), _) => c,
+                (Poll::Pending, Some(due)) => {
+                    let sleep = this.sleep.get_or_insert_with(|| Sleep::new(due));
+                    ready!(Pin::new(sleep).poll(cx));
+                    continue;
+                }
+                (Poll::Pending, None) => return Poll::Pending,
+            };
+            if let Err(e) = this.handle_completion(c) {
+                return Poll::Ready(Some(Err(e)));
+            }
+        }
+    }
+}
+
+/// Maps packet sequence numbers to smoothed receive times.
+///
+/// Completions are reported late by a varying scheduling delay, but never
+/// early. The reference point follows completions that arrive earlier than
+/// predicted immediately and later ones slowly, which tracks drift and gaps
+/// in the schedule without following the jitter of each completion.
+struct IsoClock {
+    /// Completion time and end sequence number of the first transfer.
+    first: (Instant, u64),
+
+    /// Predicted completion time and end sequence number of the most recent
+    /// transfer.
+    reference: (Instant, u64),
+
+    /// Average packet period since the first transfer, once a second
+    /// transfer has completed.
+    period: Option<Duration>,
+}
+
+impl IsoClock {
+    fn new(completed_at: Instant, end: u64) -> Self {
+        IsoClock {
+            first: (completed_at, end),
+            reference: (completed_at, end),
+            period: None,
+        }
+    }
+
+    /// Account for a transfer that completed at `completed_at`, where `end`
+    /// is the sequence number following its last packet.
+    fn update(&mut self, completed_at: Instant, end: u64) {
+        let (first_at, first_end) = self.first;
+        if end <= first_end {
+            return;
+        }
+        let elapsed = completed_at.saturating_duration_since(first_at);
+        self.period = Some(elapsed.div_f64((end - first_end) as f64));
+
+        let predicted = self.time(end - 1);
+        self.reference = match completed_at.checked_duration_since(predicted) {
+            Some(late) => (predicted + late / 16, end),
+            None => (completed_at, end),
+        };
+    }
+
+    /// Smoothed time at which packet `sequence` was received.
+    fn time(&self, sequence: u64) -> Instant {
+        let (at, end) = self.reference;
+        let Some(period) = self.period else {
+            return at;
+        };
+        let packet_end = sequence + 1;
+        if packet_end >= end {
+            at + period.mul_f64((packet_end - end) as f64)
+        } else {
+            at - period.mul_f64((end - packet_end) as f64)
+        }
+    }
+}
+
+#[test]
+fn iso_clock_smooths_jitter() {
+    let ms = |n: u64| Duration::from_millis(n);
+    let start = Instant::now();
+
+    // Transfers of 8 packets of 1 ms, the first of which completes at
+    // `start`, and the others up to 3 ms late.
+    let mut clock = IsoClock::new(start, 8);
+    let mut end = 8;
+    for delay in [3, 0, 2, 1].repeat(25) {
+        end += 8;
+        clock.update(start + ms(end - 8 + delay), end);
+    }
+
+    // Packets are spaced evenly, close to their nominal time.
+    let spacing = clock.time(end - 1) - clock.time(end - 2);
+    assert!(spacing > ms(1) && spacing < ms(1) + Duration::from_micros(50));
+    let nominal = start + ms(end - 10);
+    let time = clock.time(end - 3);
+    assert!(time > nominal - ms(1) && time < nominal + ms(1));
+
+    // An early completion moves the clock back.
+    clock.update(start + ms(end), end + 8);
+    assert_eq!(clock.time(end + 7), start + ms(end));
+}
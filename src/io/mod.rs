@@ -4,6 +4,14 @@
 //! These types wrap an [`Endpoint`](crate::Endpoint) and manage transfers to
 //! provide a higher-level buffered API.
 //!
+//! [`EndpointStream`] and [`EndpointSink`] instead implement the `Stream` and
+//! `Sink` traits from `futures`, preserving transfer boundaries.
+//! [`BulkInPipe`] keeps a Bulk IN endpoint continuously busy for data
+//! acquisition, retrying transient errors, and [`BulkOutPipe`] streams data
+//! to a Bulk OUT endpoint without gaps. On Linux,
+//! `IsoStream` provides a `Stream` of packets received from an Isochronous IN
+//! endpoint.
+//!
 //! ## Examples
 //!
 //! ### Request-response
@@ -39,3 +47,14 @@ pub use read::*;
 
 mod write;
 pub use write::*;
+
+mod stream;
+pub use stream::*;
+
+mod pipe;
+pub use pipe::*;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod iso;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use iso::*;
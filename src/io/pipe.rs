@@ -0,0 +1,558 @@
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures_core::Stream;
+
+use crate::{
+    transfer::{Buffer, BufferPool, Bulk, Completion, In, Out, TransferError},
+    Endpoint,
+};
+
+/// A chunk of data received by a [`BulkInPipe`].
+#[derive(Debug)]
+pub struct BulkChunk {
+    /// Data received in the transfer.
+    ///
+    /// Pass the buffer back to [`BulkInPipe::recycle`] once you are done with
+    /// it to reuse it for a subsequent transfer.
+    pub data: Buffer,
+
+    /// Index of this transfer among all transfers submitted by the pipe,
+    /// counting transfers that failed.
+    ///
+    /// Gaps in the sequence indicate data lost to transient errors.
+    pub sequence: u64,
+}
+
+/// Managed continuous reader for a Bulk IN [`Endpoint`](crate::Endpoint).
+///
+/// This keeps a fixed number of transfers of a fixed size pending at all
+/// times, resubmitting a transfer as soon as one completes, so that the
+/// device can keep sending data while the application processes a completed
+/// transfer. Each successful transfer is returned as a [`BulkChunk`] from
+/// [`read`](Self::read), [`poll_read`](Self::poll_read), or the [`Stream`]
+/// implementation.
+///
+/// Transient errors ([`TransferError::Fault`], [`TransferError::Overflow`],
+/// [`TransferError::TimedOut`] and [`TransferError::Unknown`]) are counted by
+/// [`transient_errors`](Self::transient_errors) and the transfer is
+/// resubmitted, leaving a gap in [`BulkChunk::sequence`]. An error is only
+/// returned if more than
+/// [`max_consecutive_errors`](Self::set_max_consecutive_errors) transient
+/// errors occur in a row.
+///
+/// [`TransferError::Stall`] and [`TransferError::Disconnected`] are returned
+/// and end the pipe: no further transfers are submitted, and the pipe ends
+/// once the remaining pending transfers have completed. A cancelled transfer
+/// also ends the pipe, without returning an error. Other errors are returned
+/// without ending the pipe.
+pub struct BulkInPipe {
+    endpoint: Endpoint<Bulk, In>,
+    num_transfers: usize,
+    pool: BufferPool,
+    next_sequence: u64,
+    transient_errors: u64,
+    consecutive_errors: usize,
+    max_consecutive_errors: usize,
+    ended: bool,
+}
+
+impl BulkInPipe {
+    /// Create a new `BulkInPipe` wrapping the given endpoint, keeping
+    /// `num_transfers` transfers of `transfer_size` bytes pending.
+    ///
+    /// The transfer size will be rounded up to the next multiple of the
+    /// endpoint's max packet size. The transfers are submitted immediately.
+    ///
+    /// ### Panics
+    /// * If `num_transfers` is zero.
+    pub fn new(endpoint: Endpoint<Bulk, In>, num_transfers: usize, transfer_size: usize) -> Self {
+        assert!(num_transfers > 0, "num_transfers must be greater than zero");
+        let packet_size = endpoint.max_packet_size();
+        let transfer_size = (transfer_size.div_ceil(packet_size)).max(1) * packet_size;
+
+        let mut pipe = Self {
+            endpoint,
+            num_transfers,
+            pool: BufferPool::new(transfer_size),
+            next_sequence: 0,
+            transient_errors: 0,
+            consecutive_errors: 0,
+            max_consecutive_errors: 8,
+            ended: false,
+        };
+        pipe.fill();
+        pipe
+    }
+
+    /// Set the number of consecutive transient errors that are retried
+    /// before the error is returned.
+    ///
+    /// The default is 8. A value of 0 returns every transient error. After an
+    /// error is returned, the pipe continues and the count starts again.
+    pub fn set_max_consecutive_errors(&mut self, max: usize) {
+        self.max_consecutive_errors = max;
+    }
+
+    /// Set the number of consecutive transient errors that are retried
+    /// before the error is returned.
+    ///
+    /// See [Self::set_max_consecutive_errors] (this version is for method
+    /// chaining).
+    pub fn with_max_consecutive_errors(mut self, max: usize) -> Self {
+        self.set_max_consecutive_errors(max);
+        self
+    }
+
+    /// Get the number of transfers that failed with a transient error and
+    /// were resubmitted.
+    pub fn transient_errors(&self) -> u64 {
+        self.transient_errors
+    }
+
+    /// Cancel all pending transfers.
+    ///
+    /// No further transfers will be submitted, and the pipe ends once the
+    /// cancelled transfers have been returned. Data received by transfers
+    /// that completed before being cancelled is still returned.
+    pub fn cancel_all(&mut self) {
+        self.ended = true;
+        self.endpoint.cancel_all();
+    }
+
+    /// Return the buffer of a [`BulkChunk`] to be reused for a subsequent
+    /// transfer.
+    ///
+    /// Buffers too small for this pipe's transfer size are dropped.
+    pub fn recycle(&mut self, buf: Buffer) {
+        self.pool.put(buf);
+    }
+
+    /// Destroy this `BulkInPipe` and return the underlying [`Endpoint`].
+    ///
+    /// Any pending transfers are not cancelled.
+    pub fn into_inner(self) -> Endpoint<Bulk, In> {
+        self.endpoint
+    }
+
+    /// Block for up to `timeout` waiting for the next chunk of data.
+    ///
+    /// Returns `None` once the pipe has ended, or
+    /// `Some(Err(TransferError::TimedOut))` if no data was received before
+    /// the timeout. Transfers are not cancelled when the timeout is reached.
+    pub fn read(&mut self, timeout: Duration) -> Option<Result<BulkChunk, TransferError>> {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            if self.endpoint.pending() == 0 {
+                return None;
+            }
+
+            let remaining =
+                deadline.map_or(timeout, |d| d.saturating_duration_since(Instant::now()));
+            let Some(c) = self.endpoint.wait_next_complete(remaining) else {
+                return Some(Err(TransferError::TimedOut));
+            };
+            if let Some(res) = self.handle_completion(c) {
+                return Some(res);
+            }
+        }
+    }
+
+    /// Poll for the next chunk of data.
+    ///
+    /// Returns `Poll::Ready(None)` once the pipe has ended.
+    pub fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<BulkChunk, TransferError>>> {
+        loop {
+            if self.endpoint.pending() == 0 {
+                return Poll::Ready(None);
+            }
+
+            let c = ready!(self.endpoint.poll_next_complete(cx));
+            if let Some(res) = self.handle_completion(c) {
+                return Poll::Ready(Some(res));
+            }
+        }
+    }
+
+    fn fill(&mut self) {
+        while !self.ended && self.endpoint.pending() < self.num_transfers {
+            let buf = self
+                .pool
+                .take()
+                .unwrap_or_else(|| self.endpoint.allocate(self.pool.buffer_size()));
+            self.endpoint.submit(buf);
+        }
+    }
+
+    fn handle_completion(&mut self, c: Completion) -> Option<Result<BulkChunk, TransferError>> {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        let res = match c.status {
+            Ok(()) => {
+                self.consecutive_errors = 0;
+                self.fill();
+                return Some(Ok(BulkChunk {
+                    data: c.buffer,
+                    sequence,
+                }));
+            }
+            Err(TransferError::Cancelled) => {
+                self.ended = true;
+                None
+            }
+            Err(e @ (TransferError::Stall | TransferError::Disconnected)) => {
+                self.ended = true;
+                Some(Err(e))
+            }
+            Err(
+                e @ (TransferError::Fault(_)
+                | TransferError::Overflow
+                | TransferError::TimedOut
+                | TransferError::Unknown(_)),
+            ) => {
+                self.transient_errors += 1;
+                self.consecutive_errors += 1;
+                if self.consecutive_errors > self.max_consecutive_errors {
+                    self.consecutive_errors = 0;
+                    Some(Err(e))
+                } else {
+                    None
+                }
+            }
+            Err(e) => Some(Err(e)),
+        };
+
+        self.pool.put(c.buffer);
+        self.fill();
+        res
+    }
+}
+
+impl Stream for BulkInPipe {
+    type Item = Result<BulkChunk, TransferError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::into_inner(self).poll_read(cx)
+    }
+}
+
+/// Managed continuous writer for a Bulk OUT [`Endpoint`](crate::Endpoint).
+///
+/// This keeps a fixed number of transfers of a fixed size pending, refilling
+/// each buffer from a source closure as soon as its transfer completes, for
+/// gapless streaming to devices such as audio outputs or signal generators.
+/// Drive the pipe with [`run`](Self::run) or [`poll_run`](Self::poll_run).
+///
+/// The source is called with an empty buffer of the pipe's transfer size and
+/// should append the next data to it. Leaving the buffer empty ends the
+/// stream: no further transfers are submitted, and the pipe finishes once the
+/// pending transfers complete. The source should not block in an async
+/// context.
+///
+/// If all pending transfers complete before the pipe is polled to refill
+/// them, the device ran out of data and an underrun is counted by
+/// [`underruns`](Self::underruns). Use more transfers to absorb longer delays
+/// in polling the pipe.
+pub struct BulkOutPipe {
+    endpoint: Endpoint<Bulk, Out>,
+    state: OutFill,
+}
+
+/// Endpoint operations used by [`OutFill`], implemented by a fake endpoint in
+/// tests.
+trait OutEndpoint {
+    fn pending(&self) -> usize;
+    fn ready(&self) -> usize;
+    fn allocate(&self, len: usize) -> Buffer;
+    fn submit(&mut self, buf: Buffer);
+}
+
+impl OutEndpoint for Endpoint<Bulk, Out> {
+    fn pending(&self) -> usize {
+        self.pending()
+    }
+
+    fn ready(&self) -> usize {
+        self.ready()
+    }
+
+    fn allocate(&self, len: usize) -> Buffer {
+        self.allocate(len)
+    }
+
+    fn submit(&mut self, buf: Buffer) {
+        self.submit(buf);
+    }
+}
+
+/// Refill and underrun bookkeeping of a [`BulkOutPipe`].
+struct OutFill {
+    num_transfers: usize,
+    pool: BufferPool,
+    source: Box<dyn FnMut(&mut Buffer) + Send>,
+    underruns: u64,
+    started: bool,
+    ended: bool,
+
+    /// Whether the last poll returned `Pending`, so completions since then
+    /// happened while the pipe was waiting for them.
+    waited: bool,
+}
+
+impl OutFill {
+    fn fill(&mut self, endpoint: &mut impl OutEndpoint) {
+        while !self.ended && endpoint.pending() < self.num_transfers {
+            let mut buf = self
+                .pool
+                .take()
+                .unwrap_or_else(|| endpoint.allocate(self.pool.buffer_size()));
+            (self.source)(&mut buf);
+            if buf.is_empty() {
+                self.ended = true;
+                self.pool.put(buf);
+                break;
+            }
+            endpoint.submit(buf);
+            self.started = true;
+        }
+    }
+
+    /// Count an underrun if every pending transfer already completed before
+    /// the pipe got back to reaping them. Called after refilling, before
+    /// waiting for the next completion.
+    fn check_underrun(&mut self, endpoint: &impl OutEndpoint) {
+        let pending = endpoint.pending();
+        if self.started && !self.ended && !self.waited && pending > 0 && endpoint.ready() == pending
+        {
+            self.underruns += 1;
+        }
+        self.waited = false;
+    }
+
+    fn handle_completion(&mut self, c: Completion) -> Result<(), TransferError> {
+        let res = match c.status {
+            Err(TransferError::Cancelled) => {
+                self.ended = true;
+                Ok(())
+            }
+            Err(e @ (TransferError::Stall | TransferError::Disconnected)) => {
+                self.ended = true;
+                Err(e)
+            }
+            res => res,
+        };
+        self.pool.put(c.buffer);
+        res
+    }
+}
+
+impl BulkOutPipe {
+    /// Create a new `BulkOutPipe` wrapping the given endpoint, keeping
+    /// `num_transfers` transfers of up to `transfer_size` bytes pending with
+    /// data from `source`.
+    ///
+    /// Transfers are first submitted when the pipe is run.
+    ///
+    /// ### Panics
+    /// * If `num_transfers` or `transfer_size` is zero.
+    pub fn new(
+        endpoint: Endpoint<Bulk, Out>,
+        num_transfers: usize,
+        transfer_size: usize,
+        source: impl FnMut(&mut Buffer) + Send + 'static,
+    ) -> Self {
+        assert!(num_transfers > 0, "num_transfers must be greater than zero");
+        assert!(transfer_size > 0, "transfer_size must be greater than zero");
+        Self {
+            endpoint,
+            state: OutFill {
+                num_transfers,
+                pool: BufferPool::new(transfer_size),
+                source: Box::new(source),
+                underruns: 0,
+                started: false,
+                ended: false,
+                waited: false,
+            },
+        }
+    }
+
+    /// Get the number of times all pending transfers completed before they
+    /// were refilled.
+    pub fn underruns(&self) -> u64 {
+        self.state.underruns
+    }
+
+    /// Cancel all pending transfers.
+    ///
+    /// No further transfers will be submitted, and the pipe finishes once the
+    /// cancelled transfers have been returned.
+    pub fn cancel_all(&mut self) {
+        self.state.ended = true;
+        self.endpoint.cancel_all();
+    }
+
+    /// Destroy this `BulkOutPipe` and return the underlying [`Endpoint`].
+    ///
+    /// Any pending transfers are not cancelled.
+    pub fn into_inner(self) -> Endpoint<Bulk, Out> {
+        self.endpoint
+    }
+
+    /// Stream data from the source, blocking until the stream ends or a
+    /// transfer fails.
+    ///
+    /// Returns `Ok(())` once the source has ended the stream and all data has
+    /// been sent. If a transfer fails, its error is returned, and the data of
+    /// that transfer is lost. The pipe can then be run again to continue,
+    /// unless the error was [`TransferError::Stall`] or
+    /// [`TransferError::Disconnected`], which end the pipe.
+    pub fn run(&mut self) -> Result<(), TransferError> {
+        loop {
+            self.state.fill(&mut self.endpoint);
+            if self.endpoint.pending() == 0 {
+                return Ok(());
+            }
+            self.state.check_underrun(&self.endpoint);
+            let c = self
+                .endpoint
+                .wait_next_complete(Duration::MAX)
+                .expect("transfer should complete");
+            self.state.handle_completion(c)?;
+        }
+    }
+
+    /// Poll to stream data from the source.
+    ///
+    /// Returns `Poll::Ready` in the same cases that [`run`](Self::run)
+    /// returns.
+    pub fn poll_run(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), TransferError>> {
+        loop {
+            self.state.fill(&mut self.endpoint);
+            if self.endpoint.pending() == 0 {
+                return Poll::Ready(Ok(()));
+            }
+            self.state.check_underrun(&self.endpoint);
+            let Poll::Ready(c) = self.endpoint.poll_next_complete(cx) else {
+                self.state.waited = true;
+                return Poll::Pending;
+            };
+            self.state.handle_completion(c)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Endpoint that completes transfers only when told to.
+    #[derive(Default)]
+    struct FakeOut {
+        pending: usize,
+        ready: usize,
+    }
+
+    impl FakeOut {
+        fn complete(&mut self, n: usize) {
+            self.ready = (self.ready + n).min(self.pending);
+        }
+
+        fn reap(&mut self) -> Completion {
+            assert!(self.ready > 0);
+            self.ready -= 1;
+            self.pending -= 1;
+            Completion {
+                status: Ok(()),
+                buffer: Buffer::new(0),
+                actual_len: 0,
+                tag: 0,
+                endpoint: 0,
+                submitted_at: None,
+                completed_at: None,
+            }
+        }
+    }
+
+    impl OutEndpoint for FakeOut {
+        fn pending(&self) -> usize {
+            self.pending
+        }
+
+        fn ready(&self) -> usize {
+            self.ready
+        }
+
+        fn allocate(&self, len: usize) -> Buffer {
+            Buffer::new(len)
+        }
+
+        fn submit(&mut self, _buf: Buffer) {
+            self.pending += 1;
+        }
+    }
+
+    fn out_fill(num_transfers: usize) -> OutFill {
+        OutFill {
+            num_transfers,
+            pool: BufferPool::new(4),
+            source: Box::new(|buf: &mut Buffer| buf.extend_from_slice(&[0; 4])),
+            underruns: 0,
+            started: false,
+            ended: false,
+            waited: false,
+        }
+    }
+
+    /// One step of `run`: refill, check for an underrun, and reap the next
+    /// completion, with `n` transfers completing while the pipe waits.
+    fn step(f: &mut OutFill, ep: &mut FakeOut, complete_early: usize) {
+        f.fill(ep);
+        ep.complete(complete_early);
+        f.check_underrun(ep);
+        ep.complete(1);
+        f.handle_completion(ep.reap()).unwrap();
+    }
+
+    #[test]
+    fn bulk_out_underruns() {
+        // Completions arriving while the pipe waits are not underruns, even
+        // with a single transfer.
+        let mut f = out_fill(1);
+        let mut ep = FakeOut::default();
+        for _ in 0..5 {
+            step(&mut f, &mut ep, 0);
+        }
+        assert_eq!(f.underruns, 0);
+
+        // With several transfers, only the device running dry counts.
+        let mut f = out_fill(4);
+        let mut ep = FakeOut::default();
+        step(&mut f, &mut ep, 3);
+        assert_eq!(f.underruns, 0);
+        step(&mut f, &mut ep, 4);
+        assert_eq!(f.underruns, 1);
+        // The remaining completed transfers are reaped after refilling, so
+        // the same gap is only counted once.
+        step(&mut f, &mut ep, 0);
+        assert_eq!(f.underruns, 1);
+
+        // Being woken by a completion is not an underrun.
+        let mut f = out_fill(1);
+        let mut ep = FakeOut::default();
+        f.fill(&mut ep);
+        f.check_underrun(&ep);
+        f.waited = true;
+        ep.complete(1);
+        f.fill(&mut ep);
+        f.check_underrun(&ep);
+        assert_eq!(f.underruns, 0);
+    }
+}
@@ -4,7 +4,7 @@ use std::{
     time::Duration,
 };
 
-#[cfg(any(feature = "tokio", feature = "smol"))]
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
 use std::{
     pin::Pin,
     task::{ready, Context, Poll},
@@ -27,7 +27,7 @@ use crate::{
 ///   [`tokio::io::AsyncRead`](tokio::io::AsyncRead) and
 ///   [`AsyncBufRead`](tokio::io::AsyncBufRead) for async IO. Tokio also
 ///   provides `AsyncReadExt` and `AsyncBufReadExt` with additional methods.
-/// * With the `smol` cargo feature,
+/// * With the `futures-io` cargo feature (enabled by `smol`),
 ///   [`futures_io::AsyncRead`](futures_io::AsyncRead) and
 ///   [`AsyncBufRead`](futures_io::AsyncBufRead) for async IO.
 ///   `futures_lite` provides `AsyncReadExt` and `AsyncBufReadExt` with
@@ -195,7 +195,7 @@ impl<EpType: BulkOrInterrupt> EndpointRead<EpType> {
     /// as a delimiter marking the end of a message. By default, [`EndpointRead`]
     /// ignores packet boundaries, but this adapter allows you to observe these
     /// delimiters.
-    pub fn until_short_packet(&mut self) -> EndpointReadUntilShortPacket<EpType> {
+    pub fn until_short_packet(&mut self) -> EndpointReadUntilShortPacket<'_, EpType> {
         EndpointReadUntilShortPacket { reader: self }
     }
 
@@ -265,7 +265,7 @@ impl<EpType: BulkOrInterrupt> EndpointRead<EpType> {
         }
     }
 
-    #[cfg(any(feature = "tokio", feature = "smol"))]
+    #[cfg(any(feature = "tokio", feature = "futures-io"))]
     fn poll(&mut self, cx: &mut Context<'_>) -> Poll<bool> {
         if self.start_read() {
             let c = ready!(self.endpoint.poll_next_complete(cx));
@@ -280,7 +280,7 @@ impl<EpType: BulkOrInterrupt> EndpointRead<EpType> {
         }
     }
 
-    #[cfg(any(feature = "tokio", feature = "smol"))]
+    #[cfg(any(feature = "tokio", feature = "futures-io"))]
     #[inline]
     fn poll_fill_buf(&mut self, cx: &mut Context<'_>) -> Poll<Result<&[u8], std::io::Error>> {
         while !self.has_data() {
@@ -291,7 +291,7 @@ impl<EpType: BulkOrInterrupt> EndpointRead<EpType> {
         Poll::Ready(self.remaining())
     }
 
-    #[cfg(any(feature = "tokio", feature = "smol"))]
+    #[cfg(any(feature = "tokio", feature = "futures-io"))]
     #[inline]
     fn poll_fill_buf_until_short(
         &mut self,
@@ -366,7 +366,7 @@ impl<EpType: BulkOrInterrupt> tokio::io::AsyncBufRead for EndpointRead<EpType> {
     }
 }
 
-#[cfg(feature = "smol")]
+#[cfg(feature = "futures-io")]
 impl<EpType: BulkOrInterrupt> futures_io::AsyncRead for EndpointRead<EpType> {
     fn poll_read(
         self: Pin<&mut Self>,
@@ -381,7 +381,7 @@ impl<EpType: BulkOrInterrupt> futures_io::AsyncRead for EndpointRead<EpType> {
     }
 }
 
-#[cfg(feature = "smol")]
+#[cfg(feature = "futures-io")]
 impl<EpType: BulkOrInterrupt> futures_io::AsyncBufRead for EndpointRead<EpType> {
     fn poll_fill_buf(
         self: Pin<&mut Self>,
@@ -514,7 +514,7 @@ impl<EpType: BulkOrInterrupt> tokio::io::AsyncBufRead for EndpointReadUntilShort
     }
 }
 
-#[cfg(feature = "smol")]
+#[cfg(feature = "futures-io")]
 impl<EpType: BulkOrInterrupt> futures_io::AsyncRead for EndpointReadUntilShortPacket<'_, EpType> {
     fn poll_read(
         self: Pin<&mut Self>,
@@ -529,7 +529,7 @@ impl<EpType: BulkOrInterrupt> futures_io::AsyncRead for EndpointReadUntilShortPa
     }
 }
 
-#[cfg(feature = "smol")]
+#[cfg(feature = "futures-io")]
 impl<EpType: BulkOrInterrupt> futures_io::AsyncBufRead
     for EndpointReadUntilShortPacket<'_, EpType>
 {
@@ -0,0 +1,285 @@
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use crate::{
+    transfer::{
+        Buffer, BufferPool, BulkOrInterrupt, Completion, In, Interrupt, Out, TransferError,
+    },
+    Endpoint,
+};
+
+/// Wrapper for a Bulk or Interrupt IN [`Endpoint`](crate::Endpoint)
+/// implementing [`Stream`] of completed transfers.
+///
+/// This keeps a configurable number of transfers pending, and yields each
+/// [`Completion`] as it finishes, in order. Unlike
+/// [`EndpointRead`](super::EndpointRead), packet boundaries are preserved: each
+/// item is the data from one transfer.
+///
+/// The stream ends after yielding a completion with
+/// [`TransferError::Disconnected`], or once all transfers have completed after
+/// [`num_transfers`](Self::set_num_transfers) is set to 0.
+///
+/// Pass buffers back to [`recycle`](Self::recycle) once you are done with them
+/// to reuse them for subsequent transfers instead of allocating new ones.
+pub struct EndpointStream<EpType: BulkOrInterrupt> {
+    endpoint: Endpoint<EpType, In>,
+    num_transfers: usize,
+    pool: BufferPool,
+    ended: bool,
+}
+
+impl<EpType: BulkOrInterrupt> EndpointStream<EpType> {
+    /// Create a new `EndpointStream` wrapping the given endpoint.
+    ///
+    /// The `transfer_size` parameter is the size of the buffer passed to the OS
+    /// for each transfer. It will be rounded up to the next multiple of the
+    /// endpoint's max packet size.
+    pub fn new(endpoint: Endpoint<EpType, In>, transfer_size: usize) -> Self {
+        let packet_size = endpoint.max_packet_size();
+        let transfer_size = (transfer_size.div_ceil(packet_size)).max(1) * packet_size;
+
+        Self {
+            endpoint,
+            num_transfers: 1,
+            pool: BufferPool::new(transfer_size),
+            ended: false,
+        }
+    }
+
+    /// Set the number of concurrent transfers.
+    ///
+    /// A value of 1 (default) means that a transfer is only submitted when the
+    /// stream is polled. To maximize throughput, a value of 2 or more is
+    /// recommended so that the host controller can continue to receive data
+    /// while the application processes a completed transfer.
+    ///
+    /// A value of 0 means no further transfers will be submitted, and the
+    /// stream ends once pending transfers have completed.
+    ///
+    /// This submits more transfers when increasing the number, but does not
+    /// [cancel transfers](Self::cancel_all) when decreasing it.
+    pub fn set_num_transfers(&mut self, num_transfers: usize) {
+        self.num_transfers = num_transfers;
+
+        // Leave the last transfer to be submitted by `poll_next` such that
+        // a value of `1` only has transfers pending while polling.
+        while self.endpoint.pending() < num_transfers.saturating_sub(1) {
+            self.submit_one();
+        }
+    }
+
+    /// Set the number of concurrent transfers.
+    ///
+    /// See [Self::set_num_transfers] (this version is for method chaining).
+    pub fn with_num_transfers(mut self, num_transfers: usize) -> Self {
+        self.set_num_transfers(num_transfers);
+        self
+    }
+
+    /// Cancel all pending transfers.
+    ///
+    /// This sets [`num_transfers`](Self::set_num_transfers) to 0, so no further
+    /// transfers will be submitted. The cancelled transfers are still yielded
+    /// by the stream, and then the stream ends.
+    pub fn cancel_all(&mut self) {
+        self.num_transfers = 0;
+        self.endpoint.cancel_all();
+    }
+
+    /// Return the buffer of a completion yielded by this stream to be reused
+    /// for a subsequent transfer.
+    ///
+    /// Buffers too small for this stream's transfer size are dropped.
+    pub fn recycle(&mut self, buf: Buffer) {
+        self.pool.put(buf);
+    }
+
+    /// Destroy this `EndpointStream` and return the underlying [`Endpoint`].
+    ///
+    /// Any pending transfers are not cancelled.
+    pub fn into_inner(self) -> Endpoint<EpType, In> {
+        self.endpoint
+    }
+
+    fn submit_one(&mut self) {
+        let buf = self
+            .pool
+            .take()
+            .unwrap_or_else(|| self.endpoint.allocate(self.pool.buffer_size()));
+        self.endpoint.submit(buf);
+    }
+}
+
+impl<EpType: BulkOrInterrupt> Stream for EndpointStream<EpType> {
+    type Item = Completion;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = Pin::into_inner(self);
+
+        if this.ended {
+            return Poll::Ready(None);
+        }
+
+        while this.endpoint.pending() < this.num_transfers {
+            this.submit_one();
+        }
+
+        if this.endpoint.pending() == 0 {
+            return Poll::Ready(None);
+        }
+
+        let c = ready!(this.endpoint.poll_next_complete(cx));
+        if c.status == Err(TransferError::Disconnected) {
+            this.ended = true;
+        }
+        Poll::Ready(Some(c))
+    }
+}
+
+/// [`Stream`] of reports received from an Interrupt IN endpoint.
+///
+/// Obtain an `InterruptStream` with
+/// [`Interface::interrupt_in_stream`](crate::Interface::interrupt_in_stream).
+///
+/// Each item is the data of one transfer of up to the endpoint's maximum
+/// packet size, such as a HID input report, or the error that ended the
+/// transfer. A configurable number of transfers is kept pending, and each
+/// completed transfer is resubmitted, so that the device is polled
+/// continuously. The stream ends after yielding
+/// [`TransferError::Disconnected`].
+pub struct InterruptStream {
+    inner: EndpointStream<Interrupt>,
+}
+
+impl InterruptStream {
+    /// Create a new `InterruptStream` wrapping the given endpoint, keeping
+    /// `depth` transfers pending.
+    ///
+    /// ### Panics
+    /// * If `depth` is zero.
+    pub fn new(endpoint: Endpoint<Interrupt, In>, depth: usize) -> Self {
+        assert!(depth > 0, "depth must be greater than zero");
+        let transfer_size = endpoint.max_packet_size();
+        Self {
+            inner: EndpointStream::new(endpoint, transfer_size).with_num_transfers(depth),
+        }
+    }
+
+    /// Cancel all pending transfers.
+    ///
+    /// See [`EndpointStream::cancel_all`].
+    pub fn cancel_all(&mut self) {
+        self.inner.cancel_all();
+    }
+
+    /// Destroy this `InterruptStream` and return the underlying [`Endpoint`].
+    ///
+    /// Any pending transfers are not cancelled.
+    pub fn into_inner(self) -> Endpoint<Interrupt, In> {
+        self.inner.into_inner()
+    }
+}
+
+impl Stream for InterruptStream {
+    type Item = Result<Vec<u8>, TransferError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = Pin::into_inner(self);
+        let Some(c) = ready!(Pin::new(&mut this.inner).poll_next(cx)) else {
+            return Poll::Ready(None);
+        };
+        let res = c.status.map(|()| c.buffer.to_vec());
+        this.inner.recycle(c.buffer);
+        Poll::Ready(Some(res))
+    }
+}
+
+/// Wrapper for a Bulk or Interrupt OUT [`Endpoint`](crate::Endpoint)
+/// implementing [`Sink`] of `Vec<u8>` or [`Buffer`].
+///
+/// Each item sent to the sink is submitted as one transfer. Up to
+/// [`num_transfers`](Self::set_num_transfers) transfers are pending at a time
+/// before backpressure is applied.
+///
+/// Errors from completed transfers are returned from the next call to
+/// `poll_ready`, `poll_flush`, or `poll_close`. Closing the sink waits for all
+/// pending transfers to complete, but does not send a zero-length packet.
+pub struct EndpointSink<EpType: BulkOrInterrupt> {
+    endpoint: Endpoint<EpType, Out>,
+    num_transfers: usize,
+}
+
+impl<EpType: BulkOrInterrupt> EndpointSink<EpType> {
+    /// Create a new `EndpointSink` wrapping the given endpoint.
+    pub fn new(endpoint: Endpoint<EpType, Out>) -> Self {
+        Self {
+            endpoint,
+            num_transfers: 1,
+        }
+    }
+
+    /// Set the maximum number of transfers that can be queued with the OS
+    /// before backpressure is applied.
+    ///
+    /// Panics if `num_transfers` is zero.
+    pub fn set_num_transfers(&mut self, num_transfers: usize) {
+        assert!(num_transfers > 0, "num_transfers must be greater than zero");
+        self.num_transfers = num_transfers;
+    }
+
+    /// Set the maximum number of transfers that can be queued with the OS
+    /// before backpressure is applied.
+    ///
+    /// See [Self::set_num_transfers] -- this is for method chaining with `EndpointSink::new()`.
+    pub fn with_num_transfers(mut self, num_transfers: usize) -> Self {
+        self.set_num_transfers(num_transfers);
+        self
+    }
+
+    /// Destroy this `EndpointSink` and return the underlying [`Endpoint`].
+    ///
+    /// Any pending transfers are not cancelled.
+    pub fn into_inner(self) -> Endpoint<EpType, Out> {
+        self.endpoint
+    }
+
+    fn poll_pending_below(
+        &mut self,
+        cx: &mut Context<'_>,
+        limit: usize,
+    ) -> Poll<Result<(), TransferError>> {
+        while self.endpoint.pending() > limit {
+            ready!(self.endpoint.poll_next_complete(cx)).status?;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<EpType: BulkOrInterrupt, T: Into<Buffer>> Sink<T> for EndpointSink<EpType> {
+    type Error = TransferError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = Pin::into_inner(self);
+        let limit = this.num_transfers - 1;
+        this.poll_pending_below(cx, limit)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        Pin::into_inner(self).endpoint.submit(item.into());
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::into_inner(self).poll_pending_below(cx, 0)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::into_inner(self).poll_pending_below(cx, 0)
+    }
+}
@@ -9,7 +9,7 @@ use std::{
     time::Duration,
 };
 
-#[cfg(any(feature = "tokio", feature = "smol"))]
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
 use std::{pin::Pin, task::ready};
 
 /// Wrapper for a Bulk or Interrupt OUT [`Endpoint`](crate::Endpoint) that
@@ -22,7 +22,7 @@ use std::{pin::Pin, task::ready};
 /// * With the `tokio` cargo feature,
 ///   [`tokio::io::AsyncWrite`](tokio::io::AsyncWrite). Tokio also provides
 ///   `AsyncWriteExt` with additional methods.
-/// * With the `smol` cargo feature,
+/// * With the `futures-io` cargo feature (enabled by `smol`),
 ///   [`futures_io::AsyncWrite`](futures_io::AsyncWrite) for async IO.
 ///   `futures_lite` provides `AsyncWriteExt` with additional methods.
 ///
@@ -132,7 +132,7 @@ impl<EpType: BulkOrInterrupt> EndpointWrite<EpType> {
             .map(|c| self.handle_completion(c))
     }
 
-    #[cfg(any(feature = "tokio", feature = "smol"))]
+    #[cfg(any(feature = "tokio", feature = "futures-io"))]
     fn poll_write(&mut self, cx: &mut Context<'_>, src: &[u8]) -> Poll<Result<usize, Error>> {
         let buf = loop {
             if let Some(buf) = self.writing.as_mut() {
@@ -162,7 +162,7 @@ impl<EpType: BulkOrInterrupt> EndpointWrite<EpType> {
     /// [Write::flush]). If the buffer is empty, this does nothing.
     pub fn submit(&mut self) {
         if self.writing.as_ref().is_some_and(|b| !b.is_empty()) {
-            self.endpoint.submit(self.writing.take().unwrap())
+            self.endpoint.submit(self.writing.take().unwrap());
         }
     }
 
@@ -212,7 +212,7 @@ impl<EpType: BulkOrInterrupt> EndpointWrite<EpType> {
         Ok(())
     }
 
-    #[cfg(any(feature = "tokio", feature = "smol"))]
+    #[cfg(any(feature = "tokio", feature = "futures-io"))]
     fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
         self.submit();
         while self.endpoint.pending() > 0 {
@@ -270,7 +270,7 @@ impl<EpType: BulkOrInterrupt> Write for EndpointWrite<EpType> {
     }
 }
 
-#[cfg(feature = "smol")]
+#[cfg(feature = "futures-io")]
 impl<EpType: BulkOrInterrupt> futures_io::AsyncWrite for EndpointWrite<EpType> {
     /// Write data to the endpoint.
     ///
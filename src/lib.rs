@@ -4,7 +4,8 @@
 //! `nusb` supports Windows, macOS, and Linux, and provides both async and
 //!  blocking APIs for listing and watching USB devices, reading descriptor
 //!  details, opening and managing devices and interfaces, and performing
-//!  transfers on control, bulk, and interrupt endpoints.
+//!  transfers on control, bulk, and interrupt endpoints, as well as isochronous
+//!  endpoints on Linux.
 //!
 //! `nusb` is comparable to the C library [libusb] and its Rust bindings [rusb],
 //! but written in pure Rust.
@@ -178,17 +179,44 @@
 //!
 //! These features do not affect and are not required for transfers, which are
 //! implemented on top of natively-async OS APIs.
+//!
+//! The [`nusb::io`][io] types implement the async IO traits from `tokio` with
+//! the `tokio` feature, and those from `futures-io` with the `futures-io`
+//! feature, which is also enabled by `smol`.
+//!
+//! ### Serialization
+//!
+//! Enable the cargo feature `serde` to implement `serde`'s `Serialize` and
+//! `Deserialize` for [`DeviceInfo`], [`BusInfo`], [`Speed`], and the other
+//! types describing devices. The fields of `DeviceInfo` and `BusInfo` are
+//! platform-specific, so these can only be deserialized on the same platform
+//! that serialized them.
+//!
+//! The [descriptor][descriptors] types are serialized as their bytes. Only
+//! [`DeviceDescriptor`][descriptors::DeviceDescriptor] implements
+//! `Deserialize`, because the others borrow the bytes of the configuration
+//! descriptor; parse them from the deserialized bytes instead.
 
 mod platform;
 
 pub mod descriptors;
 mod enumeration;
+#[cfg(target_os = "linux")]
+pub use enumeration::AuthorizedDefault;
+#[cfg(target_os = "macos")]
+pub use enumeration::IoRegistryProperty;
 #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
-pub use enumeration::BusInfo;
-pub use enumeration::{DeviceId, DeviceInfo, InterfaceInfo, Speed, UsbControllerType};
+pub use enumeration::{BusInfo, DeviceList};
+pub use enumeration::{
+    CachedString, DeviceFilter, DeviceId, DeviceInfo, InterfaceInfo, ParsePortIdError, PortId,
+    Speed, UsbControllerType,
+};
 
 mod device;
-pub use device::{Device, Endpoint, Interface};
+pub use device::{
+    BulkIn, BulkOut, ClaimStatus, Device, DeviceStatus, Endpoint, Interface, InterruptIn,
+    InterruptOut, KernelDriverGuard, ResetOutcome, SystemExitLatency,
+};
 
 pub mod transfer;
 
@@ -200,6 +228,8 @@ pub use maybe_future::MaybeFuture;
 
 mod bitset;
 
+mod timer;
+
 pub mod io;
 
 mod error;
@@ -245,6 +275,35 @@ pub fn list_buses() -> impl MaybeFuture<Output = Result<impl Iterator<Item = Bus
     platform::list_buses()
 }
 
+/// Wait for a device matching a predicate to be connected.
+///
+/// Returns immediately if a matching device is already connected. Otherwise,
+/// waits up to `timeout` for one to be connected, and fails with
+/// [`ErrorKind::Disconnected`] if none is. This watches for connection
+/// events before listing the connected devices, so a device connected
+/// while this is called is not missed.
+///
+/// See also [`DeviceInfo::wait_for_reconnect`] to wait for a device to
+/// re-enumerate, such as after a reset or a switch to bootloader mode.
+///
+/// ### Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use nusb::MaybeFuture;
+/// let device = nusb::wait_for_device(
+///     |dev| dev.vendor_id() == 0xAAAA && dev.product_id() == 0xBBBB,
+///     Duration::from_secs(10),
+/// ).wait().unwrap();
+/// ```
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+pub fn wait_for_device(
+    predicate: impl FnMut(&DeviceInfo) -> bool + Send + 'static,
+    timeout: std::time::Duration,
+) -> impl MaybeFuture<Output = Result<DeviceInfo, Error>> {
+    maybe_future::blocking::Blocking::new(move || hotplug::wait_for(predicate, None, timeout))
+}
+
 /// Get a [`Stream`][`futures_core::Stream`] that yields an
 /// [event][`hotplug::HotplugEvent`] when a USB device is connected or
 /// disconnected from the system.
@@ -252,7 +311,9 @@ pub fn list_buses() -> impl MaybeFuture<Output = Result<impl Iterator<Item = Bus
 /// Events will be returned for devices connected or disconnected beginning at
 /// the time this function is called. To maintain a list of connected devices,
 /// call [`list_devices`] after creating the watch with this function to avoid
-/// potentially missing a newly-attached device:
+/// potentially missing a newly-attached device, or use
+/// [`list_and_watch_devices`], which also skips events for changes already
+/// included in the list:
 ///
 /// ## Example
 ///
@@ -276,11 +337,53 @@ pub fn list_buses() -> impl MaybeFuture<Output = Result<impl Iterator<Item = Bus
 ///
 /// ### Platform-specific notes:
 ///
+///   * On Linux, events are received from the kernel's netlink uevent socket,
+///     and do not require udev to be running.
+///   * On Windows, events are received with `CM_Register_Notification`.
+///   * On macOS, events are received from IOKit matching and termination
+///     notifications.
 ///   * On Windows, the interfaces of a composite device might not be ready
 ///     when the `Connected` event is emitted. If you are immediately opening the device
 ///     and claiming an interface when receiving a `Connected` event,
 ///     you should retry after a short delay if opening or claiming fails.
 #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
 pub fn watch_devices() -> Result<hotplug::HotplugWatch, Error> {
-    Ok(hotplug::HotplugWatch(platform::HotplugWatch::new()?))
+    hotplug::HotplugWatch::new()
+}
+
+/// List the connected devices and begin watching for changes, without
+/// missing or repeating devices that are connected or disconnected in
+/// between.
+///
+/// Calling [`list_devices`] and [`watch_devices`] separately always leaves a
+/// window in which a change is either missed, or reported by the stream
+/// after already being reflected in the list. This starts watching before
+/// listing, and skips events for changes that the returned list already
+/// includes. Every event from the returned stream is a change from the
+/// returned list: a `Connected` event is for a device that is not in it (or
+/// that was disconnected since), and a `Disconnected` event is for a device
+/// that is in it (or that was connected since).
+///
+/// ### Example
+///
+/// ```no_run
+/// use std::collections::HashMap;
+/// use nusb::{MaybeFuture, hotplug::HotplugEvent};
+/// let (devices, watch) = nusb::list_and_watch_devices().wait().unwrap();
+/// let mut devices: HashMap<_, _> = devices.into_iter().map(|d| (d.id(), d)).collect();
+/// for event in futures_lite::stream::block_on(watch) {
+///     match event {
+///         HotplugEvent::Connected(d) => {
+///             devices.insert(d.id(), d);
+///         }
+///         HotplugEvent::Disconnected(id) => {
+///             devices.remove(&id);
+///         }
+///     }
+/// }
+/// ```
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+pub fn list_and_watch_devices(
+) -> impl MaybeFuture<Output = Result<(Vec<DeviceInfo>, hotplug::HotplugWatch), Error>> {
+    maybe_future::blocking::Blocking::new(hotplug::list_and_watch)
 }
@@ -5,7 +5,7 @@ use std::{
     io::{Read, Seek},
     mem::ManuallyDrop,
     sync::{
-        atomic::{AtomicU8, Ordering},
+        atomic::{AtomicU8, AtomicUsize, Ordering},
         Arc, Mutex, MutexGuard, Weak,
     },
     task::{Context, Poll},
@@ -13,9 +13,10 @@ use std::{
 };
 
 use log::{debug, error, warn};
+use once_cell::sync::OnceCell;
 use rustix::{
     event::epoll::EventFlags,
-    fd::{AsFd, AsRawFd, FromRawFd, OwnedFd},
+    fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
     fs::Timespec,
     io::Errno,
     time::{timerfd_create, timerfd_settime, Itimerspec, TimerfdFlags, TimerfdTimerFlags},
@@ -40,12 +41,13 @@ use crate::{
     maybe_future::{blocking::Blocking, MaybeFuture},
     transfer::{
         internal::{
-            notify_completion, take_completed_from_queue, Idle, Notify, Pending, TransferFuture,
+            notify_completion, take_completed_from_queue, CloseNotify, Idle, Notify, Pending,
+            TransferFuture,
         },
-        request_type, Buffer, Completion, ControlIn, ControlOut, ControlType, Direction, Recipient,
-        TransferError,
+        request_type, Buffer, Completion, ControlIn, ControlOut, ControlType, Direction,
+        IsoCompletion, IsoSchedule, Recipient, TransferError, TransferPriority,
     },
-    DeviceInfo, Error, ErrorKind, Speed,
+    ClaimStatus, DeviceInfo, Error, ErrorKind, ResetOutcome, Speed,
 };
 
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
@@ -71,14 +73,25 @@ pub(crate) struct LinuxDevice {
 
     active_config: AtomicU8,
 
+    /// Number of resets from `reset_and_revalidate`, which release the
+    /// interfaces claimed before them.
+    resets: AtomicUsize,
+
     timerfd: OwnedFd,
     timeouts: Mutex<BTreeMap<TimeoutEntry, ()>>,
+
+    /// Event loop polling this device instead of the global one, if any.
+    dedicated_events: OnceCell<events::DedicatedLoop>,
+
+    /// Notifies `wait_closed` once the device is released.
+    pub(crate) closed: CloseNotify,
 }
 
 impl LinuxDevice {
     #[cfg(not(target_os = "android"))]
     pub(crate) fn from_device_info(
         d: &DeviceInfo,
+        read_only: bool,
     ) -> impl MaybeFuture<Output = Result<Arc<LinuxDevice>, Error>> {
         use rustix::fs::{Mode, OFlags};
 
@@ -88,13 +101,18 @@ impl LinuxDevice {
 
         Blocking::new(move || {
             let path = std::path::PathBuf::from(format!("/dev/bus/usb/{busnum:03}/{devnum:03}"));
-            let fd = rustix::fs::open(&path, OFlags::RDWR | OFlags::CLOEXEC, Mode::empty())
-                .map_err(|e| {
+            let access = if read_only {
+                OFlags::RDONLY
+            } else {
+                OFlags::RDWR
+            };
+            let fd =
+                rustix::fs::open(&path, access | OFlags::CLOEXEC, Mode::empty()).map_err(|e| {
                     match e {
                         Errno::NOENT => {
                             Error::new_os(ErrorKind::Disconnected, "device not found", e)
                         }
-                        Errno::PERM => {
+                        Errno::PERM | Errno::ACCESS => {
                             Error::new_os(ErrorKind::PermissionDenied, "permission denied", e)
                         }
                         e => Error::new_os(ErrorKind::Other, "failed to open device", e),
@@ -109,6 +127,7 @@ impl LinuxDevice {
     #[cfg(target_os = "android")]
     pub(crate) fn from_device_info(
         _d: &DeviceInfo,
+        _read_only: bool,
     ) -> impl MaybeFuture<Output = Result<Arc<LinuxDevice>, Error>> {
         Blocking::new(move || unimplemented!())
     }
@@ -166,8 +185,11 @@ impl LinuxDevice {
                 #[cfg(not(target_os = "android"))]
                 sysfs,
                 active_config: AtomicU8::new(active_config),
+                resets: AtomicUsize::new(0),
                 timerfd,
                 timeouts: Mutex::new(BTreeMap::new()),
+                dedicated_events: OnceCell::new(),
+                closed: CloseNotify::default(),
             }
         });
 
@@ -192,6 +214,42 @@ impl LinuxDevice {
         Ok(arc)
     }
 
+    /// Move this device's file descriptors from the global event loop to a
+    /// new event loop running on its own thread.
+    pub(crate) fn use_dedicated_event_thread(&self) -> Result<(), Error> {
+        self.dedicated_events.get_or_try_init(|| {
+            let dedicated = events::DedicatedLoop::new()?;
+            dedicated.register_fd(
+                self.fd.as_fd(),
+                events::Tag::Device(self.events_id),
+                EventFlags::OUT,
+            )?;
+            dedicated.register_fd(
+                self.timerfd.as_fd(),
+                events::Tag::DeviceTimer(self.events_id),
+                EventFlags::IN,
+            )?;
+
+            // Events are level-triggered, so anything pending is picked up
+            // by the new loop.
+            events::unregister_fd(self.fd.as_fd());
+            events::unregister_fd(self.timerfd.as_fd());
+            debug!(
+                "Moved device {} to a dedicated event thread",
+                self.events_id
+            );
+            Ok::<_, Error>(dedicated)
+        })?;
+        Ok(())
+    }
+
+    fn unregister_events(&self, fd: BorrowedFd) {
+        match self.dedicated_events.get() {
+            Some(dedicated) => dedicated.unregister_fd(fd),
+            None => events::unregister_fd(fd),
+        }
+    }
+
     pub(crate) fn handle_usb_epoll(id: usize) {
         let device = DEVICES.lock().unwrap().get(id).and_then(|w| w.upgrade());
         if let Some(device) = device {
@@ -201,45 +259,78 @@ impl LinuxDevice {
 
     fn handle_events(&self) {
         debug!("Handling events for device {}", self.events_id);
-        match usbfs::reap_urb_ndelay(&self.fd) {
-            Ok(urb) => {
-                let transfer_data: *mut TransferData = unsafe { &(*urb) }.usercontext.cast();
 
-                {
-                    let transfer = unsafe { &*transfer_data };
-                    debug_assert!(transfer.urb_ptr() == urb);
-                    debug!(
-                        "URB {:?} for ep {:x} completed, status={} actual_length={}",
-                        transfer.urb_ptr(),
-                        transfer.urb().endpoint,
-                        transfer.urb().status,
-                        transfer.urb().actual_length
-                    );
-
-                    if let Some(deadline) = transfer.deadline {
-                        let mut timeouts = self.timeouts.lock().unwrap();
-                        timeouts.remove(&TimeoutEntry { deadline, urb });
-                        self.update_timeouts(timeouts, Instant::now());
-                    }
-                };
+        // Reap all completed URBs, delivering latency-priority completions
+        // immediately, and the others once all are reaped, in priority order.
+        let mut normal = Vec::new();
+        let mut throughput = Vec::new();
+        loop {
+            match usbfs::reap_urb_ndelay(&self.fd) {
+                Ok(urb) => {
+                    let transfer_data: *mut TransferData = unsafe { &(*urb) }.usercontext.cast();
+
+                    let priority = {
+                        let transfer = unsafe { &*transfer_data };
+                        debug_assert!(transfer.urb_ptr() == urb);
+                        debug!(
+                            "URB {:?} for ep {:x} completed, status={} actual_length={}",
+                            transfer.urb_ptr(),
+                            transfer.urb().endpoint,
+                            transfer.urb().status,
+                            transfer.urb().actual_length
+                        );
+
+                        if let Some(deadline) = transfer.deadline {
+                            let mut timeouts = self.timeouts.lock().unwrap();
+                            let timed_out =
+                                timeouts.remove(&TimeoutEntry { deadline, urb }).is_none();
+                            self.update_timeouts(timeouts, Instant::now());
+
+                            // The entry is only missing if `handle_timeouts` removed it and
+                            // discarded the URB, so report the cancellation as a timeout.
+                            let status = unsafe { &mut (*urb).status };
+                            if timed_out
+                                && (*status == -Errno::NOENT.raw_os_error()
+                                    || *status == -Errno::CONNRESET.raw_os_error())
+                            {
+                                *status = -Errno::TIMEDOUT.raw_os_error();
+                            }
+                        }
 
-                // SAFETY: pointer came from submit via kernel and we're now done with it
-                unsafe { notify_completion::<super::TransferData>(transfer_data) }
-            }
-            Err(Errno::AGAIN) => {}
-            Err(Errno::NODEV) => {
-                debug!("Device {} disconnected", self.events_id);
-
-                // epoll returns events continuously on a disconnected device, and REAPURB
-                // only returns ENODEV after all events are received, so unregister to
-                // keep the event thread from spinning because we won't receive further events.
-                // The drop impl will try to unregister again, but that's ok.
-                events::unregister_fd(self.fd.as_fd());
-            }
-            Err(e) => {
-                error!("Unexpected error {e} from REAPURBNDELAY");
+                        transfer.priority
+                    };
+
+                    match priority {
+                        TransferPriority::Latency => {
+                            // SAFETY: pointer came from submit via kernel and we're now done with it
+                            unsafe { notify_completion::<super::TransferData>(transfer_data) }
+                        }
+                        TransferPriority::Normal => normal.push(transfer_data),
+                        TransferPriority::Throughput => throughput.push(transfer_data),
+                    }
+                }
+                Err(Errno::AGAIN) => break,
+                Err(Errno::NODEV) => {
+                    debug!("Device {} disconnected", self.events_id);
+
+                    // epoll returns events continuously on a disconnected device, and REAPURB
+                    // only returns ENODEV after all events are received, so unregister to
+                    // keep the event thread from spinning because we won't receive further events.
+                    // The drop impl will try to unregister again, but that's ok.
+                    self.unregister_events(self.fd.as_fd());
+                    break;
+                }
+                Err(e) => {
+                    error!("Unexpected error {e} from REAPURBNDELAY");
+                    break;
+                }
             }
         }
+
+        for transfer_data in normal.into_iter().chain(throughput) {
+            // SAFETY: pointer came from submit via kernel and we're now done with it
+            unsafe { notify_completion::<super::TransferData>(transfer_data) }
+        }
     }
 
     pub(crate) fn handle_timer_epoll(id: usize) {
@@ -359,6 +450,121 @@ impl LinuxDevice {
         })
     }
 
+    pub(crate) fn cycle_port(
+        self: Arc<Self>,
+        off_time: Duration,
+    ) -> impl MaybeFuture<Output = Result<(), Error>> {
+        Blocking::new(move || {
+            #[cfg(not(target_os = "android"))]
+            {
+                let Some(sysfs) = self.sysfs.as_ref() else {
+                    return Err(Error::new(
+                        ErrorKind::Unsupported,
+                        "device has no sysfs path",
+                    ));
+                };
+
+                // `port` links to the port of the parent hub, which is absent
+                // for root hubs. Its `disable` attribute was added in Linux 4.20.
+                let map_err = |e: std::io::Error| match e.kind() {
+                    std::io::ErrorKind::NotFound => Error::new_io(
+                        ErrorKind::Unsupported,
+                        "hub port does not support disabling",
+                        e,
+                    ),
+                    std::io::ErrorKind::PermissionDenied => {
+                        Error::new_io(ErrorKind::PermissionDenied, "permission denied", e)
+                    }
+                    _ => Error::new_io(ErrorKind::Other, "failed to disable hub port", e),
+                };
+
+                // Disabling the port disconnects the device and removes its
+                // sysfs directory, so resolve the hub port's own directory
+                // (`.../usbX-portN`) first.
+                let port = SysfsPath(std::fs::canonicalize(sysfs.0.join("port")).map_err(map_err)?);
+
+                port.write_attr("disable", "1").map_err(map_err)?;
+                debug!(
+                    "Disabled hub port {:?} of device {}",
+                    port.0, self.events_id
+                );
+                std::thread::sleep(off_time);
+                port.write_attr("disable", "0").map_err(|e| {
+                    Error::new_io(ErrorKind::Other, "failed to re-enable hub port", e).log_error()
+                })?;
+                Ok(())
+            }
+
+            #[cfg(target_os = "android")]
+            {
+                let _ = off_time;
+                Err(Error::new(
+                    ErrorKind::Unsupported,
+                    "port cycle not supported on Android",
+                ))
+            }
+        })
+    }
+
+    pub(crate) fn reset_and_revalidate(
+        self: Arc<Self>,
+    ) -> impl MaybeFuture<Output = Result<ResetOutcome, Error>> {
+        Blocking::new(move || {
+            #[cfg(not(target_os = "android"))]
+            let read_devnum = || -> Option<u8> { self.sysfs.as_ref()?.read_attr("devnum").ok() };
+            #[cfg(not(target_os = "android"))]
+            let devnum = read_devnum();
+
+            match usbfs::reset(&self.fd) {
+                // usbfs unbinds the claimed interfaces to reset the device
+                Ok(()) => {
+                    self.resets.fetch_add(1, Ordering::SeqCst);
+                }
+                // The kernel disconnects the device if its descriptors changed
+                Err(Errno::NODEV) => return Ok(ResetOutcome::Reenumerated),
+                Err(Errno::BUSY) => {
+                    return Err(Error::new_os(
+                        ErrorKind::Busy,
+                        "device is busy",
+                        Errno::BUSY,
+                    ))
+                }
+                Err(e) => return Err(Error::new_os(ErrorKind::Other, "failed to reset device", e)),
+            }
+
+            let descriptors = match read_all_from_fd(&self.fd) {
+                Ok(d) => d,
+                Err(e) if e.raw_os_error() == Some(Errno::NODEV.raw_os_error()) => {
+                    return Ok(ResetOutcome::Reenumerated)
+                }
+                Err(e) => {
+                    return Err(Error::new_io(
+                        ErrorKind::Other,
+                        "failed to read descriptors",
+                        e,
+                    ))
+                }
+            };
+            if descriptors != self.descriptors {
+                debug!(
+                    "Descriptors changed after reset of device {}",
+                    self.events_id
+                );
+                return Ok(ResetOutcome::Reenumerated);
+            }
+
+            #[cfg(not(target_os = "android"))]
+            if read_devnum() != devnum {
+                debug!("Address changed after reset of device {}", self.events_id);
+                return Ok(ResetOutcome::Reenumerated);
+            }
+
+            let active_config = guess_active_configuration(&self.fd, &self.descriptors);
+            self.active_config.store(active_config, Ordering::SeqCst);
+            Ok(ResetOutcome::Retained)
+        })
+    }
+
     pub fn control_in(
         self: Arc<Self>,
         data: ControlIn,
@@ -372,6 +578,19 @@ impl LinuxDevice {
         })
     }
 
+    pub fn control_in_buffer(
+        self: Arc<Self>,
+        data: ControlIn,
+        buffer: Buffer,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Completion> {
+        let t = TransferData::new_control_in_buffer(data, buffer);
+        TransferFuture::new(t, |t| self.submit_timeout(t, timeout)).map(move |mut t| {
+            drop(self); // ensure device stays alive
+            t.take_control_in_completion()
+        })
+    }
+
     pub fn control_out(
         self: Arc<Self>,
         data: ControlOut,
@@ -405,10 +624,12 @@ impl LinuxDevice {
             dev = self.events_id
         );
         Ok(Arc::new(LinuxInterface {
+            resets: self.resets.load(Ordering::SeqCst),
             device: self,
             interface_number,
             reattach,
             state: Mutex::new(Default::default()),
+            closed: CloseNotify::default(),
         }))
     }
 
@@ -432,6 +653,37 @@ impl LinuxDevice {
         })
     }
 
+    pub(crate) fn kernel_driver(&self, interface_number: u8) -> Result<Option<String>, Error> {
+        match usbfs::get_driver(&self.fd, interface_number) {
+            Ok(name) => Ok(Some(String::from_utf8_lossy(&name).into_owned())),
+            Err(Errno::NODATA) => Ok(None),
+            Err(e @ Errno::INVAL) => {
+                Err(Error::new_os(ErrorKind::NotFound, "interface not found", e))
+            }
+            Err(e @ Errno::NODEV) => Err(Error::new_os(
+                ErrorKind::Disconnected,
+                "device disconnected",
+                e,
+            )),
+            Err(e) => Err(Error::new_os(
+                ErrorKind::Other,
+                "failed to get kernel driver",
+                e,
+            )),
+        }
+    }
+
+    pub(crate) fn interface_claim_status(
+        &self,
+        interface_number: u8,
+    ) -> Result<ClaimStatus, Error> {
+        Ok(match self.kernel_driver(interface_number)? {
+            None => ClaimStatus::Free,
+            Some(driver) if driver == "usbfs" => ClaimStatus::Claimed,
+            Some(driver) => ClaimStatus::KernelDriver(driver),
+        })
+    }
+
     #[cfg(target_os = "linux")]
     pub(crate) fn detach_kernel_driver(
         self: &Arc<Self>,
@@ -459,6 +711,15 @@ impl LinuxDevice {
     }
 
     pub(crate) fn submit(&self, transfer: Idle<TransferData>) -> Pending<TransferData> {
+        self.try_submit(transfer).unwrap_or_else(|pending| pending)
+    }
+
+    /// Submit a transfer, returning `Err` with the transfer already completed
+    /// if the kernel rejected it.
+    fn try_submit(
+        &self,
+        transfer: Idle<TransferData>,
+    ) -> Result<Pending<TransferData>, Pending<TransferData>> {
         let len = transfer.urb().buffer_length;
         let pending = transfer.pre_submit();
         let urb = pending.urb_ptr();
@@ -474,17 +735,25 @@ impl LinuxDevice {
                 let u = &mut *urb;
                 debug!("Failed to submit URB {urb:?}: {len} bytes on ep {ep:x}: {e} {u:?}");
                 u.actual_length = 0;
-                u.status = e.raw_os_error();
+                u.status = if e == Errno::REMOTEIO
+                    && u.flags & usbfs::USBDEVFS_URB_BULK_CONTINUATION != 0
+                {
+                    // The kernel rejects continuation URBs once an earlier URB of
+                    // the transfer has ended it, which is a cancellation.
+                    Errno::CONNRESET.raw_os_error()
+                } else {
+                    e.raw_os_error()
+                };
                 notify_completion::<super::TransferData>(pending.as_ptr().cast());
-            } else {
-                debug!("Submitted URB {urb:?}: {len} bytes on ep {ep:x}");
+                return Err(pending);
             }
+            debug!("Submitted URB {urb:?}: {len} bytes on ep {ep:x}");
         };
 
-        pending
+        Ok(pending)
     }
 
-    fn submit_timeout(
+    pub(crate) fn submit_timeout(
         &self,
         mut transfer: Idle<TransferData>,
         timeout: Duration,
@@ -519,6 +788,46 @@ impl LinuxDevice {
         }
     }
 
+    #[cfg(target_os = "linux")]
+    pub(crate) fn state(&self) -> Result<crate::hotplug::DeviceState, Error> {
+        // GET_SPEED does not perform IO, but fails once the device is gone,
+        // even if another device has since appeared at the same sysfs path.
+        usbfs::get_speed(&self.fd).map_err(|e| match e {
+            Errno::NODEV => Error::new_os(ErrorKind::Disconnected, "device disconnected", e),
+            _ => Error::new_os(ErrorKind::Other, "failed to query device state", e),
+        })?;
+
+        let Some(sysfs) = self.sysfs.as_ref() else {
+            return Ok(crate::hotplug::DeviceState {
+                suspended: None,
+                configuration: None,
+            });
+        };
+
+        let suspended = sysfs
+            .read_attr::<String>("power/runtime_status")
+            .ok()
+            .map(|s| s == "suspended");
+
+        // The attribute is empty while the device is unconfigured.
+        let configuration = sysfs
+            .read_attr::<String>("bConfigurationValue")
+            .ok()
+            .map(|s| s.parse().unwrap_or(0));
+        if let Some(configuration) = configuration {
+            self.active_config.store(configuration, Ordering::SeqCst);
+        }
+
+        Ok(crate::hotplug::DeviceState {
+            suspended,
+            configuration,
+        })
+    }
+
+    pub(crate) fn fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+
     pub(crate) fn speed(&self) -> Option<Speed> {
         usbfs::get_speed(&self.fd)
             .inspect_err(|e| log::error!("USBDEVFS_GET_SPEED failed: {e}"))
@@ -599,8 +908,8 @@ fn request_configuration(fd: &OwnedFd) -> Result<u8, ()> {
 impl Drop for LinuxDevice {
     fn drop(&mut self) {
         debug!("Closing device {}", self.events_id);
-        events::unregister_fd(self.fd.as_fd());
-        events::unregister_fd(self.timerfd.as_fd());
+        self.unregister_events(self.fd.as_fd());
+        self.unregister_events(self.timerfd.as_fd());
         DEVICES.lock().unwrap().remove(self.events_id);
     }
 }
@@ -610,6 +919,12 @@ pub(crate) struct LinuxInterface {
     pub(crate) device: Arc<LinuxDevice>,
     reattach: bool,
     state: Mutex<InterfaceState>,
+
+    /// The device's `resets` count when the interface was claimed.
+    resets: usize,
+
+    /// Notifies `wait_closed` once the interface is released.
+    pub(crate) closed: CloseNotify,
 }
 
 #[derive(Default)]
@@ -627,6 +942,15 @@ impl LinuxInterface {
         self.device.clone().control_in(data, timeout)
     }
 
+    pub fn control_in_buffer(
+        &self,
+        data: ControlIn,
+        buffer: Buffer,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Completion> {
+        self.device.clone().control_in_buffer(data, buffer, timeout)
+    }
+
     pub fn control_out(
         &self,
         data: ControlOut,
@@ -636,14 +960,76 @@ impl LinuxInterface {
     }
 
     pub fn get_alt_setting(&self) -> u8 {
+        if self.check_claimed().is_err() {
+            return 0;
+        }
         self.state.lock().unwrap().alt_setting
     }
 
+    /// Fail if a reset released the interface after it was claimed.
+    fn check_claimed(&self) -> Result<(), Error> {
+        if self.device.resets.load(Ordering::SeqCst) != self.resets {
+            return Err(Error::new(
+                ErrorKind::Other,
+                "interface was released by a device reset",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn update_alt_setting(&self, alt_setting: u8) {
+        self.state.lock().unwrap().alt_setting = alt_setting;
+    }
+
+    pub fn set_autosuspend(&self, enable: bool) -> Result<(), Error> {
+        let r = if enable {
+            usbfs::allow_suspend(&self.device.fd)
+        } else {
+            usbfs::forbid_suspend(&self.device.fd)
+        };
+        r.map_err(|e| match e {
+            Errno::NODEV => Error::new_os(ErrorKind::Disconnected, "device disconnected", e),
+            Errno::NOTTY => Error::new_os(ErrorKind::Unsupported, "not supported by kernel", e),
+            _ => Error::new_os(ErrorKind::Other, "failed to set autosuspend", e),
+        })
+    }
+
+    pub fn set_autosuspend_delay(&self, delay: Duration) -> Result<(), Error> {
+        #[cfg(not(target_os = "android"))]
+        {
+            let Some(sysfs) = self.device.sysfs.as_ref() else {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    "device has no sysfs path",
+                ));
+            };
+            let ms = delay.as_millis().min(i32::MAX as u128);
+            sysfs
+                .write_attr("power/autosuspend_delay_ms", &ms.to_string())
+                .map_err(|e| match e.kind() {
+                    std::io::ErrorKind::PermissionDenied => {
+                        Error::new_io(ErrorKind::PermissionDenied, "permission denied", e)
+                    }
+                    _ => Error::new_io(ErrorKind::Other, "failed to set autosuspend delay", e),
+                })
+        }
+
+        #[cfg(target_os = "android")]
+        {
+            let _ = delay;
+            Err(Error::new(
+                ErrorKind::Unsupported,
+                "setting autosuspend delay not supported on Android",
+            ))
+        }
+    }
+
     pub fn set_alt_setting(
         self: Arc<Self>,
         alt_setting: u8,
     ) -> impl MaybeFuture<Output = Result<(), Error>> {
         Blocking::new(move || {
+            self.check_claimed()?;
             let mut state = self.state.lock().unwrap();
             if !state.endpoints.is_empty() {
                 return Err(Error::new(
@@ -678,6 +1064,7 @@ impl LinuxInterface {
         let address = descriptor.address();
         let ep_type = descriptor.transfer_type();
         let max_packet_size = descriptor.max_packet_size();
+        self.check_claimed()?;
 
         let mut state = self.state.lock().unwrap();
 
@@ -694,8 +1081,13 @@ impl LinuxInterface {
                 notify: Notify::new(),
             }),
             max_packet_size,
+            max_transfer_size: MAX_TRANSFER_SIZE,
             pending: VecDeque::new(),
             idle_transfer: None,
+            auto_zlp: false,
+            no_interrupt: false,
+            priority: TransferPriority::Normal,
+            timestamps: false,
         })
     }
 }
@@ -718,15 +1110,34 @@ impl Drop for LinuxInterface {
     }
 }
 
+/// Largest buffer submitted as a single URB.
+///
+/// usbfs limits the total size of pending transfers to `usbfs_memory_mb` (16
+/// MiB by default), so larger transfers are split to leave room for others.
+const MAX_TRANSFER_SIZE: usize = 4 * 1024 * 1024;
+
 pub(crate) struct LinuxEndpoint {
     inner: Arc<EndpointInner>,
 
     pub(crate) max_packet_size: usize,
 
+    pub(crate) max_transfer_size: usize,
+
     /// A queue of pending transfers, expected to complete in order
     pending: VecDeque<Pending<super::TransferData>>,
 
     idle_transfer: Option<Idle<TransferData>>,
+
+    /// Set `USBDEVFS_URB_ZERO_PACKET` on OUT transfers
+    auto_zlp: bool,
+
+    /// Set `USBDEVFS_URB_NO_INTERRUPT` on submitted transfers
+    no_interrupt: bool,
+
+    priority: TransferPriority,
+
+    /// Record the completion time of transfers
+    timestamps: bool,
 }
 
 struct EndpointInner {
@@ -745,6 +1156,31 @@ impl LinuxEndpoint {
         self.pending.len()
     }
 
+    pub(crate) fn ready(&self) -> usize {
+        self.pending.iter().take_while(|t| t.is_complete()).count()
+    }
+
+    pub(crate) fn set_notify_callback(&self, callback: Option<Arc<dyn Fn() + Send + Sync>>) {
+        self.inner.notify.set_callback(callback);
+    }
+
+    pub(crate) fn interface(&self) -> &Arc<LinuxInterface> {
+        &self.inner.interface
+    }
+
+    pub(crate) fn native_handle(&self, index: usize) -> Option<*mut c_void> {
+        let transfer = self.pending.get(index)?;
+        Some(unsafe { (*transfer.as_ptr()).urb_ptr() }.cast())
+    }
+
+    pub(crate) fn cancel(&mut self, index: usize) -> bool {
+        let Some(transfer) = self.pending.get_mut(index) else {
+            return false;
+        };
+        self.inner.interface.device.cancel(transfer);
+        true
+    }
+
     pub(crate) fn cancel_all(&mut self) {
         // Cancel transfers in reverse order to ensure subsequent transfers
         // can't complete out of order while we're going through them.
@@ -753,34 +1189,166 @@ impl LinuxEndpoint {
         }
     }
 
-    fn get_transfer(&mut self) -> Idle<TransferData> {
-        self.idle_transfer.take().unwrap_or_else(|| {
-            Idle::new(
-                self.inner.clone(),
-                super::TransferData::new(self.inner.address, self.inner.ep_type),
-            )
-        })
+    fn get_transfer(&mut self, num_iso_packets: usize) -> Idle<TransferData> {
+        let mut transfer = self
+            .idle_transfer
+            .take()
+            .filter(|t| t.iso_packets_capacity() >= num_iso_packets)
+            .unwrap_or_else(|| {
+                Idle::new(
+                    self.inner.clone(),
+                    super::TransferData::new_with_iso_packets(
+                        self.inner.address,
+                        self.inner.ep_type,
+                        num_iso_packets,
+                    ),
+                )
+            });
+        transfer.deadline = None;
+        transfer.urb_mut().flags = if self.auto_zlp {
+            usbfs::USBDEVFS_URB_ZERO_PACKET
+        } else {
+            0
+        };
+        if self.no_interrupt {
+            transfer.urb_mut().flags |= usbfs::USBDEVFS_URB_NO_INTERRUPT;
+        }
+        transfer.urb_mut().number_of_packets_or_stream_id = 0;
+        transfer.priority = self.priority;
+        transfer.set_timestamps(self.timestamps);
+        transfer
     }
 
     pub(crate) fn submit(&mut self, data: Buffer) {
-        let mut transfer = self.get_transfer();
+        let mut transfer = self.get_transfer(0);
+        transfer.set_buffer(data);
+        self.pending
+            .push_back(self.inner.interface.device.submit(transfer));
+    }
+
+    /// Submit independent transfers, returning how many the kernel accepted.
+    ///
+    /// With `batch_interrupts`, all but the last URB are flagged with
+    /// `USBDEVFS_URB_NO_INTERRUPT`.
+    pub(crate) fn submit_batch(&mut self, bufs: Vec<Buffer>, batch_interrupts: bool) -> usize {
+        self.pending.reserve(bufs.len());
+        let last = bufs.len().saturating_sub(1);
+        let mut accepted = 0;
+        for (i, data) in bufs.into_iter().enumerate() {
+            let mut transfer = self.get_transfer(0);
+            transfer.set_buffer(data);
+            if batch_interrupts && i != last {
+                transfer.urb_mut().flags |= usbfs::USBDEVFS_URB_NO_INTERRUPT;
+            }
+            let r = self.inner.interface.device.try_submit(transfer);
+            accepted += r.is_ok() as usize;
+            self.pending.push_back(r.unwrap_or_else(|pending| pending));
+        }
+        accepted
+    }
+
+    /// Submit one buffer of a bulk transfer split across multiple URBs.
+    ///
+    /// All but the `first` URB are continuations, which the kernel cancels if
+    /// an earlier URB of the same transfer failed or ended with a short
+    /// packet. For IN, a short packet in any URB but the `last` ends the
+    /// transfer. For OUT, only the `last` URB sends a zero-length packet.
+    pub(crate) fn submit_bulk_continuation(
+        &mut self,
+        data: Buffer,
+        first: bool,
+        last: bool,
+        timeout: Option<Duration>,
+    ) {
+        debug_assert_eq!(self.inner.ep_type, TransferType::Bulk);
+        let mut transfer = self.get_transfer(0);
+        transfer.set_buffer(data);
+        if !first {
+            transfer.urb_mut().flags |= usbfs::USBDEVFS_URB_BULK_CONTINUATION;
+        }
+        if !last {
+            match Direction::from_address(self.inner.address) {
+                Direction::In => transfer.urb_mut().flags |= usbfs::USBDEVFS_URB_SHORT_NOT_OK,
+                Direction::Out => transfer.urb_mut().flags &= !usbfs::USBDEVFS_URB_ZERO_PACKET,
+            }
+        }
+        let device = &self.inner.interface.device;
+        self.pending.push_back(match timeout {
+            Some(timeout) => device.submit_timeout(transfer, timeout),
+            None => device.submit(transfer),
+        });
+    }
+
+    pub(crate) fn set_auto_zlp(&mut self, enable: bool) -> Result<(), Error> {
+        self.auto_zlp = enable;
+        Ok(())
+    }
+
+    pub(crate) fn set_priority(&mut self, priority: TransferPriority) {
+        self.priority = priority;
+    }
+
+    pub(crate) fn set_no_interrupt(&mut self, enable: bool) {
+        self.no_interrupt = enable;
+    }
+
+    pub(crate) fn set_timestamps(&mut self, enable: bool) {
+        self.timestamps = enable;
+    }
+
+    pub(crate) fn submit_stream(&mut self, data: Buffer, stream_id: u32) {
+        let mut transfer = self.get_transfer(0);
+        transfer.set_buffer(data);
+        transfer.urb_mut().number_of_packets_or_stream_id = stream_id;
+        self.pending
+            .push_back(self.inner.interface.device.submit(transfer));
+    }
+
+    pub(crate) fn submit_iso(
+        &mut self,
+        mut data: Buffer,
+        packet_lengths: &[usize],
+        schedule: IsoSchedule,
+    ) {
+        debug_assert!(packet_lengths.len() <= usbfs::USBDEVFS_MAX_ISO_PACKETS);
+        if Direction::from_address(self.inner.address) == Direction::In {
+            // Received packets are not contiguous, so zero the buffer so that
+            // the gaps left by short packets are initialized.
+            data.clear();
+            data.extend_fill(data.requested_len(), 0);
+        }
+        let mut transfer = self.get_transfer(packet_lengths.len());
         transfer.set_buffer(data);
+        transfer.set_iso_packets(packet_lengths, schedule);
+        // `IsoCompletion::completed_at` is always recorded.
+        transfer.set_timestamps(true);
         self.pending
             .push_back(self.inner.interface.device.submit(transfer));
     }
 
+    pub(crate) fn submit_timeout(&mut self, data: Buffer, timeout: Duration) {
+        let mut transfer = self.get_transfer(0);
+        transfer.set_buffer(data);
+        self.pending.push_back(
+            self.inner
+                .interface
+                .device
+                .submit_timeout(transfer, timeout),
+        );
+    }
+
     pub(crate) fn submit_err(&mut self, data: Buffer, error: TransferError) {
-        assert_eq!(error, TransferError::InvalidArgument);
-        let mut transfer = self.get_transfer();
+        let mut transfer = self.get_transfer(0);
         transfer.set_buffer(data);
-        transfer.urb_mut().status = Errno::INVAL.raw_os_error();
+        transfer.set_submit_error(error);
         self.pending.push_back(transfer.simulate_complete());
     }
 
     pub(crate) fn poll_next_complete(&mut self, cx: &mut Context) -> Poll<Completion> {
         self.inner.notify.subscribe(cx);
         if let Some(mut transfer) = take_completed_from_queue(&mut self.pending) {
-            let completion = transfer.take_completion();
+            let mut completion = transfer.take_completion();
+            completion.completed_at = transfer.completed_at();
             self.idle_transfer = Some(transfer);
             Poll::Ready(completion)
         } else {
@@ -791,7 +1359,31 @@ impl LinuxEndpoint {
     pub(crate) fn wait_next_complete(&mut self, timeout: Duration) -> Option<Completion> {
         self.inner.notify.wait_timeout(timeout, || {
             take_completed_from_queue(&mut self.pending).map(|mut transfer| {
-                let completion = transfer.take_completion();
+                let mut completion = transfer.take_completion();
+                completion.completed_at = transfer.completed_at();
+                self.idle_transfer = Some(transfer);
+                completion
+            })
+        })
+    }
+
+    pub(crate) fn poll_next_complete_iso(&mut self, cx: &mut Context) -> Poll<IsoCompletion> {
+        self.inner.notify.subscribe(cx);
+        if let Some(mut transfer) = take_completed_from_queue(&mut self.pending) {
+            let mut completion = transfer.take_iso_completion();
+            completion.completed_at = transfer.completed_at();
+            self.idle_transfer = Some(transfer);
+            Poll::Ready(completion)
+        } else {
+            Poll::Pending
+        }
+    }
+
+    pub(crate) fn wait_next_complete_iso(&mut self, timeout: Duration) -> Option<IsoCompletion> {
+        self.inner.notify.wait_timeout(timeout, || {
+            take_completed_from_queue(&mut self.pending).map(|mut transfer| {
+                let mut completion = transfer.take_iso_completion();
+                completion.completed_at = transfer.completed_at();
                 self.idle_transfer = Some(transfer);
                 completion
             })
@@ -810,6 +1402,42 @@ impl LinuxEndpoint {
         })
     }
 
+    pub(crate) fn alloc_streams(
+        &self,
+        num_streams: u32,
+    ) -> impl MaybeFuture<Output = Result<u32, Error>> {
+        let inner = self.inner.clone();
+        Blocking::new(move || {
+            let endpoint = inner.address;
+            debug!("Allocating {num_streams} streams on endpoint {endpoint:02x}");
+            usbfs::alloc_streams(&inner.interface.device.fd, endpoint, num_streams)
+                .map(|n| n as u32)
+                .map_err(|e| match e {
+                    Errno::NODEV => {
+                        Error::new_os(ErrorKind::Disconnected, "device disconnected", e)
+                    }
+                    Errno::INVAL | Errno::NOSYS => Error::new_os(
+                        ErrorKind::Unsupported,
+                        "bulk streams not supported by device or host controller",
+                        e,
+                    ),
+                    _ => Error::new_os(ErrorKind::Other, "failed to allocate streams", e),
+                })
+        })
+    }
+
+    pub(crate) fn free_streams(&self) -> impl MaybeFuture<Output = Result<(), Error>> {
+        let inner = self.inner.clone();
+        Blocking::new(move || {
+            let endpoint = inner.address;
+            debug!("Freeing streams on endpoint {endpoint:02x}");
+            usbfs::free_streams(&inner.interface.device.fd, endpoint).map_err(|e| match e {
+                Errno::NODEV => Error::new_os(ErrorKind::Disconnected, "device disconnected", e),
+                _ => Error::new_os(ErrorKind::Other, "failed to free streams", e),
+            })
+        })
+    }
+
     pub(crate) fn allocate(&self, len: usize) -> Result<Buffer, Errno> {
         Buffer::mmap(&self.inner.interface.device.fd, len).inspect_err(|e| {
             warn!(
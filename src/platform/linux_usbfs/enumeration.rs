@@ -1,17 +1,19 @@
 use log::debug;
 use log::warn;
+use std::collections::HashSet;
 use std::fs;
 use std::io;
 use std::num::ParseIntError;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
-use crate::enumeration::InterfaceInfo;
+use crate::enumeration::{InterfaceInfo, StoredString};
 use crate::maybe_future::{MaybeFuture, Ready};
 use crate::ErrorKind;
 use crate::{BusInfo, DeviceInfo, Error, Speed, UsbControllerType};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SysfsPath(pub(crate) PathBuf);
 
 #[derive(Debug)]
@@ -64,6 +66,33 @@ impl SysfsPath {
         self.parse_attr(attr, |s| s.parse())
     }
 
+    pub(crate) fn write_attr(&self, attr: &str, value: &str) -> Result<(), io::Error> {
+        fs::write(self.0.join(attr), value)
+    }
+
+    /// Read an attribute, mapping failure to an [`Error`] with `message`.
+    pub(crate) fn read_attr_checked<T: FromStr>(
+        &self,
+        attr: &str,
+        message: &'static str,
+    ) -> Result<T, Error> {
+        self.read_attr(attr).map_err(|e| match e.1 {
+            SysfsErrorKind::Io(e) => sysfs_io_error(e, message),
+            SysfsErrorKind::Parse(_) => Error::new(ErrorKind::Other, message),
+        })
+    }
+
+    /// Write an attribute, mapping failure to an [`Error`] with `message`.
+    pub(crate) fn write_attr_checked(
+        &self,
+        attr: &str,
+        value: &str,
+        message: &'static str,
+    ) -> Result<(), Error> {
+        self.write_attr(attr, value)
+            .map_err(|e| sysfs_io_error(e, message))
+    }
+
     fn read_attr_hex<T: FromHexStr>(&self, attr: &str) -> Result<T, SysfsError> {
         self.parse_attr(attr, |s| T::from_hex_str(s.strip_prefix("0x").unwrap_or(s)))
     }
@@ -84,6 +113,47 @@ impl SysfsPath {
         })?
     }
 
+    /// Read an arbitrary attribute as a string, for the public API.
+    ///
+    /// Unlike `read_attr_checked`, a missing attribute is reported as
+    /// `NotFound` rather than as a disconnected device.
+    pub(crate) fn read_attr_string(&self, attr: &str) -> Result<String, Error> {
+        fs::read_to_string(self.0.join(attr))
+            .map(|s| s.trim_end_matches('\n').to_owned())
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound if self.0.exists() => {
+                    Error::new_io(ErrorKind::NotFound, "sysfs attribute not found", e)
+                }
+                _ => sysfs_io_error(e, "failed to read sysfs attribute"),
+            })
+    }
+
+    /// Read the properties of the device from its `uevent` attribute and the
+    /// udev database.
+    ///
+    /// Properties set by udev rules are only in the database, which is
+    /// missing if udev is not running (e.g. in some containers). In that
+    /// case, only the kernel's properties are returned.
+    pub(crate) fn udev_properties(&self) -> Result<Vec<(String, String)>, Error> {
+        let uevent = self.read_attr_string("uevent")?;
+        let mut properties: Vec<(String, String)> = parse_uevent(&uevent).collect();
+
+        let dev = self.read_attr_string("dev")?;
+        match fs::read_to_string(format!("/run/udev/data/c{}", dev.trim())) {
+            Ok(db) => {
+                for (key, value) in parse_udev_db(&db) {
+                    match properties.iter_mut().find(|(k, _)| *k == key) {
+                        Some((_, v)) => *v = value,
+                        None => properties.push((key, value)),
+                    }
+                }
+            }
+            Err(e) => debug!("Failed to read udev database for {dev}: {e}"),
+        }
+
+        Ok(properties)
+    }
+
     fn children(&self) -> impl Iterator<Item = SysfsPath> {
         fs::read_dir(&self.0)
             .ok()
@@ -95,6 +165,21 @@ impl SysfsPath {
     }
 }
 
+/// Parse the `KEY=VALUE` lines of a sysfs `uevent` attribute.
+fn parse_uevent(s: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    s.lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(k, v)| (k.to_owned(), v.to_owned()))
+}
+
+/// Parse the properties from a udev database file in `/run/udev/data`,
+/// which are stored in lines like `E:KEY=VALUE`.
+fn parse_udev_db(s: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    s.lines()
+        .filter_map(|line| line.strip_prefix("E:")?.split_once('='))
+        .map(|(k, v)| (k.to_owned(), v.to_owned()))
+}
+
 trait FromHexStr: Sized {
     fn from_hex_str(s: &str) -> Result<Self, ParseIntError>;
 }
@@ -111,6 +196,16 @@ impl FromHexStr for u16 {
     }
 }
 
+fn sysfs_io_error(e: io::Error, message: &'static str) -> Error {
+    match e.kind() {
+        io::ErrorKind::NotFound => Error::new_io(ErrorKind::Disconnected, "device disconnected", e),
+        io::ErrorKind::PermissionDenied => {
+            Error::new_io(ErrorKind::PermissionDenied, "permission denied", e)
+        }
+        _ => Error::new_io(ErrorKind::Other, message, e),
+    }
+}
+
 fn sysfs_list_usb() -> Result<fs::ReadDir, Error> {
     fs::read_dir("/sys/bus/usb/devices/").map_err(|e| match e.kind() {
         io::ErrorKind::NotFound => {
@@ -127,32 +222,111 @@ fn sysfs_list_usb() -> Result<fs::ReadDir, Error> {
 
 pub fn list_devices() -> impl MaybeFuture<Output = Result<impl Iterator<Item = DeviceInfo>, Error>>
 {
+    list_devices_by_id(None, None)
+}
+
+/// List devices, skipping those that don't match `vendor_id` and
+/// `product_id` before probing them.
+pub fn list_devices_by_id(
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+) -> impl MaybeFuture<Output = Result<impl Iterator<Item = DeviceInfo>, Error>> {
     Ready((|| {
-        Ok(sysfs_list_usb()?.flat_map(|entry| {
-            let path = entry.ok()?.path();
-            let name = path.file_name()?;
-
-            // Device names look like `1-6` or `1-6.4.2`
-            // We'll ignore:
-            //  * root hubs (`usb1`) -- they're not useful to talk to and are not exposed on other platforms
-            //  * interfaces (`1-6:1.0`)
-            if !name
-                .as_encoded_bytes()
-                .iter()
-                .all(|c| matches!(c, b'0'..=b'9' | b'-' | b'.'))
-            {
+        Ok(sysfs_list_device_paths()?.flat_map(move |path| {
+            let id_matches = |attr, id: Option<u16>| {
+                id.map_or(true, |id| path.read_attr_hex::<u16>(attr).ok() == Some(id))
+            };
+            if !id_matches("idVendor", vendor_id) || !id_matches("idProduct", product_id) {
                 return None;
             }
 
-            let path = path.canonicalize().ok()?;
-
-            probe_device(SysfsPath(path))
+            probe_device(path)
                 .inspect_err(|e| warn!("{e}; ignoring device"))
                 .ok()
         }))
     })())
 }
 
+/// List connected devices, probing only those not included in `known`.
+///
+/// Returns the ID of every device, with its `DeviceInfo` if it was probed.
+pub fn scan_devices(
+    known: HashSet<crate::DeviceId>,
+) -> impl MaybeFuture<Output = Result<Vec<(crate::DeviceId, Option<DeviceInfo>)>, Error>> {
+    Ready((|| {
+        Ok(sysfs_list_device_paths()?
+            .flat_map(|path| {
+                let id = crate::DeviceId(super::DeviceId {
+                    bus: path.read_attr("busnum").ok()?,
+                    addr: path.read_attr("devnum").ok()?,
+                });
+                if known.contains(&id) {
+                    return Some((id, None));
+                }
+                probe_device(path)
+                    .inspect_err(|e| warn!("{e}; ignoring device"))
+                    .ok()
+                    .map(|d| (id, Some(d)))
+            })
+            .collect())
+    })())
+}
+
+/// Check whether the device node could be opened, without opening it.
+pub fn check_access(busnum: u8, device_address: u8) -> Result<(), Error> {
+    use rustix::fs::{accessat, Access, AtFlags, CWD};
+    use rustix::io::Errno;
+
+    let path = format!("/dev/bus/usb/{busnum:03}/{device_address:03}");
+    let check = |access| accessat(CWD, &path, access, AtFlags::EACCESS);
+    match check(Access::READ_OK | Access::WRITE_OK) {
+        Ok(()) => Ok(()),
+        Err(Errno::NOENT) => Err(Error::new(ErrorKind::Disconnected, "device not found")),
+        Err(e @ (Errno::ACCESS | Errno::PERM | Errno::ROFS)) => {
+            if check(Access::READ_OK).is_ok() {
+                Err(Error::new_os(
+                    ErrorKind::PermissionDenied,
+                    "device node is readable but not writable, so it can only be opened read-only",
+                    e,
+                ))
+            } else {
+                Err(Error::new_os(
+                    ErrorKind::PermissionDenied,
+                    "permission denied to open device node",
+                    e,
+                ))
+            }
+        }
+        Err(e) => Err(Error::new_os(
+            ErrorKind::Other,
+            "failed to check access to device node",
+            e,
+        )),
+    }
+}
+
+/// Iterate over the sysfs paths of devices other than root hubs.
+fn sysfs_list_device_paths() -> Result<impl Iterator<Item = SysfsPath>, Error> {
+    Ok(sysfs_list_usb()?.flat_map(|entry| {
+        let path = entry.ok()?.path();
+        let name = path.file_name()?;
+
+        // Device names look like `1-6` or `1-6.4.2`
+        // We'll ignore:
+        //  * root hubs (`usb1`) -- they're not useful to talk to and are not exposed on other platforms
+        //  * interfaces (`1-6:1.0`)
+        if !name
+            .as_encoded_bytes()
+            .iter()
+            .all(|c| matches!(c, b'0'..=b'9' | b'-' | b'.'))
+        {
+            return None;
+        }
+
+        Some(SysfsPath(path.canonicalize().ok()?))
+    }))
+}
+
 pub fn list_root_hubs() -> Result<impl Iterator<Item = DeviceInfo>, Error> {
     Ok(sysfs_list_usb()?.filter_map(|entry| {
         let path = entry.ok()?.path();
@@ -191,6 +365,32 @@ pub fn list_buses() -> impl MaybeFuture<Output = Result<impl Iterator<Item = Bus
     })())
 }
 
+/// Probe the device with the specified bus number and address, without
+/// listing all devices.
+pub fn probe_bus_addr(busnum: u8, device_address: u8) -> Result<DeviceInfo, Error> {
+    if busnum == 0 || !(1..=128).contains(&device_address) {
+        return Err(Error::new(ErrorKind::Disconnected, "device not found"));
+    }
+
+    // usbfs device nodes have major number 189, and minor numbers assigned
+    // from the bus number and address.
+    let minor = (u32::from(busnum) - 1) * 128 + u32::from(device_address) - 1;
+    probe_sysfs_path(Path::new(&format!("/sys/dev/char/189:{minor}")))
+}
+
+/// Probe the device at the specified sysfs path, such as
+/// `/sys/bus/usb/devices/1-6`.
+pub fn probe_sysfs_path(path: &Path) -> Result<DeviceInfo, Error> {
+    let path = path
+        .canonicalize()
+        .map_err(|e| sysfs_io_error(e, "failed to resolve sysfs path"))?;
+
+    probe_device(SysfsPath(path)).map_err(|e| match e.1 {
+        SysfsErrorKind::Io(e) => sysfs_io_error(e, "failed to probe device"),
+        SysfsErrorKind::Parse(_) => Error::new(ErrorKind::Other, "failed to probe device"),
+    })
+}
+
 pub fn probe_device(path: SysfsPath) -> Result<DeviceInfo, SysfsError> {
     debug!("Probing device {:?}", path.0);
 
@@ -208,6 +408,11 @@ pub fn probe_device(path: SysfsPath) -> Result<DeviceInfo, SysfsError> {
         })
         .unwrap_or_default();
 
+    // The kernel only creates the string attributes for nonzero indexes, and
+    // the descriptor tells whether a missing attribute is missing from the
+    // device.
+    let device_desc = fs::read(path.0.join("descriptors")).unwrap_or_default();
+
     Ok(DeviceInfo {
         busnum,
         bus_id: format!("{busnum:03}"),
@@ -230,9 +435,18 @@ pub fn probe_device(path: SysfsPath) -> Result<DeviceInfo, SysfsError> {
             .ok()
             .as_deref()
             .and_then(Speed::from_str),
-        manufacturer_string: path.read_attr("manufacturer").ok(),
-        product_string: path.read_attr("product").ok(),
-        serial_number: path.read_attr("serial").ok(),
+        manufacturer_string: StoredString::new(
+            path.read_attr("manufacturer").ok(),
+            string_index(&device_desc, 14),
+        ),
+        product_string: StoredString::new(
+            path.read_attr("product").ok(),
+            string_index(&device_desc, 15),
+        ),
+        serial_number: StoredString::new(
+            path.read_attr("serial").ok(),
+            string_index(&device_desc, 16),
+        ),
         interfaces: {
             let mut interfaces: Vec<_> = path
                 .children()
@@ -252,6 +466,7 @@ pub fn probe_device(path: SysfsPath) -> Result<DeviceInfo, SysfsError> {
                         subclass: i.read_attr_hex("bInterfaceSubClass").ok()?,
                         protocol: i.read_attr_hex("bInterfaceProtocol").ok()?,
                         interface_string: i.read_attr("interface").ok(),
+                        driver: i.readlink_attr_filename("driver").ok(),
                     })
                 })
                 .collect();
@@ -261,3 +476,24 @@ pub fn probe_device(path: SysfsPath) -> Result<DeviceInfo, SysfsError> {
         path,
     })
 }
+
+/// Get a string index from the device descriptor read from sysfs.
+fn string_index(device_desc: &[u8], offset: usize) -> Option<u8> {
+    device_desc
+        .get(offset)
+        .copied()
+        .filter(|_| device_desc.len() >= 18)
+}
+
+#[test]
+fn test_parse_udev_db() {
+    let db = "S:bus/usb/001/004\nI:5188203\nE:ID_MODEL=Widget\nE:ID_SERIAL=Acme_Widget_123\nE:MY_VAR=a=b\nG:uaccess\n";
+    assert_eq!(
+        parse_udev_db(db).collect::<Vec<_>>(),
+        [
+            ("ID_MODEL".to_owned(), "Widget".to_owned()),
+            ("ID_SERIAL".to_owned(), "Acme_Widget_123".to_owned()),
+            ("MY_VAR".to_owned(), "a=b".to_owned()),
+        ]
+    );
+}
@@ -4,6 +4,9 @@
 //! for events on usbfs devices and arbitrary file descriptors
 //! (used for udev hotplug).
 //!
+//! A device can instead be moved to a [`DedicatedLoop`], which polls only
+//! that device's file descriptors on a thread of its own.
+//!
 //! ### Why not share an event loop with `tokio` or `async-io`?
 //!
 //! This event loop will call USBFS_REAP_URB on the event thread and
@@ -14,12 +17,20 @@
 use crate::{Error, ErrorKind};
 use once_cell::sync::OnceCell;
 use rustix::{
-    event::epoll::{self, EventData, EventFlags},
+    event::{
+        epoll::{self, EventData, EventFlags},
+        eventfd, EventfdFlags,
+    },
     fd::{AsFd, BorrowedFd, OwnedFd},
     io::Errno,
 };
 use slab::Slab;
-use std::{mem::MaybeUninit, sync::Mutex, task::Waker, thread};
+use std::{
+    mem::MaybeUninit,
+    sync::{Arc, Mutex},
+    task::Waker,
+    thread,
+};
 
 use super::Device;
 
@@ -29,18 +40,21 @@ pub(crate) enum Tag {
     Device(usize),
     DeviceTimer(usize),
     Waker(usize),
+    Stop,
 }
 
 impl Tag {
     const DEVICE: u64 = 1;
     const DEVICE_TIMER: u64 = 2;
     const WAKER: u64 = 3;
+    const STOP: u64 = 4;
 
     fn as_event_data(&self) -> EventData {
         let (tag, id) = match *self {
             Tag::Device(id) => (Self::DEVICE, id),
             Tag::DeviceTimer(id) => (Self::DEVICE_TIMER, id),
             Tag::Waker(id) => (Self::WAKER, id),
+            Tag::Stop => (Self::STOP, 0),
         };
         EventData::new_u64((id as u64) << 3 | tag)
     }
@@ -52,6 +66,7 @@ impl Tag {
             (Self::DEVICE, id) => Tag::Device(id),
             (Self::DEVICE_TIMER, id) => Tag::DeviceTimer(id),
             (Self::WAKER, id) => Tag::Waker(id),
+            (Self::STOP, _) => Tag::Stop,
             _ => panic!("Invalid event data"),
         }
     }
@@ -82,9 +97,14 @@ pub(super) fn unregister_fd(fd: BorrowedFd) {
 }
 
 fn event_loop() {
-    let epoll_fd = EPOLL_FD.get().unwrap();
+    run(EPOLL_FD.get().unwrap());
+}
+
+/// Dispatch events from `epoll_fd` until a `Tag::Stop` event is received.
+fn run(epoll_fd: &OwnedFd) {
     let mut event_buf = [MaybeUninit::<epoll::Event>::uninit(); 4];
-    loop {
+    let mut stop = false;
+    while !stop {
         let events = match epoll::wait(epoll_fd, &mut event_buf, None) {
             Ok((events, _)) => events,
             Err(Errno::INTR) => &mut [],
@@ -101,11 +121,81 @@ fn event_loop() {
                         }
                     }
                 }
+                Tag::Stop => stop = true,
             }
         }
     }
 }
 
+/// An event loop with its own epoll instance and thread.
+///
+/// The thread exits when the `DedicatedLoop` is dropped. It is not joined,
+/// because the last reference to the device may be dropped on the thread
+/// itself.
+pub(crate) struct DedicatedLoop {
+    inner: Arc<DedicatedLoopInner>,
+}
+
+struct DedicatedLoopInner {
+    epoll_fd: OwnedFd,
+
+    /// Eventfd written to stop the thread. Owned by the thread as well, so
+    /// that it stays registered until the thread has seen the event.
+    stop_fd: OwnedFd,
+}
+
+impl DedicatedLoop {
+    pub(super) fn new() -> Result<Self, Error> {
+        let epoll_fd = epoll::create(epoll::CreateFlags::CLOEXEC).map_err(|e| {
+            Error::new_os(ErrorKind::Other, "failed to initialize epoll", e).log_error()
+        })?;
+
+        let stop_fd = eventfd(0, EventfdFlags::CLOEXEC | EventfdFlags::NONBLOCK).map_err(|e| {
+            Error::new_os(ErrorKind::Other, "failed to create eventfd", e).log_error()
+        })?;
+
+        epoll::add(
+            &epoll_fd,
+            &stop_fd,
+            Tag::Stop.as_event_data(),
+            EventFlags::IN,
+        )
+        .map_err(|e| Error::new_os(ErrorKind::Other, "failed to add epoll watch", e).log_error())?;
+
+        let inner = Arc::new(DedicatedLoopInner { epoll_fd, stop_fd });
+        let thread_inner = inner.clone();
+        thread::Builder::new()
+            .name("nusb-device-events".into())
+            .spawn(move || run(&thread_inner.epoll_fd))
+            .map_err(|e| {
+                Error::new_io(ErrorKind::Other, "failed to spawn event thread", e).log_error()
+            })?;
+
+        Ok(DedicatedLoop { inner })
+    }
+
+    pub(super) fn register_fd(
+        &self,
+        fd: BorrowedFd,
+        tag: Tag,
+        flags: EventFlags,
+    ) -> Result<(), Error> {
+        epoll::add(&self.inner.epoll_fd, fd, tag.as_event_data(), flags).map_err(|e| {
+            Error::new_os(ErrorKind::Other, "failed to add epoll watch", e).log_error()
+        })
+    }
+
+    pub(super) fn unregister_fd(&self, fd: BorrowedFd) {
+        epoll::delete(&self.inner.epoll_fd, fd).ok();
+    }
+}
+
+impl Drop for DedicatedLoop {
+    fn drop(&mut self) {
+        rustix::io::write(&self.inner.stop_fd, &1u64.to_ne_bytes()).ok();
+    }
+}
+
 static WAKERS: Mutex<Slab<Option<Waker>>> = Mutex::new(Slab::new());
 
 pub(crate) struct Async<T: AsFd> {
@@ -10,7 +10,7 @@ use rustix::{
 };
 use std::{mem::MaybeUninit, os::unix::prelude::BorrowedFd, path::Path, task::Poll};
 
-use crate::{hotplug::HotplugEvent, Error, ErrorKind};
+use crate::{hotplug::HotplugEvent, DeviceFilter, Error, ErrorKind};
 
 use super::{enumeration::probe_device, events::Async, SysfsPath};
 
@@ -19,10 +19,12 @@ const UDEV_MULTICAST_GROUP: u32 = 1 << 1;
 
 pub(crate) struct LinuxHotplugWatch {
     fd: Async<OwnedFd>,
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
 }
 
 impl LinuxHotplugWatch {
-    pub(crate) fn new() -> Result<Self, Error> {
+    pub(crate) fn new(filter: &DeviceFilter) -> Result<Self, Error> {
         let fd = socket_with(
             AddressFamily::NETLINK,
             SocketType::RAW,
@@ -39,11 +41,15 @@ impl LinuxHotplugWatch {
 
         Ok(LinuxHotplugWatch {
             fd: Async::new(fd)?,
+            vendor_id: filter.vendor_id,
+            product_id: filter.product_id,
         })
     }
 
     pub(crate) fn poll_next(&mut self, cx: &mut std::task::Context<'_>) -> Poll<HotplugEvent> {
-        if let Some(event) = try_receive_event(self.fd.inner.as_fd()) {
+        if let Some(event) =
+            try_receive_event(self.fd.inner.as_fd(), self.vendor_id, self.product_id)
+        {
             return Poll::Ready(event);
         }
 
@@ -55,7 +61,11 @@ impl LinuxHotplugWatch {
     }
 }
 
-fn try_receive_event(fd: BorrowedFd) -> Option<HotplugEvent> {
+fn try_receive_event(
+    fd: BorrowedFd,
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+) -> Option<HotplugEvent> {
     let mut buf = [MaybeUninit::uninit(); 8192];
 
     let (data, src) = match recvfrom(fd, &mut buf, RecvFlags::DONTWAIT) {
@@ -77,10 +87,14 @@ fn try_receive_event(fd: BorrowedFd) -> Option<HotplugEvent> {
         }
     }
 
-    parse_packet(data)
+    parse_packet(data, vendor_id, product_id)
 }
 
-fn parse_packet(buf: &[u8]) -> Option<HotplugEvent> {
+fn parse_packet(
+    buf: &[u8],
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+) -> Option<HotplugEvent> {
     if buf.len() < 24 {
         error!("packet too short: {buf:x?}");
         return None;
@@ -102,6 +116,7 @@ fn parse_packet(buf: &[u8]) -> Option<HotplugEvent> {
     let mut busnum = None;
     let mut devnum = None;
     let mut devpath = None;
+    let mut ids = None;
 
     for (k, v) in parse_properties(properties_buf) {
         trace!("uevent property {k} = {v}");
@@ -124,6 +139,9 @@ fn parse_packet(buf: &[u8]) -> Option<HotplugEvent> {
             "DEVPATH" => {
                 devpath = Some(v);
             }
+            "PRODUCT" => {
+                ids = parse_product(v);
+            }
             _ => {}
         }
     }
@@ -133,6 +151,14 @@ fn parse_packet(buf: &[u8]) -> Option<HotplugEvent> {
     let devnum = devnum?;
     let devpath = devpath?;
 
+    if vendor_id.is_some() || product_id.is_some() {
+        let (vid, pid) = ids?;
+        if vendor_id.is_some_and(|v| v != vid) || product_id.is_some_and(|p| p != pid) {
+            trace!("Skipping uevent for device {vid:04x}:{pid:04x} not matching filter");
+            return None;
+        }
+    }
+
     if is_add {
         let path = Path::new("/sys/").join(devpath.trim_start_matches('/'));
         match probe_device(SysfsPath(path.clone())) {
@@ -152,6 +178,15 @@ fn parse_packet(buf: &[u8]) -> Option<HotplugEvent> {
     }
 }
 
+/// Parse the vendor and product ID from a `PRODUCT` property like
+/// `1234/abcd/100`.
+fn parse_product(v: &str) -> Option<(u16, u16)> {
+    let mut parts = v.split('/');
+    let vid = u16::from_str_radix(parts.next()?, 16).ok()?;
+    let pid = u16::from_str_radix(parts.next()?, 16).ok()?;
+    Some((vid, pid))
+}
+
 /// Split nul-separated key=value pairs
 fn parse_properties(buf: &[u8]) -> impl Iterator<Item = (&str, &str)> + '_ {
     buf.split(|b| b == &0)
@@ -10,7 +10,10 @@ mod usbfs;
 mod enumeration;
 
 #[cfg(not(target_os = "android"))]
-pub use enumeration::{list_buses, list_devices, SysfsPath};
+pub use enumeration::{
+    check_access, list_buses, list_devices, list_devices_by_id, list_root_hubs, probe_bus_addr,
+    probe_sysfs_path, scan_devices, SysfsPath,
+};
 
 #[cfg(not(target_os = "android"))]
 mod hotplug;
@@ -25,10 +28,13 @@ pub(crate) use device::LinuxDevice as Device;
 pub(crate) use device::LinuxEndpoint as Endpoint;
 pub(crate) use device::LinuxInterface as Interface;
 
+pub(crate) const MAX_ISO_PACKETS: usize = usbfs::USBDEVFS_MAX_ISO_PACKETS;
+
 use crate::transfer::TransferError;
 use crate::ErrorKind;
 
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeviceId {
     pub(crate) bus: u8,
     pub(crate) addr: u8,
@@ -38,9 +44,11 @@ fn errno_to_transfer_error(e: Errno) -> TransferError {
     match e {
         Errno::NODEV | Errno::SHUTDOWN => TransferError::Disconnected,
         Errno::PIPE => TransferError::Stall,
-        Errno::NOENT | Errno::CONNRESET | Errno::TIMEDOUT => TransferError::Cancelled,
-        Errno::PROTO | Errno::ILSEQ | Errno::OVERFLOW | Errno::COMM | Errno::TIME => {
-            TransferError::Fault(e.raw_os_error() as u32);
+        Errno::NOENT | Errno::CONNRESET => TransferError::Cancelled,
+        Errno::TIMEDOUT => TransferError::TimedOut,
+        Errno::OVERFLOW => TransferError::Overflow,
+        Errno::PROTO | Errno::ILSEQ | Errno::COMM | Errno::TIME => {
+            TransferError::Fault(e.raw_os_error() as u32)
         }
         Errno::INVAL => TransferError::InvalidArgument,
         _ => TransferError::Unknown(e.raw_os_error() as u32),
@@ -1,4 +1,6 @@
 use std::{
+    alloc::{self, Layout},
+    ffi::c_int,
     mem::{self, ManuallyDrop},
     ptr::{addr_of_mut, null_mut},
     slice,
@@ -11,14 +13,16 @@ use crate::{
     descriptors::TransferType,
     transfer::{
         internal::Pending, Allocator, Buffer, Completion, ControlIn, ControlOut, Direction,
-        TransferError, SETUP_PACKET_SIZE,
+        IsoCompletion, IsoPacketResult, IsoSchedule, TransferError, TransferPriority,
+        SETUP_PACKET_SIZE,
     },
 };
 
 use super::{
     errno_to_transfer_error,
     usbfs::{
-        Urb, USBDEVFS_URB_TYPE_BULK, USBDEVFS_URB_TYPE_CONTROL, USBDEVFS_URB_TYPE_INTERRUPT,
+        IsoPacketDesc, Urb, USBDEVFS_URB_ISO_ASAP, USBDEVFS_URB_SHORT_NOT_OK,
+        USBDEVFS_URB_TYPE_BULK, USBDEVFS_URB_TYPE_CONTROL, USBDEVFS_URB_TYPE_INTERRUPT,
         USBDEVFS_URB_TYPE_ISO,
     },
 };
@@ -33,14 +37,37 @@ pub struct TransferData {
     urb: *mut Urb,
     capacity: u32,
     allocator: Allocator,
+
+    /// Number of `iso_packet_desc` entries allocated after the URB
+    iso_packets_capacity: u32,
+
     pub(crate) deadline: Option<Instant>,
+
+    pub(crate) priority: TransferPriority,
 }
 
 unsafe impl Send for TransferData {}
 unsafe impl Sync for TransferData {}
 
+/// Layout of a URB followed by `num_iso_packets` packet descriptors.
+fn urb_layout(num_iso_packets: usize) -> Layout {
+    Layout::new::<Urb>()
+        .extend(Layout::array::<IsoPacketDesc>(num_iso_packets).unwrap())
+        .unwrap()
+        .0
+        .pad_to_align()
+}
+
 impl TransferData {
     pub(super) fn new(endpoint: u8, ep_type: TransferType) -> TransferData {
+        Self::new_with_iso_packets(endpoint, ep_type, 0)
+    }
+
+    pub(super) fn new_with_iso_packets(
+        endpoint: u8,
+        ep_type: TransferType,
+        num_iso_packets: usize,
+    ) -> TransferData {
         let ep_type = match ep_type {
             TransferType::Control => USBDEVFS_URB_TYPE_CONTROL,
             TransferType::Interrupt => USBDEVFS_URB_TYPE_INTERRUPT,
@@ -50,8 +77,15 @@ impl TransferData {
 
         let mut empty = ManuallyDrop::new(Vec::new());
 
-        TransferData {
-            urb: Box::into_raw(Box::new(Urb {
+        // SAFETY: the layout has nonzero size because it contains the `Urb`.
+        // The `Urb` is initialized below, and the packet descriptors are
+        // zero-initialized, which is a valid value for them.
+        let urb = unsafe {
+            let urb = alloc::alloc_zeroed(urb_layout(num_iso_packets)) as *mut Urb;
+            if urb.is_null() {
+                alloc::handle_alloc_error(urb_layout(num_iso_packets));
+            }
+            urb.write(Urb {
                 ep_type,
                 endpoint,
                 status: 0,
@@ -64,10 +98,22 @@ impl TransferData {
                 error_count: 0,
                 signr: 0,
                 usercontext: null_mut(),
-            })),
+            });
+            urb
+        };
+
+        TransferData {
+            urb,
             capacity: 0,
             allocator: Allocator::Default,
+            iso_packets_capacity: num_iso_packets.try_into().unwrap(),
             deadline: None,
+            // Control transfers are usually interactive
+            priority: if ep_type == USBDEVFS_URB_TYPE_CONTROL {
+                TransferPriority::Latency
+            } else {
+                TransferPriority::Normal
+            },
         }
     }
 
@@ -81,16 +127,29 @@ impl TransferData {
     }
 
     pub(super) fn new_control_in(data: ControlIn) -> TransferData {
-        let mut t = TransferData::new(0x80, TransferType::Control);
-        let mut buffer = Buffer::new(SETUP_PACKET_SIZE.checked_add(data.length as usize).unwrap());
+        let buffer = Buffer::new(SETUP_PACKET_SIZE.checked_add(data.length as usize).unwrap());
+        Self::new_control_in_buffer(data, buffer)
+    }
+
+    /// Set up a control IN transfer using the storage of `buffer`, which is
+    /// reallocated if it is too small to hold the setup packet and data.
+    pub(super) fn new_control_in_buffer(data: ControlIn, mut buffer: Buffer) -> TransferData {
+        let len = SETUP_PACKET_SIZE.checked_add(data.length as usize).unwrap();
+        if buffer.capacity() < len {
+            buffer = Buffer::new(len);
+        }
+        buffer.clear();
+        buffer.set_requested_len(len);
         buffer.extend_from_slice(&data.setup_packet());
+
+        let mut t = TransferData::new(0x80, TransferType::Control);
         t.set_buffer(buffer);
         t
     }
 
     pub fn set_buffer(&mut self, buf: Buffer) {
         debug_assert!(self.capacity == 0);
-        let buf = ManuallyDrop::new(buf);
+        let mut buf = ManuallyDrop::new(buf);
         self.capacity = buf.capacity;
         self.urb_mut().buffer = buf.ptr;
         self.urb_mut().actual_length = 0;
@@ -98,7 +157,7 @@ impl TransferData {
             Direction::Out => buf.len as i32,
             Direction::In => buf.requested_len as i32,
         };
-        self.allocator = buf.allocator;
+        self.allocator = mem::replace(&mut buf.allocator, Allocator::Default);
     }
 
     pub fn take_completion(&mut self) -> Completion {
@@ -127,6 +186,101 @@ impl TransferData {
                 capacity,
                 allocator,
             },
+            tag: 0,
+            endpoint: 0,
+            submitted_at: None,
+            completed_at: None,
+        }
+    }
+
+    /// Number of packet descriptors that can be used for an isochronous transfer.
+    pub(super) fn iso_packets_capacity(&self) -> usize {
+        self.iso_packets_capacity as usize
+    }
+
+    /// Set up the packet descriptors for an isochronous transfer.
+    ///
+    /// The buffer must already be set and the sum of `packet_lengths` must not exceed
+    /// its `buffer_length`.
+    pub(super) fn set_iso_packets(&mut self, packet_lengths: &[usize], schedule: IsoSchedule) {
+        assert!(packet_lengths.len() <= self.iso_packets_capacity());
+        debug_assert!(
+            packet_lengths.iter().sum::<usize>() <= self.urb().buffer_length as usize,
+            "packet lengths exceed buffer length"
+        );
+
+        let (flags, start_frame) = match schedule {
+            IsoSchedule::Asap => (USBDEVFS_URB_ISO_ASAP, 0),
+            IsoSchedule::Frame(frame) => (0, frame as c_int),
+        };
+        self.urb_mut().flags = flags;
+        self.urb_mut().start_frame = start_frame;
+        self.urb_mut().error_count = 0;
+        self.urb_mut().number_of_packets_or_stream_id = packet_lengths.len() as u32;
+        for (desc, &len) in self.iso_packet_descs_mut().iter_mut().zip(packet_lengths) {
+            *desc = IsoPacketDesc {
+                length: len as u32,
+                actual_length: 0,
+                status: 0,
+            };
+        }
+    }
+
+    fn iso_packet_descs(&self) -> &[IsoPacketDesc] {
+        let len = if self.urb().ep_type == USBDEVFS_URB_TYPE_ISO {
+            self.urb().number_of_packets_or_stream_id as usize
+        } else {
+            0
+        };
+        debug_assert!(len <= self.iso_packets_capacity());
+        // SAFETY: `iso_packets_capacity` descriptors were allocated after the URB
+        unsafe { slice::from_raw_parts(self.urb.add(1).cast::<IsoPacketDesc>(), len) }
+    }
+
+    fn iso_packet_descs_mut(&mut self) -> &mut [IsoPacketDesc] {
+        let len = self.urb().number_of_packets_or_stream_id as usize;
+        debug_assert!(len <= self.iso_packets_capacity());
+        // SAFETY: `iso_packets_capacity` descriptors were allocated after the URB
+        unsafe { slice::from_raw_parts_mut(self.urb.add(1).cast::<IsoPacketDesc>(), len) }
+    }
+
+    pub fn take_iso_completion(&mut self) -> IsoCompletion {
+        let mut offset = 0;
+        let packets = self
+            .iso_packet_descs()
+            .iter()
+            .map(|desc| {
+                let packet = IsoPacketResult {
+                    offset,
+                    requested_len: desc.length as usize,
+                    actual_len: desc.actual_length as usize,
+                    status: Self::status_from_raw(desc.status as i32),
+                };
+                offset += desc.length as usize;
+                packet
+            })
+            .collect();
+
+        self.urb_mut().number_of_packets_or_stream_id = 0;
+        self.urb_mut().flags = 0;
+
+        // Packets are placed at their offset within the buffer, so include the
+        // whole requested length in the buffer even for IN transfers. The buffer
+        // is zeroed when submitting an IN transfer to make this safe.
+        let buffer_length = self.urb().buffer_length as u32;
+        let start_frame = self.urb().start_frame as u32;
+        let Completion {
+            mut buffer, status, ..
+        } = self.take_completion();
+        buffer.len = buffer_length;
+
+        IsoCompletion {
+            buffer,
+            packets,
+            endpoint: 0,
+            status,
+            start_frame,
+            completed_at: None,
         }
     }
 
@@ -145,19 +299,59 @@ impl TransferData {
         self.urb
     }
 
+    /// Set the status of a transfer completed with `error` instead of being
+    /// submitted.
+    pub(super) fn set_submit_error(&mut self, error: TransferError) {
+        let errno = match error {
+            TransferError::InvalidArgument => Errno::INVAL,
+            TransferError::Cancelled => Errno::CONNRESET,
+            e => panic!("unexpected submit error {e:?}"),
+        };
+        let urb = self.urb_mut();
+        urb.status = -errno.raw_os_error();
+        urb.actual_length = 0;
+    }
+
     #[inline]
     pub fn status(&self) -> Result<(), TransferError> {
-        if self.urb().status == 0 {
+        let urb = self.urb();
+        if urb.status == -Errno::REMOTEIO.raw_os_error()
+            && urb.flags & USBDEVFS_URB_SHORT_NOT_OK != 0
+        {
+            // A short packet with `SHORT_NOT_OK` is the normal end of a transfer
+            // split across multiple URBs.
+            return Ok(());
+        }
+        Self::status_from_raw(urb.status)
+    }
+
+    #[inline]
+    fn status_from_raw(status: i32) -> Result<(), TransferError> {
+        if status == 0 {
             return Ok(());
         }
 
         // It's sometimes positive, sometimes negative, but rustix panics if negative.
         Err(errno_to_transfer_error(Errno::from_raw_os_error(
-            self.urb().status.abs(),
+            status.abs(),
         )))
     }
 
     #[inline]
+    /// Take the completion of a control IN transfer, with the received data
+    /// moved to the start of the buffer in place of the setup packet.
+    pub fn take_control_in_completion(&mut self) -> Completion {
+        debug_assert!(self.urb().endpoint == 0x80);
+        let mut c = self.take_completion();
+        let data_len = c.actual_len;
+        unsafe {
+            std::ptr::copy(c.buffer.ptr.add(SETUP_PACKET_SIZE), c.buffer.ptr, data_len);
+        }
+        c.buffer.len = data_len as u32;
+        c.buffer.requested_len -= SETUP_PACKET_SIZE as u32;
+        c
+    }
+
     pub fn control_in_data(&self) -> &[u8] {
         debug_assert!(self.urb().endpoint == 0x80);
         let urb = self.urb();
@@ -182,7 +376,19 @@ impl Drop for TransferData {
     fn drop(&mut self) {
         unsafe {
             drop(self.take_completion());
-            drop(Box::from_raw(self.urb));
+            alloc::dealloc(
+                self.urb.cast(),
+                urb_layout(self.iso_packets_capacity as usize),
+            );
         }
     }
 }
+
+#[test]
+fn submit_error_status() {
+    for error in [TransferError::Cancelled, TransferError::InvalidArgument] {
+        let mut t = TransferData::new(0x81, TransferType::Bulk);
+        t.set_submit_error(error);
+        assert_eq!(t.status(), Err(error));
+    }
+}
@@ -7,10 +7,11 @@
 use std::ffi::{c_int, c_uchar, c_uint, c_void};
 
 use linux_raw_sys::ioctl::{
-    USBDEVFS_CLAIMINTERFACE, USBDEVFS_CLEAR_HALT, USBDEVFS_CONNECT, USBDEVFS_CONTROL,
-    USBDEVFS_DISCARDURB, USBDEVFS_DISCONNECT, USBDEVFS_DISCONNECT_CLAIM, USBDEVFS_GET_SPEED,
-    USBDEVFS_IOCTL, USBDEVFS_REAPURBNDELAY, USBDEVFS_RELEASEINTERFACE, USBDEVFS_RESET,
-    USBDEVFS_SETCONFIGURATION, USBDEVFS_SETINTERFACE, USBDEVFS_SUBMITURB,
+    USBDEVFS_ALLOC_STREAMS, USBDEVFS_ALLOW_SUSPEND, USBDEVFS_CLAIMINTERFACE, USBDEVFS_CLEAR_HALT,
+    USBDEVFS_CONNECT, USBDEVFS_CONTROL, USBDEVFS_DISCARDURB, USBDEVFS_DISCONNECT,
+    USBDEVFS_DISCONNECT_CLAIM, USBDEVFS_FORBID_SUSPEND, USBDEVFS_FREE_STREAMS, USBDEVFS_GETDRIVER,
+    USBDEVFS_GET_SPEED, USBDEVFS_IOCTL, USBDEVFS_REAPURBNDELAY, USBDEVFS_RELEASEINTERFACE,
+    USBDEVFS_RESET, USBDEVFS_SETCONFIGURATION, USBDEVFS_SETINTERFACE, USBDEVFS_SUBMITURB,
 };
 use rustix::{
     fd::AsFd,
@@ -65,6 +66,31 @@ pub fn detach_and_claim_interface<Fd: AsFd>(fd: Fd, interface: u8) -> io::Result
     }
 }
 
+#[repr(C)]
+struct GetDriver {
+    interface: c_uint,
+    driver: [c_uchar; 255 + 1],
+}
+
+/// Get the name of the driver bound to an interface.
+pub fn get_driver<Fd: AsFd>(fd: Fd, interface: u8) -> io::Result<Vec<u8>> {
+    let mut gd = GetDriver {
+        interface: interface.into(),
+        driver: [0; 256],
+    };
+    unsafe {
+        // Declared as `_IOW`, but the kernel writes the driver name into the struct.
+        let ctl = ioctl::Updater::<{ USBDEVFS_GETDRIVER as _ }, GetDriver>::new(&mut gd);
+        ioctl::ioctl(fd, ctl)?;
+    }
+    let len = gd
+        .driver
+        .iter()
+        .position(|&c| c == 0)
+        .unwrap_or(gd.driver.len());
+    Ok(gd.driver[..len].to_vec())
+}
+
 #[repr(C)]
 struct UsbFsIoctl {
     interface: c_uint,
@@ -171,6 +197,20 @@ pub unsafe fn discard_urb<Fd: AsFd>(fd: Fd, urb: *mut Urb) -> io::Result<()> {
     }
 }
 
+pub fn forbid_suspend<Fd: AsFd>(fd: Fd) -> io::Result<()> {
+    unsafe {
+        let ctl = ioctl::NoArg::<{ USBDEVFS_FORBID_SUSPEND as _ }>::new();
+        ioctl::ioctl(fd, ctl)
+    }
+}
+
+pub fn allow_suspend<Fd: AsFd>(fd: Fd) -> io::Result<()> {
+    unsafe {
+        let ctl = ioctl::NoArg::<{ USBDEVFS_ALLOW_SUSPEND as _ }>::new();
+        ioctl::ioctl(fd, ctl)
+    }
+}
+
 pub fn reset<Fd: AsFd>(fd: Fd) -> io::Result<()> {
     unsafe {
         let ctl = ioctl::NoArg::<{ USBDEVFS_RESET as _ }>::new();
@@ -178,11 +218,14 @@ pub fn reset<Fd: AsFd>(fd: Fd) -> io::Result<()> {
     }
 }
 
-const USBDEVFS_URB_SHORT_NOT_OK: c_uint = 0x01;
-const USBDEVFS_URB_ISO_ASAP: c_uint = 0x02;
-const USBDEVFS_URB_BULK_CONTINUATION: c_uint = 0x04;
-const USBDEVFS_URB_ZERO_PACKET: c_uint = 0x40;
-const USBDEVFS_URB_NO_INTERRUPT: c_uint = 0x80;
+pub const USBDEVFS_URB_SHORT_NOT_OK: c_uint = 0x01;
+pub const USBDEVFS_URB_ISO_ASAP: c_uint = 0x02;
+pub const USBDEVFS_URB_BULK_CONTINUATION: c_uint = 0x04;
+pub const USBDEVFS_URB_ZERO_PACKET: c_uint = 0x40;
+pub const USBDEVFS_URB_NO_INTERRUPT: c_uint = 0x80;
+
+/// Maximum number of packets in an isochronous URB accepted by usbfs.
+pub const USBDEVFS_MAX_ISO_PACKETS: usize = 128;
 
 pub const USBDEVFS_URB_TYPE_ISO: c_uchar = 0;
 pub const USBDEVFS_URB_TYPE_INTERRUPT: c_uchar = 1;
@@ -207,6 +250,14 @@ pub struct Urb {
     // + variable size array of iso_packet_desc
 }
 
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct IsoPacketDesc {
+    pub length: c_uint,
+    pub actual_length: c_uint,
+    pub status: c_uint,
+}
+
 pub struct Transfer<const OPCODE: Opcode, Input> {
     input: Input,
 }
@@ -268,3 +319,33 @@ pub fn get_speed<Fd: AsFd>(fd: Fd) -> io::Result<usize> {
         ioctl::ioctl(fd, ctl)
     }
 }
+
+/// `struct usbdevfs_streams` with a single endpoint in the flexible array.
+#[repr(C)]
+struct Streams {
+    num_streams: c_uint,
+    num_eps: c_uint,
+    eps: [c_uchar; 1],
+}
+
+pub fn alloc_streams<Fd: AsFd>(fd: Fd, endpoint: u8, num_streams: u32) -> io::Result<usize> {
+    unsafe {
+        let ctl = Transfer::<{ USBDEVFS_ALLOC_STREAMS as _ }, Streams>::new(Streams {
+            num_streams,
+            num_eps: 1,
+            eps: [endpoint],
+        });
+        ioctl::ioctl(fd, ctl)
+    }
+}
+
+pub fn free_streams<Fd: AsFd>(fd: Fd, endpoint: u8) -> io::Result<()> {
+    unsafe {
+        let ctl = Transfer::<{ USBDEVFS_FREE_STREAMS as _ }, Streams>::new(Streams {
+            num_streams: 0,
+            num_eps: 1,
+            eps: [endpoint],
+        });
+        ioctl::ioctl(fd, ctl).map(|_| ())
+    }
+}
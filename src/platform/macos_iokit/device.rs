@@ -1,7 +1,7 @@
 use std::{
     collections::VecDeque,
     ffi::c_void,
-    mem::ManuallyDrop,
+    mem::{self, ManuallyDrop},
     sync::{
         atomic::{AtomicU8, AtomicUsize, Ordering},
         Arc, Mutex,
@@ -15,15 +15,17 @@ use log::{debug, error};
 
 use crate::{
     bitset::EndpointBitSet,
-    descriptors::{ConfigurationDescriptor, DeviceDescriptor, EndpointDescriptor},
-    maybe_future::blocking::Blocking,
+    descriptors::{ConfigurationDescriptor, DeviceDescriptor, EndpointDescriptor, TransferType},
+    maybe_future::{blocking::Blocking, Ready},
     transfer::{
         internal::{
-            notify_completion, take_completed_from_queue, Idle, Notify, Pending, TransferFuture,
+            notify_completion, take_completed_from_queue, CloseNotify, Idle, Notify, Pending,
+            TransferFuture,
         },
-        Buffer, Completion, ControlIn, ControlOut, Direction, TransferError,
+        Allocator, Buffer, Completion, ControlIn, ControlOut, Direction, TransferError,
+        TransferPriority,
     },
-    DeviceInfo, Error, ErrorKind, MaybeFuture, Speed,
+    ClaimStatus, DeviceInfo, Error, ErrorKind, MaybeFuture, ResetOutcome, Speed,
 };
 
 use super::{
@@ -44,6 +46,9 @@ pub(crate) struct MacDevice {
     active_config: AtomicU8,
     is_open_exclusive: Mutex<bool>,
     claimed_interfaces: AtomicUsize,
+
+    /// Notifies `wait_closed` once the device is released.
+    pub(crate) closed: CloseNotify,
 }
 
 // `get_configuration` does IO, so avoid it in the common case that:
@@ -61,6 +66,7 @@ fn guess_active_config(configs: &[Vec<u8>], dev: &IoKitDevice) -> Option<u8> {
 impl MacDevice {
     pub(crate) fn from_device_info(
         d: &DeviceInfo,
+        read_only: bool,
     ) -> impl MaybeFuture<Output = Result<Arc<MacDevice>, Error>> {
         let registry_id = d.registry_id;
         let speed = d.speed;
@@ -74,12 +80,15 @@ impl MacDevice {
             })?;
             let _event_registration = add_event_source(event_source);
 
-            let opened = device
-                .open()
-                .inspect_err(|err| {
-                    log::debug!("Could not open device for exclusive access: 0x{err:08x}");
-                })
-                .is_ok();
+            // In read-only mode, exclusive access is only requested later if
+            // an operation requires it.
+            let opened = !read_only
+                && device
+                    .open()
+                    .inspect_err(|err| {
+                        log::debug!("Could not open device for exclusive access: 0x{err:08x}");
+                    })
+                    .is_ok();
 
             let device_descriptor = device_descriptor_from_fields(&service).ok_or_else(|| {
                 Error::new(
@@ -129,6 +138,7 @@ impl MacDevice {
                 active_config: AtomicU8::new(active_config),
                 is_open_exclusive: Mutex::new(opened),
                 claimed_interfaces: AtomicUsize::new(0),
+                closed: CloseNotify::default(),
             }))
         })
     }
@@ -137,6 +147,10 @@ impl MacDevice {
         self.device_descriptor.clone()
     }
 
+    pub(crate) fn raw_device(&self) -> *mut c_void {
+        self.device.raw as *mut c_void
+    }
+
     pub(crate) fn speed(&self) -> Option<Speed> {
         self.speed
     }
@@ -214,6 +228,62 @@ impl MacDevice {
         })
     }
 
+    pub(crate) fn cycle_port(
+        self: Arc<Self>,
+        _off_time: Duration,
+    ) -> impl MaybeFuture<Output = Result<(), Error>> {
+        Ready(Err(Error::new(
+            ErrorKind::Unsupported,
+            "port cycle not supported on this platform",
+        )))
+    }
+
+    pub(crate) fn state(&self) -> Result<crate::hotplug::DeviceState, Error> {
+        let configuration = self.device.get_configuration().map_err(|e| match e {
+            io_kit_sys::ret::kIOReturnNoDevice => {
+                Error::new_os(ErrorKind::Disconnected, "device disconnected", e)
+            }
+            _ => Error::new_os(ErrorKind::Other, "failed to query device state", e),
+        })?;
+        self.active_config.store(configuration, Ordering::SeqCst);
+
+        Ok(crate::hotplug::DeviceState {
+            suspended: None,
+            configuration: Some(configuration),
+        })
+    }
+
+    pub(crate) fn interface_claim_status(
+        &self,
+        _interface_number: u8,
+    ) -> Result<ClaimStatus, Error> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "querying interface claim status is not supported on this platform",
+        ))
+    }
+
+    pub(crate) fn use_dedicated_event_thread(&self) -> Result<(), Error> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "dedicated event threads are not supported on this platform",
+        ))
+    }
+
+    pub(crate) fn kernel_driver(&self, _interface_number: u8) -> Result<Option<String>, Error> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "querying interface drivers is not supported on this platform",
+        ))
+    }
+
+    pub(crate) fn reset_and_revalidate(
+        self: Arc<Self>,
+    ) -> impl MaybeFuture<Output = Result<ResetOutcome, Error>> {
+        // IOKit always re-enumerates the device on reset
+        self.reset().map(|r| r.map(|()| ResetOutcome::Reenumerated))
+    }
+
     pub(crate) fn claim_interface(
         self: Arc<Self>,
         interface_number: u8,
@@ -257,6 +327,7 @@ impl MacDevice {
                 interface,
                 state: Mutex::new(InterfaceState::default()),
                 _event_registration,
+                closed: CloseNotify::default(),
             }))
         })
     }
@@ -299,6 +370,40 @@ impl MacDevice {
         })
     }
 
+    pub fn control_in_buffer(
+        self: Arc<Self>,
+        data: ControlIn,
+        buffer: Buffer,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Completion> {
+        let timeout = timeout.as_millis().try_into().expect("timeout too long");
+        let mut v = buffer.into_vec();
+        v.clear();
+        v.reserve(data.length as usize);
+        let mut v = ManuallyDrop::new(v);
+        let t = unsafe {
+            TransferData::from_raw(v.as_mut_ptr(), data.length as u32, v.capacity() as u32)
+        };
+
+        let req = IOUSBDevRequestTO {
+            bmRequestType: data.request_type(),
+            bRequest: data.request,
+            wValue: data.value,
+            wIndex: data.index,
+            wLength: data.length,
+            pData: t.buf as *mut c_void,
+            wLenDone: 0,
+            completionTimeout: timeout,
+            noDataTimeout: timeout,
+        };
+
+        TransferFuture::new(t, |t| self.submit_control(Direction::In, t, req)).map(move |mut t| {
+            drop(self); // ensure device stays alive
+                        // SAFETY: the transfer has completed, and is an IN transfer
+            unsafe { t.take_completion(Direction::In) }
+        })
+    }
+
     pub fn control_out(
         self: Arc<Self>,
         data: ControlOut,
@@ -379,6 +484,9 @@ pub(crate) struct MacInterface {
     pub(crate) interface: IoKitInterface,
     pub(crate) device: Arc<MacDevice>,
     state: Mutex<InterfaceState>,
+
+    /// Notifies `wait_closed` once the interface is released.
+    pub(crate) closed: CloseNotify,
 }
 
 #[derive(Default)]
@@ -426,6 +534,24 @@ impl MacInterface {
         self.state.lock().unwrap().alt_setting
     }
 
+    pub fn update_alt_setting(&self, alt_setting: u8) {
+        self.state.lock().unwrap().alt_setting = alt_setting;
+    }
+
+    pub fn set_autosuspend(&self, _enable: bool) -> Result<(), Error> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "autosuspend control not supported on macOS",
+        ))
+    }
+
+    pub fn set_autosuspend_delay(&self, _delay: Duration) -> Result<(), Error> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "autosuspend control not supported on macOS",
+        ))
+    }
+
     pub fn control_in(
         self: &Arc<Self>,
         data: ControlIn,
@@ -434,6 +560,15 @@ impl MacInterface {
         self.device.clone().control_in(data, timeout)
     }
 
+    pub fn control_in_buffer(
+        self: &Arc<Self>,
+        data: ControlIn,
+        buffer: Buffer,
+        timeout: Duration,
+    ) -> impl MaybeFuture<Output = Completion> {
+        self.device.clone().control_in_buffer(data, buffer, timeout)
+    }
+
     pub fn control_out(
         self: &Arc<Self>,
         data: ControlOut,
@@ -449,6 +584,13 @@ impl MacInterface {
         let address = descriptor.address();
         let max_packet_size = descriptor.max_packet_size();
 
+        if descriptor.transfer_type() == TransferType::Isochronous {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "isochronous endpoints are not supported on this platform",
+            ));
+        }
+
         let mut state = self.state.lock().unwrap();
 
         let Some(pipe_ref) = self.interface.find_pipe_ref(address) else {
@@ -468,12 +610,15 @@ impl MacInterface {
             inner: Arc::new(EndpointInner {
                 pipe_ref,
                 address,
+                ep_type: descriptor.transfer_type(),
                 interface: self.clone(),
                 notify: Notify::new(),
             }),
             max_packet_size,
+            max_transfer_size: usize::MAX,
             pending: VecDeque::new(),
             idle_transfer: None,
+            timestamps: false,
         })
     }
 }
@@ -492,17 +637,22 @@ impl Drop for MacInterface {
 pub(crate) struct MacEndpoint {
     inner: Arc<EndpointInner>,
     pub(crate) max_packet_size: usize,
+    pub(crate) max_transfer_size: usize,
 
     /// A queue of pending transfers, expected to complete in order
     pending: VecDeque<Pending<TransferData>>,
 
     idle_transfer: Option<Idle<TransferData>>,
+
+    /// Record the completion time of transfers
+    timestamps: bool,
 }
 
 struct EndpointInner {
     interface: Arc<MacInterface>,
     pipe_ref: u8,
     address: u8,
+    ep_type: TransferType,
     notify: Notify,
 }
 
@@ -515,6 +665,49 @@ impl MacEndpoint {
         self.pending.len()
     }
 
+    pub(crate) fn ready(&self) -> usize {
+        self.pending.iter().take_while(|t| t.is_complete()).count()
+    }
+
+    pub(crate) fn set_notify_callback(&self, callback: Option<Arc<dyn Fn() + Send + Sync>>) {
+        self.inner.notify.set_callback(callback);
+    }
+
+    pub(crate) fn interface(&self) -> &Arc<MacInterface> {
+        &self.inner.interface
+    }
+
+    pub(crate) fn native_handle(&self, index: usize) -> Option<*mut c_void> {
+        Some(self.pending.get(index)?.as_ptr().cast())
+    }
+
+    pub(crate) fn set_no_interrupt(&mut self, _enable: bool) {
+        // Not implemented: there is no equivalent hint.
+    }
+
+    pub(crate) fn set_priority(&mut self, _priority: TransferPriority) {
+        // Not implemented: only the Linux event loop reorders completions.
+    }
+
+    pub(crate) fn set_timestamps(&mut self, enable: bool) {
+        self.timestamps = enable;
+    }
+
+    pub(crate) fn set_auto_zlp(&mut self, _enable: bool) -> Result<(), Error> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "automatic zero-length packets are not supported on macOS",
+        ))
+    }
+
+    pub(crate) fn cancel(&mut self, _index: usize) -> bool {
+        debug!(
+            "Cancelling a single transfer is not supported on endpoint {:02x}",
+            self.inner.address
+        );
+        false
+    }
+
     pub(crate) fn cancel_all(&mut self) {
         let r = unsafe {
             call_iokit_function!(
@@ -533,10 +726,12 @@ impl MacEndpoint {
             .idle_transfer
             .take()
             .unwrap_or_else(|| Idle::new(self.inner.clone(), super::TransferData::new()));
+        transfer.set_timestamps(self.timestamps);
 
-        let buffer = ManuallyDrop::new(buffer);
+        let mut buffer = ManuallyDrop::new(buffer);
         transfer.buf = buffer.ptr;
         transfer.capacity = buffer.capacity;
+        transfer.allocator = mem::replace(&mut buffer.allocator, Allocator::Default);
         transfer.actual_len = 0;
         let req_len = match Direction::from_address(self.inner.address) {
             Direction::Out => buffer.len,
@@ -547,6 +742,35 @@ impl MacEndpoint {
     }
 
     pub(crate) fn submit(&mut self, buffer: Buffer) {
+        self.submit_inner(buffer, None);
+    }
+
+    pub(crate) fn submit_timeout(&mut self, buffer: Buffer, timeout: Duration) {
+        if self.inner.ep_type == TransferType::Bulk {
+            // A timeout of 0 means no timeout to IOKit, so round up.
+            let timeout_ms = timeout.as_millis().clamp(1, u32::MAX as u128) as u32;
+            self.submit_inner(buffer, Some(timeout_ms));
+        } else {
+            debug!(
+                "Ignoring timeout on non-bulk endpoint {:02X}",
+                self.inner.address
+            );
+            self.submit_inner(buffer, None);
+        }
+    }
+
+    /// Submit independent transfers, returning how many IOKit accepted.
+    pub(crate) fn submit_batch(&mut self, bufs: Vec<Buffer>, _batch_interrupts: bool) -> usize {
+        self.pending.reserve(bufs.len());
+        let mut accepted = 0;
+        for buffer in bufs {
+            accepted += self.submit_inner(buffer, None) as usize;
+        }
+        accepted
+    }
+
+    /// Submit a transfer, returning whether IOKit accepted it.
+    fn submit_inner(&mut self, buffer: Buffer, timeout_ms: Option<u32>) -> bool {
         let transfer = self.make_transfer(buffer);
         let endpoint = self.inner.address;
         let dir = Direction::from_address(endpoint);
@@ -557,8 +781,8 @@ impl MacEndpoint {
         let ptr = transfer.as_ptr();
 
         let res = unsafe {
-            match dir {
-                Direction::Out => call_iokit_function!(
+            match (dir, timeout_ms) {
+                (Direction::Out, None) => call_iokit_function!(
                     self.inner.interface.interface.raw,
                     WritePipeAsync(
                         self.inner.pipe_ref,
@@ -568,7 +792,7 @@ impl MacEndpoint {
                         ptr as *mut c_void
                     )
                 ),
-                Direction::In => call_iokit_function!(
+                (Direction::In, None) => call_iokit_function!(
                     self.inner.interface.interface.raw,
                     ReadPipeAsync(
                         self.inner.pipe_ref,
@@ -578,10 +802,35 @@ impl MacEndpoint {
                         ptr as *mut c_void
                     )
                 ),
+                (Direction::Out, Some(timeout)) => call_iokit_function!(
+                    self.inner.interface.interface.raw,
+                    WritePipeAsyncTO(
+                        self.inner.pipe_ref,
+                        buf_ptr as *mut c_void,
+                        req_len,
+                        timeout,
+                        timeout,
+                        transfer_callback,
+                        ptr as *mut c_void
+                    )
+                ),
+                (Direction::In, Some(timeout)) => call_iokit_function!(
+                    self.inner.interface.interface.raw,
+                    ReadPipeAsyncTO(
+                        self.inner.pipe_ref,
+                        buf_ptr as *mut c_void,
+                        req_len,
+                        timeout,
+                        timeout,
+                        transfer_callback,
+                        ptr as *mut c_void
+                    )
+                ),
             }
         };
 
-        if res == kIOReturnSuccess {
+        let accepted = res == kIOReturnSuccess;
+        if accepted {
             debug!(
                 "Submitted {dir:?} transfer {ptr:?} of len {req_len} on endpoint {endpoint:02X}"
             );
@@ -595,12 +844,38 @@ impl MacEndpoint {
         }
 
         self.pending.push_back(transfer);
+        accepted
+    }
+
+    pub(crate) fn alloc_streams(
+        &self,
+        _num_streams: u32,
+    ) -> impl MaybeFuture<Output = Result<u32, Error>> {
+        Ready(Err(Error::new(
+            ErrorKind::Unsupported,
+            "bulk streams not supported on macOS",
+        )))
+    }
+
+    pub(crate) fn free_streams(&self) -> impl MaybeFuture<Output = Result<(), Error>> {
+        Ready(Err(Error::new(
+            ErrorKind::Unsupported,
+            "bulk streams not supported on macOS",
+        )))
+    }
+
+    pub(crate) fn submit_stream(&mut self, buffer: Buffer, _stream_id: u32) {
+        // No stream can have been allocated, so the ID is invalid.
+        self.submit_err(buffer, TransferError::InvalidArgument);
     }
 
     pub(crate) fn submit_err(&mut self, buffer: Buffer, err: TransferError) {
-        assert_eq!(err, TransferError::InvalidArgument);
         let mut transfer = self.make_transfer(buffer);
-        transfer.status = io_kit_sys::ret::kIOReturnBadArgument;
+        transfer.status = match err {
+            TransferError::InvalidArgument => io_kit_sys::ret::kIOReturnBadArgument,
+            TransferError::Cancelled => io_kit_sys::ret::kIOReturnAborted,
+            e => panic!("unexpected submit error {e:?}"),
+        };
         self.pending.push_back(transfer.simulate_complete());
     }
 
@@ -608,7 +883,8 @@ impl MacEndpoint {
         self.inner.notify.subscribe(cx);
         if let Some(mut transfer) = take_completed_from_queue(&mut self.pending) {
             let dir = Direction::from_address(self.inner.address);
-            let completion = unsafe { transfer.take_completion(dir) };
+            let mut completion = unsafe { transfer.take_completion(dir) };
+            completion.completed_at = transfer.completed_at();
             self.idle_transfer = Some(transfer);
             Poll::Ready(completion)
         } else {
@@ -620,7 +896,8 @@ impl MacEndpoint {
         self.inner.notify.wait_timeout(timeout, || {
             take_completed_from_queue(&mut self.pending).map(|mut transfer| {
                 let dir = Direction::from_address(self.inner.address);
-                let completion = unsafe { transfer.take_completion(dir) };
+                let mut completion = unsafe { transfer.take_completion(dir) };
+                completion.completed_at = transfer.completed_at();
                 self.idle_transfer = Some(transfer);
                 completion
             })
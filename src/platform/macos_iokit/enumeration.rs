@@ -1,22 +1,29 @@
+use std::collections::HashSet;
+
 use core_foundation::{
-    base::{CFType, TCFType},
+    base::{kCFAllocatorDefault, CFAllocatorRef, CFType, CFTypeRef, TCFType},
+    boolean::CFBoolean,
     data::CFData,
+    error::CFErrorRef,
     number::CFNumber,
-    string::CFString,
+    string::{CFString, CFStringRef},
     ConcreteCFType,
 };
 use io_kit_sys::{
     kIOMasterPortDefault, kIORegistryIterateParents, kIORegistryIterateRecursively,
     keys::kIOServicePlane, ret::kIOReturnSuccess, usb::lib::kIOUSBDeviceClassName,
-    IORegistryEntryGetChildIterator, IORegistryEntryGetRegistryEntryID,
-    IORegistryEntrySearchCFProperty, IOServiceGetMatchingServices, IOServiceMatching,
+    IORegistryEntryCreateCFProperty, IORegistryEntryGetChildIterator,
+    IORegistryEntryGetRegistryEntryID, IORegistryEntrySearchCFProperty,
+    IOServiceGetMatchingServices, IOServiceMatching,
 };
 use log::debug;
 
 use crate::{
     descriptors::DeviceDescriptor,
+    enumeration::StoredString,
     maybe_future::{MaybeFuture, Ready},
-    BusInfo, DeviceInfo, Error, ErrorKind, InterfaceInfo, Speed, UsbControllerType,
+    BusInfo, DeviceInfo, Error, ErrorKind, InterfaceInfo, IoRegistryProperty, Speed,
+    UsbControllerType,
 };
 
 use super::iokit::{IoService, IoServiceIterator};
@@ -88,7 +95,26 @@ fn usb_controller_service_iter(
 
 pub fn list_devices() -> impl MaybeFuture<Output = Result<impl Iterator<Item = DeviceInfo>, Error>>
 {
-    Ready(usb_service_iter().map(|i| i.filter_map(probe_device)))
+    list_devices_by_id(None, None)
+}
+
+/// List devices, skipping those that don't match `vendor_id` and
+/// `product_id` before probing them.
+pub fn list_devices_by_id(
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+) -> impl MaybeFuture<Output = Result<impl Iterator<Item = DeviceInfo>, Error>> {
+    Ready(usb_service_iter().map(move |i| {
+        i.filter(move |dev| {
+            let id_matches = |property, id: Option<u16>| {
+                id.map_or(true, |id| {
+                    get_integer_property(dev, property) == Some(i64::from(id))
+                })
+            };
+            id_matches("idVendor", vendor_id) && id_matches("idProduct", product_id)
+        })
+        .filter_map(probe_device)
+    }))
 }
 
 pub fn list_buses() -> impl MaybeFuture<Output = Result<impl Iterator<Item = BusInfo>, Error>> {
@@ -108,12 +134,41 @@ pub fn list_buses() -> impl MaybeFuture<Output = Result<impl Iterator<Item = Bus
     .flatten()))
 }
 
+/// List connected devices, probing only those not included in `known`.
+///
+/// Returns the ID of every device, with its `DeviceInfo` if it was probed.
+pub fn scan_devices(
+    known: HashSet<crate::DeviceId>,
+) -> impl MaybeFuture<Output = Result<Vec<(crate::DeviceId, Option<DeviceInfo>)>, Error>> {
+    Ready(usb_service_iter().map(|iter| {
+        iter.flat_map(|dev| {
+            let id = crate::DeviceId(get_registry_id(&dev)?);
+            if known.contains(&id) {
+                return Some((id, None));
+            }
+            probe_device(dev).map(|d| (id, Some(d)))
+        })
+        .collect()
+    }))
+}
+
 pub(crate) fn service_by_registry_id(registry_id: u64) -> Result<IoService, Error> {
     usb_service_iter()?
         .find(|dev| get_registry_id(dev) == Some(registry_id))
         .ok_or(Error::new(ErrorKind::NotFound, "not found by registry id"))
 }
 
+/// Probe the device with the specified location ID, without probing all
+/// devices.
+pub fn probe_location_id(location_id: u32) -> Result<DeviceInfo, Error> {
+    usb_service_iter()?
+        .find(|dev| {
+            get_integer_property(dev, "locationID").map(|id| id as u32) == Some(location_id)
+        })
+        .and_then(probe_device)
+        .ok_or(Error::new(ErrorKind::Disconnected, "device not found"))
+}
+
 pub(crate) fn probe_device(device: IoService) -> Option<DeviceInfo> {
     let registry_id = get_registry_id(&device)?;
     log::debug!("Probing device {registry_id:08x}");
@@ -135,12 +190,21 @@ pub(crate) fn probe_device(device: IoService) -> Option<DeviceInfo> {
         subclass: get_integer_property(&device, "bDeviceSubClass")? as u8,
         protocol: get_integer_property(&device, "bDeviceProtocol")? as u8,
         speed: get_integer_property(&device, "Device Speed").and_then(map_speed),
-        manufacturer_string: get_string_property(&device, "kUSBVendorString")
-            .or_else(|| get_string_property(&device, "USB Vendor Name")),
-        product_string: get_string_property(&device, "kUSBProductString")
-            .or_else(|| get_string_property(&device, "USB Product Name")),
-        serial_number: get_string_property(&device, "kUSBSerialNumberString")
-            .or_else(|| get_string_property(&device, "USB Serial Number")),
+        manufacturer_string: StoredString::new(
+            get_string_property(&device, "kUSBVendorString")
+                .or_else(|| get_string_property(&device, "USB Vendor Name")),
+            get_integer_property(&device, "iManufacturer").map(|i| i as u8),
+        ),
+        product_string: StoredString::new(
+            get_string_property(&device, "kUSBProductString")
+                .or_else(|| get_string_property(&device, "USB Product Name")),
+            get_integer_property(&device, "iProduct").map(|i| i as u8),
+        ),
+        serial_number: StoredString::new(
+            get_string_property(&device, "kUSBSerialNumberString")
+                .or_else(|| get_string_property(&device, "USB Serial Number")),
+            get_integer_property(&device, "iSerialNumber").map(|i| i as u8),
+        ),
         interfaces: get_children(&device).map_or(Vec::new(), |iter| {
             iter.flat_map(|child| {
                 Some(InterfaceInfo {
@@ -150,6 +214,7 @@ pub(crate) fn probe_device(device: IoService) -> Option<DeviceInfo> {
                     protocol: get_integer_property(&child, "bInterfaceProtocol")? as u8,
                     interface_string: get_string_property(&child, "kUSBString")
                         .or_else(|| get_string_property(&child, "USB Interface Name")),
+                    driver: None,
                 })
             })
             .collect()
@@ -220,6 +285,94 @@ fn get_property<T: ConcreteCFType>(device: &IoService, property: &'static str) -
     }
 }
 
+#[link(name = "Security", kind = "framework")]
+extern "C" {
+    fn SecTaskCreateFromSelf(allocator: CFAllocatorRef) -> CFTypeRef;
+    fn SecTaskCopyValueForEntitlement(
+        task: CFTypeRef,
+        entitlement: CFStringRef,
+        error: *mut CFErrorRef,
+    ) -> CFTypeRef;
+}
+
+/// Check whether the current process has a boolean entitlement set to true.
+fn has_entitlement(entitlement: &'static str) -> Option<bool> {
+    unsafe {
+        let task = SecTaskCreateFromSelf(kCFAllocatorDefault);
+        if task.is_null() {
+            debug!("SecTaskCreateFromSelf failed");
+            return None;
+        }
+        let task = CFType::wrap_under_create_rule(task);
+
+        let key = CFString::from_static_string(entitlement);
+        let value = SecTaskCopyValueForEntitlement(
+            task.as_CFTypeRef(),
+            key.as_concrete_TypeRef(),
+            std::ptr::null_mut(),
+        );
+        if value.is_null() {
+            return Some(false);
+        }
+        let value = CFType::wrap_under_create_rule(value);
+        Some(value.downcast::<CFBoolean>().is_some_and(bool::from))
+    }
+}
+
+/// Check whether the device is connected and whether the process is allowed
+/// to access USB devices, without opening it.
+pub fn check_access(registry_id: u64) -> Result<(), Error> {
+    service_by_registry_id(registry_id)
+        .map_err(|_| Error::new(ErrorKind::Disconnected, "device not found"))?;
+
+    if has_entitlement("com.apple.security.app-sandbox") == Some(true)
+        && has_entitlement("com.apple.security.device.usb") != Some(true)
+    {
+        return Err(Error::new(
+            ErrorKind::PermissionDenied,
+            "sandboxed app does not have the com.apple.security.device.usb entitlement",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Read a property of the device's own registry entry, for the public API.
+pub(crate) fn get_registry_property(
+    registry_id: u64,
+    property: &str,
+) -> Result<Option<IoRegistryProperty>, Error> {
+    let device = service_by_registry_id(registry_id)
+        .map_err(|_| Error::new(ErrorKind::Disconnected, "device disconnected"))?;
+
+    let value = unsafe {
+        let cf_property = CFString::new(property);
+        let raw = IORegistryEntryCreateCFProperty(
+            device.get(),
+            cf_property.as_concrete_TypeRef(),
+            kCFAllocatorDefault,
+            0,
+        );
+        if raw.is_null() {
+            return Ok(None);
+        }
+        CFType::wrap_under_create_rule(raw)
+    };
+
+    Ok(if let Some(s) = value.downcast::<CFString>() {
+        Some(IoRegistryProperty::String(s.to_string()))
+    } else if let Some(b) = value.downcast::<CFBoolean>() {
+        Some(IoRegistryProperty::Bool(b.into()))
+    } else if let Some(n) = value.downcast::<CFNumber>() {
+        n.to_i64().map(IoRegistryProperty::Number)
+    } else if let Some(d) = value.downcast::<CFData>() {
+        Some(IoRegistryProperty::Data(d.bytes().to_vec()))
+    } else {
+        debug!("Unsupported type for property `{property}`");
+        None
+    })
+}
+
 fn get_string_property(device: &IoService, property: &'static str) -> Option<String> {
     get_property::<CFString>(device, property).map(|s| s.to_string())
 }
@@ -268,7 +421,8 @@ fn map_speed(speed: i64) -> Option<Speed> {
         1 => Some(Speed::Full),
         2 => Some(Speed::High),
         3 => Some(Speed::Super),
-        4 | 5 => Some(Speed::SuperPlus),
+        4 => Some(Speed::SuperPlus),
+        5 => Some(Speed::SuperPlusX2),
         _ => None,
     }
 }
@@ -5,7 +5,11 @@ use std::{
     task::{Context, Poll, Waker},
 };
 
-use core_foundation::{base::TCFType, dictionary::CFDictionary, runloop::CFRunLoopSource};
+use core_foundation::{
+    base::TCFType, dictionary::CFDictionary, number::CFNumber, runloop::CFRunLoopSource,
+    string::CFString,
+};
+use core_foundation_sys::dictionary::CFDictionarySetValue;
 use io_kit_sys::{
     kIOMasterPortDefault,
     keys::{kIOFirstMatchNotification, kIOTerminatedNotification},
@@ -18,7 +22,7 @@ use io_kit_sys::{
 use log::debug;
 use slab::Slab;
 
-use crate::{hotplug::HotplugEvent, DeviceId, Error, ErrorKind};
+use crate::{hotplug::HotplugEvent, DeviceFilter, DeviceId, Error, ErrorKind};
 
 use super::{
     enumeration::{get_registry_id, probe_device},
@@ -73,7 +77,7 @@ impl Drop for NotificationPort {
 unsafe impl Send for NotificationPort {}
 
 impl MacHotplugWatch {
-    pub(crate) fn new() -> Result<Self, Error> {
+    pub(crate) fn new(filter: &DeviceFilter) -> Result<Self, Error> {
         let waker_id = SlabWaker::new();
 
         let dictionary = unsafe {
@@ -81,6 +85,20 @@ impl MacHotplugWatch {
             if d.is_null() {
                 return Err(Error::new(ErrorKind::Other, "IOServiceMatching failed"));
             }
+
+            // IOUSBHostDevice matches these properties of the device, so
+            // notifications for other devices are not delivered.
+            for (key, id) in [
+                ("idVendor", filter.vendor_id),
+                ("idProduct", filter.product_id),
+            ] {
+                if let Some(id) = id {
+                    let key = CFString::from_static_string(key);
+                    let value = CFNumber::from(i32::from(id));
+                    CFDictionarySetValue(d, key.as_CFTypeRef(), value.as_CFTypeRef());
+                }
+            }
+
             CFDictionary::wrap_under_create_rule(d)
         };
 
@@ -9,7 +9,10 @@ pub(crate) use transfer::TransferData;
 
 mod enumeration;
 mod events;
-pub use enumeration::{list_buses, list_devices};
+pub(crate) use enumeration::get_registry_property;
+pub use enumeration::{
+    check_access, list_buses, list_devices, list_devices_by_id, probe_location_id, scan_devices,
+};
 
 mod device;
 pub(crate) use device::MacDevice as Device;
@@ -32,10 +35,14 @@ fn status_to_transfer_result(status: IOReturn) -> Result<(), TransferError> {
     match status {
         io_kit_sys::ret::kIOReturnSuccess | io_kit_sys::ret::kIOReturnUnderrun => Ok(()),
         io_kit_sys::ret::kIOReturnNoDevice => Err(TransferError::Disconnected),
-        io_kit_sys::ret::kIOReturnAborted | iokit_c::kIOUSBTransactionTimeout => {
-            Err(TransferError::Cancelled)
-        }
+        io_kit_sys::ret::kIOReturnAborted => Err(TransferError::Cancelled),
+        iokit_c::kIOUSBTransactionTimeout => Err(TransferError::TimedOut),
         iokit_c::kIOUSBPipeStalled => Err(TransferError::Stall),
+        io_kit_sys::ret::kIOReturnOverrun => Err(TransferError::Overflow),
+        io_kit_sys::ret::kIOReturnNotResponding
+        | io_kit_sys::ret::kIOReturnIOError
+        | io_kit_sys::ret::kIOReturnDMAError
+        | iokit_c::kIOUSBHighSpeedSplitError => Err(TransferError::Fault(status as u32)),
         io_kit_sys::ret::kIOReturnBadArgument => Err(TransferError::InvalidArgument), // used for `submit_err`
         _ => Err(TransferError::Unknown(status as u32)),
     }
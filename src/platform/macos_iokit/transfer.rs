@@ -7,6 +7,7 @@ use crate::transfer::{Allocator, Buffer, Completion, Direction, TransferError};
 pub struct TransferData {
     pub(super) buf: *mut u8,
     pub(super) capacity: u32,
+    pub(super) allocator: Allocator,
     pub(super) requested_len: u32,
     pub(super) actual_len: u32,
     pub(super) status: IOReturn,
@@ -14,7 +15,13 @@ pub struct TransferData {
 
 impl Drop for TransferData {
     fn drop(&mut self) {
-        unsafe { drop(Vec::from_raw_parts(self.buf, 0, self.capacity as usize)) }
+        drop(Buffer {
+            ptr: self.buf,
+            len: 0,
+            requested_len: 0,
+            capacity: self.capacity,
+            allocator: mem::replace(&mut self.allocator, Allocator::Default),
+        });
     }
 }
 
@@ -28,6 +35,7 @@ impl TransferData {
         TransferData {
             buf,
             capacity,
+            allocator: Allocator::Default,
             requested_len,
             actual_len: 0,
             status: kIOReturnSuccess,
@@ -53,19 +61,24 @@ impl TransferData {
         };
         let requested_len = mem::replace(&mut self.requested_len, 0);
         let actual_len = mem::replace(&mut self.actual_len, 0) as usize;
+        let allocator = mem::replace(&mut self.allocator, Allocator::Default);
 
         let buffer = Buffer {
             ptr,
             len,
             requested_len,
             capacity,
-            allocator: Allocator::Default,
+            allocator,
         };
 
         Completion {
             status,
             actual_len,
             buffer,
+            tag: 0,
+            endpoint: 0,
+            submitted_at: None,
+            completed_at: None,
         }
     }
 }
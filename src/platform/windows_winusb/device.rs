@@ -17,9 +17,9 @@ use log::{debug, error, warn};
 use windows_sys::Win32::{
     Devices::Usb::{
         self, WinUsb_ControlTransfer, WinUsb_Free, WinUsb_GetAssociatedInterface,
-        WinUsb_Initialize, WinUsb_ReadPipe, WinUsb_ResetPipe, WinUsb_SetCurrentAlternateSetting,
-        WinUsb_SetPipePolicy, WinUsb_WritePipe, USB_DEVICE_DESCRIPTOR, WINUSB_INTERFACE_HANDLE,
-        WINUSB_SETUP_PACKET,
+        WinUsb_GetPipePolicy, WinUsb_Initialize, WinUsb_ReadPipe, WinUsb_ResetPipe,
+        WinUsb_SetCurrentAlternateSetting, WinUsb_SetPipePolicy, WinUsb_SetPowerPolicy,
+        WinUsb_WritePipe, USB_DEVICE_DESCRIPTOR, WINUSB_INTERFACE_HANDLE, WINUSB_SETUP_PACKET,
     },
     Foundation::{
         GetLastError, ERROR_BAD_COMMAND, ERROR_DEVICE_NOT_CONNECTED, ERROR_FILE_NOT_FOUND,
@@ -32,17 +32,19 @@ use windows_sys::Win32::{
 use crate::{
     bitset::EndpointBitSet,
     descriptors::{
-        ConfigurationDescriptor, DeviceDescriptor, EndpointDescriptor, DESCRIPTOR_LEN_DEVICE,
-        DESCRIPTOR_TYPE_CONFIGURATION,
+        ConfigurationDescriptor, DeviceDescriptor, EndpointDescriptor, TransferType,
+        DESCRIPTOR_LEN_DEVICE, DESCRIPTOR_TYPE_CONFIGURATION,
     },
     maybe_future::{blocking::Blocking, Ready},
     transfer::{
         internal::{
-            notify_completion, take_completed_from_queue, Idle, Notify, Pending, TransferFuture,
+            notify_completion, take_completed_from_queue, CloseNotify, Idle, Notify, Pending,
+            TransferFuture,
         },
         Buffer, Completion, ControlIn, ControlOut, Direction, Recipient, TransferError,
+        TransferPriority,
     },
-    DeviceInfo, Error, ErrorKind, MaybeFuture, Speed,
+    ClaimStatus, DeviceInfo, Error, ErrorKind, MaybeFuture, ResetOutcome, Speed,
 };
 
 use super::{
@@ -51,7 +53,7 @@ use super::{
     },
     hub::HubPort,
     transfer::TransferData,
-    util::{create_file, raw_handle, WCStr},
+    util::{create_file, is_file_in_use, raw_handle, WCStr},
     DevInst,
 };
 
@@ -60,13 +62,18 @@ pub(crate) struct WindowsDevice {
     config_descriptors: Vec<Vec<u8>>,
     active_config: u8,
     speed: Option<Speed>,
+    address: u8,
     devinst: DevInst,
     handles: Mutex<BTreeMap<u8, WinusbFileHandle>>,
+
+    /// Notifies `wait_closed` once the device is released.
+    pub(crate) closed: CloseNotify,
 }
 
 impl WindowsDevice {
     pub(crate) fn from_device_info(
         d: &DeviceInfo,
+        _read_only: bool,
     ) -> impl MaybeFuture<Output = Result<Arc<WindowsDevice>, Error>> {
         let instance_id = d.instance_id.clone();
         let devinst = d.devinst;
@@ -106,8 +113,10 @@ impl WindowsDevice {
                 config_descriptors,
                 speed: connection_info.speed,
                 active_config: connection_info.active_config,
+                address: connection_info.address,
                 devinst,
                 handles: Mutex::new(BTreeMap::new()),
+                closed: CloseNotify::default(),
             }))
         })
     }
@@ -170,6 +179,101 @@ impl WindowsDevice {
         )))
     }
 
+    pub(crate) fn cycle_port(
+        &self,
+        _off_time: Duration,
+    ) -> impl MaybeFuture<Output = Result<(), Error>> {
+        Ready(Err(Error::new(
+            ErrorKind::Unsupported,
+            "port cycle not supported on this platform",
+        )))
+    }
+
+    pub(crate) fn state(&self) -> Result<crate::hotplug::DeviceState, Error> {
+        // `get_info` fails if nothing is connected to the port, but the port
+        // may be connected to a different device, or to the same device
+        // after it re-enumerated, which gets a new address.
+        let info = HubPort::by_child_devinst(self.devinst)?.get_info()?;
+        if info.address != self.address
+            || info.device_desc.idVendor != self.device_descriptor.vendor_id()
+            || info.device_desc.idProduct != self.device_descriptor.product_id()
+        {
+            return Err(Error::new(
+                ErrorKind::Disconnected,
+                "a different device is connected to the port",
+            ));
+        }
+        Ok(crate::hotplug::DeviceState {
+            suspended: None,
+            configuration: Some(info.active_config),
+        })
+    }
+
+    pub(crate) fn interface_claim_status(
+        &self,
+        interface_number: u8,
+    ) -> Result<ClaimStatus, Error> {
+        let mut first_interface = 0;
+        let mut child = None;
+        let mut driver = get_driver_name(self.devinst);
+        if driver.eq_ignore_ascii_case("usbccgp") {
+            let (first, child_dev) = find_usbccgp_child(self.devinst, interface_number)
+                .ok_or_else(|| Error::new(ErrorKind::NotFound, "Interface not found"))?;
+            first_interface = first;
+            child = Some(child_dev);
+            driver = get_driver_name(child_dev);
+        }
+
+        if driver.is_empty() {
+            return Ok(ClaimStatus::Free);
+        } else if !driver.eq_ignore_ascii_case("winusb") {
+            return Ok(ClaimStatus::KernelDriver(driver));
+        }
+
+        let handles = self.handles.lock().unwrap();
+        if let Some(h) = handles.get(&first_interface) {
+            // This process holds the WinUSB handle.
+            return Ok(if h.claimed_interfaces.is_set(interface_number) {
+                ClaimStatus::Claimed
+            } else {
+                ClaimStatus::Free
+            });
+        }
+
+        let path = match child {
+            Some(child_dev) => get_usbccgp_winusb_device_path(child_dev)?,
+            None => get_winusb_device_path(self.devinst)?,
+        };
+        match is_file_in_use(&path) {
+            Ok(true) => Ok(ClaimStatus::Claimed),
+            Ok(false) => Ok(ClaimStatus::Free),
+            Err(e) => Err(Error::new_os(ErrorKind::Other, "failed to open device", e).log_debug()),
+        }
+    }
+
+    pub(crate) fn use_dedicated_event_thread(&self) -> Result<(), Error> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "dedicated event threads are not supported on this platform",
+        ))
+    }
+
+    pub(crate) fn kernel_driver(&self, _interface_number: u8) -> Result<Option<String>, Error> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "querying interface drivers is not supported on this platform",
+        ))
+    }
+
+    pub(crate) fn reset_and_revalidate(
+        &self,
+    ) -> impl MaybeFuture<Output = Result<ResetOutcome, Error>> {
+        Ready(Err(Error::new(
+            ErrorKind::Unsupported,
+            "reset not supported by WinUSB",
+        )))
+    }
+
     pub(crate) fn claim_interface(
         self: Arc<Self>,
         interface_number: u8,
@@ -354,6 +458,7 @@ impl WinusbFileHandle {
             first_interface_number: self.first_interface,
             winusb_handle,
             state: Mutex::new(InterfaceState::default()),
+            closed: CloseNotify::default(),
         }))
     }
 }
@@ -377,6 +482,9 @@ pub(crate) struct WindowsInterface {
     pub(crate) interface_number: u8,
     pub(crate) winusb_handle: WINUSB_INTERFACE_HANDLE,
     state: Mutex<InterfaceState>,
+
+    /// Notifies `wait_closed` once the interface is released.
+    pub(crate) closed: CloseNotify,
 }
 
 #[derive(Default)]
@@ -450,6 +558,36 @@ impl WindowsInterface {
         })
     }
 
+    pub fn control_in_buffer(
+        self: &Arc<Self>,
+        data: ControlIn,
+        mut buffer: Buffer,
+        _timeout: Duration,
+    ) -> impl MaybeFuture<Output = Completion> {
+        let len = data.length as usize;
+        if buffer.capacity() < len {
+            buffer = Buffer::new(len);
+        }
+        buffer.clear();
+        buffer.set_requested_len(len);
+
+        let mut t = TransferData::new(0x80);
+        t.set_buffer(buffer);
+
+        let pkt = WINUSB_SETUP_PACKET {
+            RequestType: data.request_type(),
+            Request: data.request,
+            Value: data.value,
+            Index: data.index,
+            Length: data.length,
+        };
+
+        let intf = self.clone();
+
+        TransferFuture::new(t, |t| self.submit_control(t, pkt))
+            .map(move |mut t| t.take_completion(&intf))
+    }
+
     pub fn control_out(
         self: &Arc<Self>,
         data: ControlOut,
@@ -512,6 +650,40 @@ impl WindowsInterface {
         self.state.lock().unwrap().alt_setting
     }
 
+    pub fn update_alt_setting(&self, alt_setting: u8) {
+        self.state.lock().unwrap().alt_setting = alt_setting;
+    }
+
+    fn set_power_policy<T>(&self, policy: u32, value: T, msg: &'static str) -> Result<(), Error> {
+        unsafe {
+            let r = WinUsb_SetPowerPolicy(
+                self.winusb_handle,
+                policy,
+                size_of_val(&value) as u32,
+                &value as *const _ as *const c_void,
+            );
+            if r == TRUE {
+                Ok(())
+            } else {
+                Err(match GetLastError() {
+                    e @ ERROR_BAD_COMMAND => {
+                        Error::new_os(ErrorKind::Disconnected, "device disconnected", e)
+                    }
+                    e => Error::new_os(ErrorKind::Other, msg, e),
+                })
+            }
+        }
+    }
+
+    pub fn set_autosuspend(&self, enable: bool) -> Result<(), Error> {
+        self.set_power_policy(Usb::AUTO_SUSPEND, enable as u8, "failed to set autosuspend")
+    }
+
+    pub fn set_autosuspend_delay(&self, delay: Duration) -> Result<(), Error> {
+        let ms = delay.as_millis().min(u32::MAX as u128) as u32;
+        self.set_power_policy(Usb::SUSPEND_DELAY, ms, "failed to set autosuspend delay")
+    }
+
     pub fn endpoint(
         self: &Arc<Self>,
         descriptor: EndpointDescriptor,
@@ -519,6 +691,13 @@ impl WindowsInterface {
         let address = descriptor.address();
         let max_packet_size = descriptor.max_packet_size();
 
+        if descriptor.transfer_type() == TransferType::Isochronous {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "isochronous endpoints are not supported on this platform",
+            ));
+        }
+
         let mut state = self.state.lock().unwrap();
 
         if state.endpoints.is_set(address) {
@@ -543,6 +722,31 @@ impl WindowsInterface {
             }
         }
 
+        // With RAW_IO, reads larger than `MAXIMUM_TRANSFER_SIZE` fail instead
+        // of being split by WinUSB.
+        let max_transfer_size = if Direction::from_address(address) == Direction::In {
+            unsafe {
+                let mut size: u32 = 0;
+                let mut len = size_of_val(&size) as u32;
+                let r = WinUsb_GetPipePolicy(
+                    self.winusb_handle,
+                    address,
+                    Usb::MAXIMUM_TRANSFER_SIZE,
+                    &mut len,
+                    &mut size as *mut _ as *mut c_void,
+                );
+                if r == TRUE && size > 0 {
+                    size as usize
+                } else {
+                    let err = GetLastError();
+                    warn!("Failed to get MAXIMUM_TRANSFER_SIZE on endpoint {address:02X}: error {err:x}");
+                    usize::MAX
+                }
+            }
+        } else {
+            usize::MAX
+        };
+
         Ok(WindowsEndpoint {
             inner: Arc::new(EndpointInner {
                 address,
@@ -550,12 +754,24 @@ impl WindowsInterface {
                 notify: Notify::new(),
             }),
             max_packet_size,
+            max_transfer_size,
             pending: VecDeque::new(),
             idle_transfer: None,
+            pipe_timeout_ms: 0,
+            timestamps: false,
         })
     }
 
-    fn submit(&self, mut t: Idle<TransferData>) -> Pending<TransferData> {
+    fn submit(&self, t: Idle<TransferData>) -> Pending<TransferData> {
+        self.try_submit(t).unwrap_or_else(|t| t)
+    }
+
+    /// Submit a transfer, returning `Err` with the transfer already completed
+    /// if WinUSB rejected it.
+    fn try_submit(
+        &self,
+        mut t: Idle<TransferData>,
+    ) -> Result<Pending<TransferData>, Pending<TransferData>> {
         let endpoint = t.endpoint;
         let dir = Direction::from_address(endpoint);
         let len = t.request_len;
@@ -628,10 +844,14 @@ impl WindowsInterface {
             )
         };
 
-        self.post_submit(r, t)
+        self.post_submit(r, t).unwrap_or_else(|t| t)
     }
 
-    fn post_submit(&self, r: i32, t: Pending<TransferData>) -> Pending<TransferData> {
+    fn post_submit(
+        &self,
+        r: i32,
+        t: Pending<TransferData>,
+    ) -> Result<Pending<TransferData>, Pending<TransferData>> {
         if r == TRUE {
             error!("Transfer submit completed synchronously")
         }
@@ -653,9 +873,10 @@ impl WindowsInterface {
                 };
                 notify_completion::<TransferData>(t.as_ptr());
             }
+            return Err(t);
         }
 
-        t
+        Ok(t)
     }
 
     fn cancel(&self, t: &mut Pending<TransferData>) {
@@ -680,10 +901,18 @@ pub(crate) struct WindowsEndpoint {
 
     pub(crate) max_packet_size: usize,
 
+    pub(crate) max_transfer_size: usize,
+
     /// A queue of pending transfers, expected to complete in order
     pending: VecDeque<Pending<TransferData>>,
 
     idle_transfer: Option<Idle<TransferData>>,
+
+    /// Current `PIPE_TRANSFER_TIMEOUT` policy of the pipe, 0 for no timeout.
+    pipe_timeout_ms: u32,
+
+    /// Record the completion time of transfers
+    timestamps: bool,
 }
 
 struct EndpointInner {
@@ -701,6 +930,30 @@ impl WindowsEndpoint {
         self.pending.len()
     }
 
+    pub(crate) fn ready(&self) -> usize {
+        self.pending.iter().take_while(|t| t.is_complete()).count()
+    }
+
+    pub(crate) fn set_notify_callback(&self, callback: Option<Arc<dyn Fn() + Send + Sync>>) {
+        self.inner.notify.set_callback(callback);
+    }
+
+    pub(crate) fn interface(&self) -> &Arc<WindowsInterface> {
+        &self.inner.interface
+    }
+
+    pub(crate) fn native_handle(&self, index: usize) -> Option<*mut c_void> {
+        Some(self.pending.get(index)?.as_ptr().cast())
+    }
+
+    pub(crate) fn cancel(&mut self, index: usize) -> bool {
+        let Some(transfer) = self.pending.get_mut(index) else {
+            return false;
+        };
+        self.inner.interface.cancel(transfer);
+        true
+    }
+
     pub(crate) fn cancel_all(&mut self) {
         // Cancel transfers in reverse order to ensure subsequent transfers
         // can't complete out of order while we're going through them.
@@ -714,15 +967,122 @@ impl WindowsEndpoint {
             Idle::new(self.inner.clone(), TransferData::new(self.inner.address))
         });
         t.set_buffer(buffer);
+        t.set_timestamps(self.timestamps);
         t
     }
 
     pub(crate) fn submit(&mut self, buffer: Buffer) {
+        self.set_pipe_timeout(0);
         let t = self.make_transfer(buffer);
         let t = self.inner.interface.submit(t);
         self.pending.push_back(t);
     }
 
+    /// Submit independent transfers, returning how many WinUSB accepted.
+    pub(crate) fn submit_batch(&mut self, bufs: Vec<Buffer>, _batch_interrupts: bool) -> usize {
+        self.set_pipe_timeout(0);
+        self.pending.reserve(bufs.len());
+        let mut accepted = 0;
+        for buffer in bufs {
+            let t = self.make_transfer(buffer);
+            let r = self.inner.interface.try_submit(t);
+            accepted += r.is_ok() as usize;
+            self.pending.push_back(r.unwrap_or_else(|t| t));
+        }
+        accepted
+    }
+
+    pub(crate) fn submit_timeout(&mut self, buffer: Buffer, timeout: Duration) {
+        // A timeout of 0 means no timeout to WinUSB, so round up.
+        let timeout_ms = timeout.as_millis().clamp(1, u32::MAX as u128) as u32;
+        self.set_pipe_timeout(timeout_ms);
+        let t = self.make_transfer(buffer);
+        let t = self.inner.interface.submit(t);
+        self.pending.push_back(t);
+    }
+
+    pub(crate) fn set_no_interrupt(&mut self, _enable: bool) {
+        // Not implemented: there is no equivalent hint.
+    }
+
+    pub(crate) fn set_timestamps(&mut self, enable: bool) {
+        self.timestamps = enable;
+    }
+
+    pub(crate) fn set_priority(&mut self, _priority: TransferPriority) {
+        // Not implemented: only the Linux event loop reorders completions.
+    }
+
+    pub(crate) fn set_auto_zlp(&mut self, enable: bool) -> Result<(), Error> {
+        let address = self.inner.address;
+        let value: u8 = enable.into();
+        unsafe {
+            let r = WinUsb_SetPipePolicy(
+                self.inner.interface.winusb_handle,
+                address,
+                Usb::SHORT_PACKET_TERMINATE,
+                size_of_val(&value) as u32,
+                &value as *const _ as *const c_void,
+            );
+            if r == TRUE {
+                Ok(())
+            } else {
+                Err(Error::new_os(
+                    ErrorKind::Other,
+                    "failed to set SHORT_PACKET_TERMINATE",
+                    GetLastError(),
+                ))
+            }
+        }
+    }
+
+    fn set_pipe_timeout(&mut self, timeout_ms: u32) {
+        if self.pipe_timeout_ms == timeout_ms {
+            return;
+        }
+
+        let address = self.inner.address;
+        unsafe {
+            let r = WinUsb_SetPipePolicy(
+                self.inner.interface.winusb_handle,
+                address,
+                Usb::PIPE_TRANSFER_TIMEOUT,
+                size_of_val(&timeout_ms) as u32,
+                &timeout_ms as *const _ as *const c_void,
+            );
+            if r == TRUE {
+                self.pipe_timeout_ms = timeout_ms;
+            } else {
+                let err = GetLastError();
+                warn!(
+                    "Failed to set PIPE_TRANSFER_TIMEOUT on endpoint {address:02X}: error {err:x}"
+                );
+            }
+        }
+    }
+
+    pub(crate) fn alloc_streams(
+        &self,
+        _num_streams: u32,
+    ) -> impl MaybeFuture<Output = Result<u32, Error>> {
+        Ready(Err(Error::new(
+            ErrorKind::Unsupported,
+            "bulk streams not supported by WinUSB",
+        )))
+    }
+
+    pub(crate) fn free_streams(&self) -> impl MaybeFuture<Output = Result<(), Error>> {
+        Ready(Err(Error::new(
+            ErrorKind::Unsupported,
+            "bulk streams not supported by WinUSB",
+        )))
+    }
+
+    pub(crate) fn submit_stream(&mut self, buffer: Buffer, _stream_id: u32) {
+        // No stream can have been allocated, so the ID is invalid.
+        self.submit_err(buffer, TransferError::InvalidArgument);
+    }
+
     pub(crate) fn submit_err(&mut self, buffer: Buffer, err: TransferError) {
         let mut t = self.make_transfer(buffer);
         t.error_from_submit = Err(err);
@@ -732,7 +1092,8 @@ impl WindowsEndpoint {
     pub(crate) fn poll_next_complete(&mut self, cx: &mut Context) -> Poll<Completion> {
         self.inner.notify.subscribe(cx);
         if let Some(mut transfer) = take_completed_from_queue(&mut self.pending) {
-            let completion = transfer.take_completion(&self.inner.interface);
+            let mut completion = transfer.take_completion(&self.inner.interface);
+            completion.completed_at = transfer.completed_at();
             self.idle_transfer = Some(transfer);
             Poll::Ready(completion)
         } else {
@@ -743,7 +1104,8 @@ impl WindowsEndpoint {
     pub(crate) fn wait_next_complete(&mut self, timeout: Duration) -> Option<Completion> {
         self.inner.notify.wait_timeout(timeout, || {
             take_completed_from_queue(&mut self.pending).map(|mut transfer| {
-                let completion = transfer.take_completion(&self.inner.interface);
+                let mut completion = transfer.take_completion(&self.inner.interface);
+                completion.completed_at = transfer.completed_at();
                 self.idle_transfer = Some(transfer);
                 completion
             })
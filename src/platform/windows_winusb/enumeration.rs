@@ -1,11 +1,14 @@
+use std::collections::HashSet;
 use std::ffi::{OsStr, OsString};
+use std::iter;
 
 use log::debug;
+use windows_sys::core::GUID;
 use windows_sys::Win32::Devices::{
     Properties::{
-        DEVPKEY_Device_Address, DEVPKEY_Device_BusReportedDeviceDesc, DEVPKEY_Device_DeviceDesc,
-        DEVPKEY_Device_HardwareIds, DEVPKEY_Device_InstanceId, DEVPKEY_Device_LocationPaths,
-        DEVPKEY_Device_Parent, DEVPKEY_Device_Service,
+        DEVPKEY_Device_Address, DEVPKEY_Device_BusReportedDeviceDesc, DEVPKEY_Device_ContainerId,
+        DEVPKEY_Device_DeviceDesc, DEVPKEY_Device_HardwareIds, DEVPKEY_Device_InstanceId,
+        DEVPKEY_Device_LocationPaths, DEVPKEY_Device_Parent, DEVPKEY_Device_Service,
     },
     Usb::{GUID_DEVINTERFACE_USB_DEVICE, GUID_DEVINTERFACE_USB_HUB},
 };
@@ -15,6 +18,7 @@ use crate::{
         decode_string_descriptor, language_id::US_ENGLISH, ConfigurationDescriptor,
         DESCRIPTOR_TYPE_CONFIGURATION, DESCRIPTOR_TYPE_STRING,
     },
+    enumeration::StoredString,
     maybe_future::{blocking::Blocking, MaybeFuture},
     BusInfo, DeviceInfo, Error, ErrorKind, InterfaceInfo, UsbControllerType,
 };
@@ -27,12 +31,26 @@ use super::{
 
 pub fn list_devices() -> impl MaybeFuture<Output = Result<impl Iterator<Item = DeviceInfo>, Error>>
 {
-    Blocking::new(|| {
-        let devs: Vec<DeviceInfo> = cfgmgr32::list_interfaces(GUID_DEVINTERFACE_USB_DEVICE, None)
-            // get USB_HUB devices as well, like other platforms. ROOT_HUBs will be dropped by probe_device
-            .iter()
-            .chain(cfgmgr32::list_interfaces(GUID_DEVINTERFACE_USB_HUB, None).iter())
-            .flat_map(|i| get_device_interface_property::<WCString>(i, DEVPKEY_Device_InstanceId))
+    list_devices_by_id(None, None)
+}
+
+/// List devices, skipping those that don't match `vendor_id` and
+/// `product_id` before probing them.
+pub fn list_devices_by_id(
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+) -> impl MaybeFuture<Output = Result<impl Iterator<Item = DeviceInfo>, Error>> {
+    Blocking::new(move || {
+        let devs: Vec<DeviceInfo> = list_device_instance_ids()
+            .into_iter()
+            .filter(|d| {
+                // Instance IDs look like `USB\VID_1234&PID_5678\...`. Probe
+                // devices with other formats, and filter them afterwards.
+                let Some((vid, pid)) = parse_instance_id_vid_pid(&OsString::from(&**d)) else {
+                    return true;
+                };
+                vendor_id.map_or(true, |v| v == vid) && product_id.map_or(true, |p| p == pid)
+            })
             .flat_map(|d| DevInst::from_instance_id(&d))
             .flat_map(probe_device)
             .collect();
@@ -40,6 +58,39 @@ pub fn list_devices() -> impl MaybeFuture<Output = Result<impl Iterator<Item = D
     })
 }
 
+/// List connected devices, probing only those not included in `known`.
+///
+/// Returns the ID of every device, with its `DeviceInfo` if it was probed.
+pub fn scan_devices(
+    known: HashSet<crate::DeviceId>,
+) -> impl MaybeFuture<Output = Result<Vec<(crate::DeviceId, Option<DeviceInfo>)>, Error>> {
+    Blocking::new(move || {
+        Ok(list_device_instance_ids()
+            .into_iter()
+            .flat_map(|d| DevInst::from_instance_id(&d))
+            .flat_map(|devinst| {
+                let id = crate::DeviceId(devinst);
+                if known.contains(&id) {
+                    return Some((id, None));
+                }
+                probe_device(devinst).map(|d| (id, Some(d)))
+            })
+            .collect())
+    })
+}
+
+/// Get the instance IDs of all USB devices, including hubs.
+fn list_device_instance_ids() -> Vec<WCString> {
+    let devices = cfgmgr32::list_interfaces(GUID_DEVINTERFACE_USB_DEVICE, None);
+    // get USB_HUB devices as well, like other platforms. ROOT_HUBs will be dropped by probe_device
+    let hubs = cfgmgr32::list_interfaces(GUID_DEVINTERFACE_USB_HUB, None);
+    devices
+        .iter()
+        .chain(hubs.iter())
+        .flat_map(|i| get_device_interface_property::<WCString>(i, DEVPKEY_Device_InstanceId))
+        .collect()
+}
+
 pub fn list_buses() -> impl MaybeFuture<Output = Result<impl Iterator<Item = BusInfo>, Error>> {
     Blocking::new(|| {
         let devs: Vec<BusInfo> = cfgmgr32::list_interfaces(GUID_DEVINTERFACE_USB_HUB, None)
@@ -52,6 +103,14 @@ pub fn list_buses() -> impl MaybeFuture<Output = Result<impl Iterator<Item = Bus
     })
 }
 
+/// Probe the device with the specified instance ID, without listing all
+/// devices.
+pub fn probe_instance_id(instance_id: &OsStr) -> Result<DeviceInfo, Error> {
+    DevInst::from_instance_id(&WCString::from(instance_id))
+        .and_then(probe_device)
+        .ok_or_else(|| Error::new(ErrorKind::Disconnected, "device not found"))
+}
+
 pub fn probe_device(devinst: DevInst) -> Option<DeviceInfo> {
     let instance_id = devinst.get_property::<OsString>(DEVPKEY_Device_InstanceId)?;
     if instance_id.to_string_lossy().starts_with("USB\\ROOT_HUB") {
@@ -93,6 +152,12 @@ pub fn probe_device(devinst: DevInst) -> Option<DeviceInfo> {
         list_interfaces_from_desc(&hub_port, info.active_config).unwrap_or_default();
 
     if driver.eq_ignore_ascii_case("usbccgp") {
+        for interface_info in &mut interfaces {
+            interface_info.driver = find_usbccgp_child(devinst, interface_info.interface_number)
+                .map(|(_, child)| get_driver_name(child))
+                .filter(|s| !s.is_empty());
+        }
+
         // Populate interface descriptor strings when available from child device nodes.
         devinst
             .children()
@@ -110,12 +175,26 @@ pub fn probe_device(devinst: DevInst) -> Option<DeviceInfo> {
                     interface_info.interface_string = interface_string.into_string().ok();
                 }
             });
+    } else if !driver.is_empty() {
+        for interface_info in &mut interfaces {
+            interface_info.driver = Some(driver.clone());
+        }
     }
 
     let location_paths = devinst
         .get_property::<Vec<OsString>>(DEVPKEY_Device_LocationPaths)
         .unwrap_or_default();
 
+    let container_id = devinst
+        .get_property::<GUID>(DEVPKEY_Device_ContainerId)
+        .map(|g| format_guid(&g));
+
+    let device_paths = devinst
+        .interfaces(GUID_DEVINTERFACE_USB_DEVICE)
+        .iter()
+        .map(OsString::from)
+        .collect();
+
     let (bus_id, port_chain) = location_paths
         .iter()
         .find_map(|p| parse_location_path(p))
@@ -124,6 +203,8 @@ pub fn probe_device(devinst: DevInst) -> Option<DeviceInfo> {
     Some(DeviceInfo {
         instance_id,
         location_paths,
+        container_id,
+        device_paths,
         parent_instance_id,
         devinst,
         port_number,
@@ -139,9 +220,9 @@ pub fn probe_device(devinst: DevInst) -> Option<DeviceInfo> {
         subclass: info.device_desc.bDeviceSubClass,
         protocol: info.device_desc.bDeviceProtocol,
         speed: info.speed,
-        manufacturer_string: None,
-        product_string,
-        serial_number,
+        manufacturer_string: StoredString::new(None, Some(info.device_desc.iManufacturer)),
+        product_string: StoredString::new(product_string, Some(info.device_desc.iProduct)),
+        serial_number: StoredString::new(serial_number, Some(info.device_desc.iSerialNumber)),
         interfaces,
     })
 }
@@ -215,12 +296,66 @@ fn list_interfaces_from_desc(hub_port: &HubPort, active_config: u8) -> Option<Ve
                     subclass: i_desc.subclass(),
                     protocol: i_desc.protocol(),
                     interface_string: None,
+                    driver: None,
                 }
             })
             .collect(),
     )
 }
 
+/// Format a GUID in the registry format, like
+/// `{A5DCBF10-6530-11D2-901F-00C04FB951ED}`.
+fn format_guid(guid: &GUID) -> String {
+    let [d0, d1, d2, d3, d4, d5, d6, d7] = guid.data4;
+    format!(
+        "{{{:08X}-{:04X}-{:04X}-{d0:02X}{d1:02X}-{d2:02X}{d3:02X}{d4:02X}{d5:02X}{d6:02X}{d7:02X}}}",
+        guid.data1, guid.data2, guid.data3
+    )
+}
+
+/// Check whether the device is connected and whether it or at least one of
+/// its interfaces is bound to WinUSB, without opening it.
+pub fn check_access(devinst: DevInst) -> Result<(), Error> {
+    HubPort::by_child_devinst(devinst)
+        .map_err(|_| Error::new(ErrorKind::Disconnected, "device not found"))?;
+
+    let driver = get_driver_name(devinst);
+    if driver.eq_ignore_ascii_case("winusb") {
+        Ok(())
+    } else if driver.eq_ignore_ascii_case("usbccgp") {
+        if devinst
+            .children()
+            .any(|child| get_driver_name(child).eq_ignore_ascii_case("winusb"))
+        {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ErrorKind::Unsupported,
+                "no interface of the composite device is bound to WinUSB",
+            ))
+        }
+    } else if driver.is_empty() {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "no driver is installed for the device",
+        ))
+    } else {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "device is bound to a driver other than WinUSB",
+        ))
+    }
+}
+
+/// Check whether the device or one of its composite functions has a device
+/// interface of the specified class.
+pub(crate) fn has_device_interface(devinst: DevInst, guid: u128) -> bool {
+    let guid = GUID::from_u128(guid);
+    iter::once(devinst)
+        .chain(devinst.children())
+        .any(|d| d.interfaces(guid).iter().next().is_some())
+}
+
 pub(crate) fn get_driver_name(dev: DevInst) -> String {
     dev.get_property::<OsString>(DEVPKEY_Device_Service)
         .and_then(|s| s.into_string().ok())
@@ -316,6 +451,30 @@ fn get_interface_number(intf_dev: DevInst) -> Option<u8> {
         })
 }
 
+/// Parse the vendor and product ID from a device instance ID such as
+/// `USB\VID_1234&PID_ABCD\0123456`
+pub(crate) fn parse_instance_id_vid_pid(s: &OsStr) -> Option<(u16, u16)> {
+    let s = s.to_str()?;
+    let ids = s.get(..21)?.strip_prefix("USB\\VID_")?;
+    let (vid, pid) = ids.split_once("&PID_")?;
+    Some((
+        u16::from_str_radix(vid, 16).ok()?,
+        u16::from_str_radix(pid, 16).ok()?,
+    ))
+}
+
+#[test]
+fn test_parse_instance_id_vid_pid() {
+    assert_eq!(
+        parse_instance_id_vid_pid(OsStr::new("USB\\VID_1234&PID_ABCD\\0123456")),
+        Some((0x1234, 0xabcd))
+    );
+    assert_eq!(
+        parse_instance_id_vid_pid(OsStr::new("USB\\ROOT_HUB30\\4&1234&0&0")),
+        None
+    );
+}
+
 /// Parse interface number from a Hardware ID value
 fn parse_hardware_id(s: &OsStr) -> Option<u8> {
     let s = s.to_str()?;
@@ -323,6 +482,14 @@ fn parse_hardware_id(s: &OsStr) -> Option<u8> {
     u8::from_str_radix(s.get(0..2)?, 16).ok()
 }
 
+#[test]
+fn test_format_guid() {
+    assert_eq!(
+        format_guid(&GUID_DEVINTERFACE_USB_DEVICE),
+        "{A5DCBF10-6530-11D2-901F-00C04FB951ED}"
+    );
+}
+
 #[test]
 fn test_parse_hardware_id() {
     assert_eq!(parse_hardware_id(OsStr::new("")), None);
@@ -1,6 +1,6 @@
 use std::{
     collections::VecDeque,
-    ffi::c_void,
+    ffi::{c_void, OsString},
     mem::size_of,
     ptr::{self, addr_of},
     sync::Mutex,
@@ -8,6 +8,7 @@ use std::{
 };
 
 use log::debug;
+use windows_sys::core::GUID;
 use windows_sys::Win32::{
     Devices::{
         DeviceAndDriverInstallation::{
@@ -25,10 +26,13 @@ use windows_sys::Win32::{
 use crate::{
     hotplug::HotplugEvent,
     platform::windows_winusb::{cfgmgr32::get_device_interface_property, util::WCString},
-    DeviceId, Error,
+    DeviceFilter, DeviceId, Error,
 };
 
-use super::{enumeration::probe_device, util::WCStr};
+use super::{
+    enumeration::{parse_instance_id_vid_pid, probe_device},
+    util::WCStr,
+};
 
 use super::DevInst;
 
@@ -40,6 +44,13 @@ pub(crate) struct WindowsHotplugWatch {
 struct HotplugInner {
     waker: Mutex<Option<Waker>>,
     events: Mutex<VecDeque<(Action, DevInst)>>,
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+
+    /// Whether notifications are for an interface class other than
+    /// `GUID_DEVINTERFACE_USB_DEVICE`, which may be registered on the child
+    /// device node of a composite function.
+    custom_interface: bool,
 }
 
 #[derive(Debug)]
@@ -49,10 +60,17 @@ enum Action {
 }
 
 impl WindowsHotplugWatch {
-    pub fn new() -> Result<WindowsHotplugWatch, Error> {
+    pub fn new(filter: &DeviceFilter) -> Result<WindowsHotplugWatch, Error> {
+        let class_guid = filter
+            .interface_guid
+            .map_or(GUID_DEVINTERFACE_USB_DEVICE, GUID::from_u128);
+
         let inner = Box::into_raw(Box::new(HotplugInner {
             events: Mutex::new(VecDeque::new()),
             waker: Mutex::new(None),
+            vendor_id: filter.vendor_id,
+            product_id: filter.product_id,
+            custom_interface: filter.interface_guid.is_some(),
         }));
 
         let mut registration = ptr::null_mut();
@@ -63,7 +81,7 @@ impl WindowsHotplugWatch {
             Reserved: 0,
             u: CM_NOTIFY_FILTER_0 {
                 DeviceInterface: CM_NOTIFY_FILTER_0_0 {
-                    ClassGuid: GUID_DEVINTERFACE_USB_DEVICE,
+                    ClassGuid: class_guid,
                 },
             },
         };
@@ -161,7 +179,27 @@ unsafe extern "system" fn hotplug_callback(
     let device_instance =
         get_device_interface_property::<WCString>(device_interface, DEVPKEY_Device_InstanceId)
             .unwrap();
-    let devinst = DevInst::from_instance_id(&device_instance).unwrap();
+    let mut devinst = DevInst::from_instance_id(&device_instance).unwrap();
+
+    let instance_id = OsString::from(&*device_instance);
+    if inner.vendor_id.is_some() || inner.product_id.is_some() {
+        let ids_match = parse_instance_id_vid_pid(&instance_id).is_some_and(|(vid, pid)| {
+            inner.vendor_id.map_or(true, |v| v == vid)
+                && inner.product_id.map_or(true, |p| p == pid)
+        });
+        if !ids_match {
+            debug!("Hotplug callback: skipping {device_instance} not matching filter");
+            return ERROR_SUCCESS;
+        }
+    }
+
+    // Interfaces of a composite function are registered on a child of the
+    // USB device's node, like `USB\VID_1234&PID_5678&MI_00\...`.
+    if inner.custom_interface && instance_id.to_string_lossy().contains("&MI_") {
+        if let Some(parent) = devinst.parent() {
+            devinst = parent;
+        }
+    }
 
     debug!("Hotplug callback: action={action:?}, instance={device_instance}");
     inner.events.lock().unwrap().push_back((action, devinst));
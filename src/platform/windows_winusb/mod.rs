@@ -1,7 +1,10 @@
 mod enumeration;
 use std::num::NonZeroU32;
 
-pub use enumeration::{list_buses, list_devices};
+pub(crate) use enumeration::has_device_interface;
+pub use enumeration::{
+    check_access, list_buses, list_devices, list_devices_by_id, probe_instance_id, scan_devices,
+};
 
 mod events;
 
@@ -3,14 +3,16 @@ use std::mem::{self, ManuallyDrop};
 use log::debug;
 use windows_sys::Win32::{
     Foundation::{
-        GetLastError, ERROR_DEVICE_NOT_CONNECTED, ERROR_FILE_NOT_FOUND, ERROR_GEN_FAILURE,
-        ERROR_NO_SUCH_DEVICE, ERROR_OPERATION_ABORTED, ERROR_REQUEST_ABORTED, ERROR_SEM_TIMEOUT,
-        ERROR_SUCCESS, ERROR_TIMEOUT,
+        GetLastError, ERROR_CRC, ERROR_DEVICE_NOT_CONNECTED, ERROR_FILE_NOT_FOUND,
+        ERROR_GEN_FAILURE, ERROR_IO_DEVICE, ERROR_NO_SUCH_DEVICE, ERROR_OPERATION_ABORTED,
+        ERROR_REQUEST_ABORTED, ERROR_SEM_TIMEOUT, ERROR_SUCCESS, ERROR_TIMEOUT,
     },
     System::IO::{GetOverlappedResult, OVERLAPPED},
 };
 
-use crate::transfer::{internal::notify_completion, Buffer, Completion, Direction, TransferError};
+use crate::transfer::{
+    internal::notify_completion, Allocator, Buffer, Completion, Direction, TransferError,
+};
 
 use super::Interface;
 
@@ -22,6 +24,7 @@ pub struct TransferData {
     pub(crate) overlapped: OVERLAPPED,
     pub(crate) buf: *mut u8,
     pub(crate) capacity: u32,
+    pub(crate) allocator: Allocator,
     pub(crate) request_len: u32,
     pub(crate) endpoint: u8,
     pub(crate) error_from_submit: Result<(), TransferError>,
@@ -38,6 +41,7 @@ impl TransferData {
             overlapped: unsafe { mem::zeroed() },
             buf: empty.as_mut_ptr(),
             capacity: 0,
+            allocator: Allocator::Default,
             request_len: 0,
             endpoint,
             error_from_submit: Ok(()),
@@ -51,9 +55,10 @@ impl TransferData {
 
     pub fn set_buffer(&mut self, buf: Buffer) {
         debug_assert!(self.capacity == 0);
-        let buf = ManuallyDrop::new(buf);
+        let mut buf = ManuallyDrop::new(buf);
         self.capacity = buf.capacity;
         self.buf = buf.ptr;
+        self.allocator = mem::replace(&mut buf.allocator, Allocator::Default);
         self.overlapped.InternalHigh = 0;
         self.request_len = match Direction::from_address(self.endpoint) {
             Direction::Out => buf.len,
@@ -70,13 +75,12 @@ impl TransferData {
             match unsafe { GetLastError() } {
                 ERROR_SUCCESS => Ok(()),
                 ERROR_GEN_FAILURE => Err(TransferError::Stall),
-                ERROR_REQUEST_ABORTED
-                | ERROR_TIMEOUT
-                | ERROR_SEM_TIMEOUT
-                | ERROR_OPERATION_ABORTED => Err(TransferError::Cancelled),
+                ERROR_REQUEST_ABORTED | ERROR_OPERATION_ABORTED => Err(TransferError::Cancelled),
+                ERROR_TIMEOUT | ERROR_SEM_TIMEOUT => Err(TransferError::TimedOut),
                 ERROR_FILE_NOT_FOUND | ERROR_DEVICE_NOT_CONNECTED | ERROR_NO_SUCH_DEVICE => {
                     Err(TransferError::Disconnected)
                 }
+                e @ (ERROR_CRC | ERROR_IO_DEVICE) => Err(TransferError::Fault(e)),
                 e => Err(TransferError::Unknown(e)),
             }
         });
@@ -89,6 +93,7 @@ impl TransferData {
             Direction::In => actual_len,
         };
         let requested_len = mem::replace(&mut self.request_len, 0);
+        let allocator = mem::replace(&mut self.allocator, Allocator::Default);
         self.overlapped.InternalHigh = 0;
 
         Completion {
@@ -99,17 +104,25 @@ impl TransferData {
                 len,
                 requested_len,
                 capacity,
-                allocator: crate::transfer::Allocator::Default,
+                allocator,
             },
+            tag: 0,
+            endpoint: 0,
+            submitted_at: None,
+            completed_at: None,
         }
     }
 }
 
 impl Drop for TransferData {
     fn drop(&mut self) {
-        unsafe {
-            drop(Vec::from_raw_parts(self.buf, 0, self.capacity as usize));
-        }
+        drop(Buffer {
+            ptr: self.buf,
+            len: 0,
+            requested_len: 0,
+            capacity: self.capacity,
+            allocator: mem::replace(&mut self.allocator, Allocator::Default),
+        });
     }
 }
 
@@ -11,7 +11,10 @@ use std::{
 };
 
 use windows_sys::Win32::{
-    Foundation::{GetLastError, GENERIC_READ, GENERIC_WRITE, HANDLE, WIN32_ERROR},
+    Foundation::{
+        GetLastError, ERROR_ACCESS_DENIED, ERROR_SHARING_VIOLATION, GENERIC_READ, GENERIC_WRITE,
+        HANDLE, WIN32_ERROR,
+    },
     Storage::FileSystem::{
         CreateFileW, FILE_FLAG_OVERLAPPED, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
     },
@@ -35,6 +38,33 @@ pub fn create_file(path: &WCStr) -> Result<OwnedHandle, WIN32_ERROR> {
     }
 }
 
+/// Check whether a file is open by another handle, by opening and closing it
+/// without sharing.
+///
+/// WinUSB only allows one handle to a device, so opening it fails with
+/// `ERROR_ACCESS_DENIED` rather than `ERROR_SHARING_VIOLATION` if it is in
+/// use.
+pub fn is_file_in_use(path: &WCStr) -> Result<bool, WIN32_ERROR> {
+    unsafe {
+        let r = CreateFileW(
+            path.as_ptr(),
+            GENERIC_READ | GENERIC_WRITE,
+            0,
+            null(),
+            OPEN_EXISTING,
+            FILE_FLAG_OVERLAPPED,
+            ptr::null_mut(),
+        );
+        match OwnedHandle::try_from(HandleOrInvalid::from_raw_handle(r as RawHandle)) {
+            Ok(_handle) => Ok(false),
+            Err(_) => match GetLastError() {
+                ERROR_ACCESS_DENIED | ERROR_SHARING_VIOLATION => Ok(true),
+                e => Err(e),
+            },
+        }
+    }
+}
+
 pub fn raw_handle(h: impl AsHandle) -> HANDLE {
     h.as_handle().as_raw_handle() as HANDLE
 }
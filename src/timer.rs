@@ -0,0 +1,212 @@
+//! Runtime-independent timer for async timeouts, and blocking waits with a
+//! timeout.
+//!
+//! Launches a thread on first use that wakes registered wakers when their
+//! deadline passes. This avoids depending on the timer of a particular async
+//! runtime for methods like `Endpoint::next_complete_timeout`.
+
+use std::{
+    future::{poll_fn, Future},
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex},
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::OnceCell;
+use slab::Slab;
+
+struct Timer {
+    /// Registered deadlines, keyed by their `Sleep`. The waker is taken once
+    /// the deadline passes, and the entry is removed by the `Sleep`.
+    entries: Mutex<Slab<(Instant, Option<Waker>)>>,
+    cond: Condvar,
+}
+
+static TIMER: OnceCell<Timer> = OnceCell::new();
+
+fn timer() -> &'static Timer {
+    let mut start_thread = false;
+    let timer = TIMER.get_or_init(|| {
+        start_thread = true;
+        Timer {
+            entries: Mutex::new(Slab::new()),
+            cond: Condvar::new(),
+        }
+    });
+    if start_thread {
+        thread::spawn(|| timer_loop(TIMER.get().unwrap()));
+    }
+    timer
+}
+
+fn timer_loop(timer: &Timer) {
+    let mut entries = timer.entries.lock().unwrap();
+    loop {
+        let now = Instant::now();
+        let mut next = None;
+        for (_, (deadline, waker)) in entries.iter_mut() {
+            if waker.is_none() {
+                continue;
+            }
+            if *deadline <= now {
+                waker.take().unwrap().wake();
+            } else if next.map_or(true, |next| *deadline < next) {
+                next = Some(*deadline);
+            }
+        }
+
+        entries = match next {
+            Some(next) => timer.cond.wait_timeout(entries, next - now).unwrap().0,
+            None => timer.cond.wait(entries).unwrap(),
+        };
+    }
+}
+
+/// Future that completes at a deadline.
+///
+/// A waker is registered with the timer thread when first polled, and
+/// updated if polled with a different waker. The registration is removed
+/// when the `Sleep` is dropped.
+pub(crate) struct Sleep {
+    deadline: Instant,
+    entry: Option<(usize, Waker)>,
+}
+
+impl Sleep {
+    pub(crate) fn new(deadline: Instant) -> Self {
+        Sleep {
+            deadline,
+            entry: None,
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+
+        let deadline = self.deadline;
+        match &mut self.entry {
+            Some((_, waker)) if waker.will_wake(cx.waker()) => {}
+            Some((key, waker)) => {
+                *waker = cx.waker().clone();
+                let mut entries = timer().entries.lock().unwrap();
+                entries[*key].1 = Some(cx.waker().clone());
+            }
+            None => {
+                let timer = timer();
+                let key = timer
+                    .entries
+                    .lock()
+                    .unwrap()
+                    .insert((deadline, Some(cx.waker().clone())));
+                self.entry = Some((key, cx.waker().clone()));
+                timer.cond.notify_one();
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some((key, _)) = self.entry.take() {
+            timer().entries.lock().unwrap().remove(key);
+        }
+    }
+}
+
+/// Poll with `poll` until it is ready, or resolve to `None` once `timeout`
+/// has elapsed.
+pub(crate) fn with_timeout<T>(
+    timeout: Duration,
+    mut poll: impl FnMut(&mut Context<'_>) -> Poll<T>,
+) -> impl Future<Output = Option<T>> {
+    let mut sleep = Instant::now().checked_add(timeout).map(Sleep::new);
+    poll_fn(move |cx| {
+        if let Poll::Ready(v) = poll(cx) {
+            return Poll::Ready(Some(v));
+        }
+        match &mut sleep {
+            Some(sleep) => Pin::new(sleep).poll(cx).map(|()| None),
+            None => Poll::Pending,
+        }
+    })
+}
+
+/// Waker that unparks a thread blocked in a `wait_*` method.
+pub(crate) struct ThreadWaker(pub(crate) Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Block the current thread polling with `poll` until it is ready, or return
+/// `None` once `timeout` has elapsed.
+///
+/// `poll` is called again whenever the waker of its context is woken, and a
+/// zero `timeout` polls only once.
+pub(crate) fn block_on_timeout<T>(
+    timeout: Duration,
+    mut poll: impl FnMut(&mut Context<'_>) -> Poll<T>,
+) -> Option<T> {
+    let deadline = Instant::now().checked_add(timeout);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(v) = poll(&mut cx) {
+            return Some(v);
+        }
+        match deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return None;
+                }
+                thread::park_timeout(remaining);
+            }
+            None => thread::park(),
+        }
+    }
+}
+
+#[test]
+fn sleep_completes() {
+    use std::time::Duration;
+
+    let start = Instant::now();
+    futures_lite::future::block_on(Sleep::new(start + Duration::from_millis(20)));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+}
+
+#[test]
+fn sleep_drop_unregisters() {
+    use std::time::Duration;
+
+    let deadline = Instant::now() + Duration::from_secs(60);
+    let registered = || {
+        let entries = timer().entries.lock().unwrap();
+        entries.iter().filter(|(_, (d, _))| *d == deadline).count()
+    };
+
+    let waker = futures_lite::future::block_on(poll_fn(|cx| Poll::Ready(cx.waker().clone())));
+    let mut cx = Context::from_waker(&waker);
+    let mut sleep = Sleep::new(deadline);
+    assert!(Pin::new(&mut sleep).poll(&mut cx).is_pending());
+    assert!(Pin::new(&mut sleep).poll(&mut cx).is_pending());
+    assert_eq!(registered(), 1);
+    drop(sleep);
+    assert_eq!(registered(), 0);
+}
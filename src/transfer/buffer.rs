@@ -1,20 +1,65 @@
 use std::{
+    alloc::{self, Layout},
     fmt::Debug,
     mem::{ManuallyDrop, MaybeUninit},
     ops::{Deref, DerefMut},
+    ptr::NonNull,
+    sync::Arc,
 };
 
-#[derive(Copy, Clone)]
 pub(crate) enum Allocator {
     Default,
     #[cfg(any(target_os = "linux", target_os = "android"))]
     Mmap,
+    /// Memory managed by the caller of `Buffer::from_raw_parts`, not freed
+    /// by nusb.
+    External,
+    /// Memory from the system allocator with the contained alignment.
+    Aligned(usize),
+    /// Memory from a user-provided allocator.
+    Custom(Arc<dyn BufferAllocator>),
+    /// Part of a larger buffer, which is freed once it and all of its other
+    /// chunks have been dropped.
+    Chunk(Arc<Buffer>),
 }
 
-/// Buffer for bulk and interrupt transfers.
+/// A source of memory for [`Buffer`]s.
+///
+/// Implement this to route transfer buffers through a memory pool, huge
+/// pages, or other memory managed by the application, and allocate buffers
+/// from it with [`Buffer::new_in`] or a
+/// [`BufferPool`][super::BufferPool] created with
+/// [`BufferPool::new_in`][super::BufferPool::new_in]. The data is transferred
+/// directly to or from the returned memory, without copying it at the API
+/// boundary.
+///
+/// The allocator is kept alive by every buffer allocated from it, and
+/// [`deallocate`][Self::deallocate] is called when the buffer is dropped,
+/// which may be on nusb's event thread.
+///
+/// ### Safety
+/// * `allocate` must return memory that is valid for reads and writes of at
+///   least `len` bytes, and not accessed by anything else until it is passed
+///   to `deallocate`.
+pub unsafe trait BufferAllocator: Send + Sync {
+    /// Allocate `len` bytes, or return `None` if the allocation fails.
+    fn allocate(&self, len: usize) -> Option<NonNull<u8>>;
+
+    /// Free memory returned from [`allocate`][Self::allocate].
+    ///
+    /// ### Safety
+    /// * `ptr` must have been returned from `allocate` on this allocator with
+    ///   the same `len`, and not already deallocated.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, len: usize);
+}
+
+/// Buffer for bulk, interrupt, and isochronous transfers.
 ///
 /// The fixed-capacity buffer can be backed either by the system allocator or a
-/// platform-specific way of allocating memory for zero-copy transfers.
+/// platform-specific way of allocating memory for zero-copy transfers. Use
+/// [`Buffer::new`] for a buffer from the system allocator, or
+/// [`Endpoint::allocate`][`crate::Endpoint::allocate`] for a zero-copy buffer
+/// where supported (currently Linux `usbfs` memory mapped with `mmap`).
 ///
 /// * For OUT transfers, fill the buffer with data prior to submitting it.
 ///   The `len` is the number of initialized bytes which will be sent when
@@ -45,7 +90,7 @@ pub struct Buffer {
 }
 
 impl Buffer {
-    /// Allocate a new bufffer with the default allocator.
+    /// Allocate a new buffer with the default allocator.
     ///
     /// This buffer will not support [zero-copy
     /// transfers][`crate::Endpoint::allocate`], but can be cheaply converted to
@@ -96,6 +141,109 @@ impl Buffer {
         })
     }
 
+    /// Allocate a new buffer from the system allocator, with its start
+    /// aligned to `align` bytes.
+    ///
+    /// Use this with
+    /// [`Endpoint::buffer_alignment`][crate::Endpoint::buffer_alignment] to
+    /// prepare buffers with the alignment the platform prefers. The
+    /// `requested_len` and `capacity` are `requested_len`.
+    ///
+    /// Unlike [`Buffer::new`], [`into_vec`][Self::into_vec] copies the data
+    /// into a new `Vec`.
+    ///
+    /// ### Panics
+    /// * If `align` is not a power of two.
+    /// * If the requested length is greater than `u32::MAX`.
+    pub fn new_aligned(requested_len: usize, align: usize) -> Self {
+        let len_u32 = requested_len.try_into().expect("length overflow");
+        let layout = Self::aligned_layout(requested_len, align);
+        let ptr = unsafe { alloc::alloc(layout) };
+        if ptr.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+        Buffer {
+            ptr,
+            len: 0,
+            requested_len: len_u32,
+            capacity: len_u32,
+            allocator: Allocator::Aligned(align),
+        }
+    }
+
+    /// Allocate a new buffer from a custom allocator.
+    ///
+    /// The passed size is used as both the `requested_len` and `capacity`.
+    /// The buffer is not [zero-copy][Self::is_zero_copy] in the sense of
+    /// `Endpoint::allocate`, and [`into_vec`][Self::into_vec] copies the data
+    /// into a new `Vec`.
+    ///
+    /// ### Panics
+    /// * If the allocator returns `None`.
+    /// * If the requested length is greater than `u32::MAX`.
+    pub fn new_in(requested_len: usize, allocator: Arc<dyn BufferAllocator>) -> Self {
+        let len_u32 = requested_len.try_into().expect("length overflow");
+        let ptr = allocator
+            .allocate(requested_len)
+            .expect("buffer allocation failed");
+        Buffer {
+            ptr: ptr.as_ptr(),
+            len: 0,
+            requested_len: len_u32,
+            capacity: len_u32,
+            allocator: Allocator::Custom(allocator),
+        }
+    }
+
+    fn aligned_layout(capacity: usize, align: usize) -> Layout {
+        // Zero-size allocations are not allowed
+        Layout::from_size_align(capacity.max(1), align).expect("invalid alignment")
+    }
+
+    /// Create a buffer backed by memory managed by the caller.
+    ///
+    /// This allows transferring data directly to or from memory that was
+    /// not allocated by nusb or the system allocator, such as DMA-capable or
+    /// externally pinned memory, without copying it. The memory is not freed
+    /// when the buffer is dropped.
+    ///
+    /// The first `len` bytes are considered initialized, and are sent if the
+    /// buffer is submitted for an OUT transfer. The `requested_len` is set to
+    /// `capacity`.
+    ///
+    /// Such a buffer is not [zero-copy][Self::is_zero_copy] in the sense of
+    /// `Endpoint::allocate`: the OS may still copy the data internally, and
+    /// transfers larger than
+    /// [`max_transfer_size`][crate::Endpoint::max_transfer_size] are copied
+    /// through intermediate buffers. [`into_vec`][Self::into_vec] copies the
+    /// data into a new `Vec`.
+    ///
+    /// ### Safety
+    /// * `ptr` must be valid for reads and writes of `capacity` bytes, and
+    ///   the first `len` bytes must be initialized.
+    /// * The memory must remain valid, and must not be accessed other than
+    ///   through the `Buffer`, until the `Buffer` is dropped. Note that if an
+    ///   endpoint is dropped with transfers pending, their buffers are
+    ///   dropped only once the OS has finished cancelling them, so wait for
+    ///   pending transfers (for example with
+    ///   [`Endpoint::drain`][crate::Endpoint::drain]) before releasing the
+    ///   memory.
+    ///
+    /// ### Panics
+    /// * If `len` is greater than `capacity`, or `capacity` is greater than
+    ///   `u32::MAX`.
+    pub unsafe fn from_raw_parts(ptr: *mut u8, len: usize, capacity: usize) -> Self {
+        assert!(len <= capacity, "length exceeds capacity");
+        let capacity_u32 = capacity.try_into().expect("capacity overflow");
+        Buffer {
+            ptr,
+            len: len as u32,
+            requested_len: capacity_u32,
+            capacity: capacity_u32,
+            allocator: Allocator::External,
+        }
+    }
+
     /// Get the number of initialized bytes in the buffer.
     ///
     /// For OUT transfers, this is the amount of data written to the buffer which will be sent when the buffer is submitted.
@@ -181,7 +329,16 @@ impl Buffer {
 
     /// Returns whether the buffer is specially-allocated for zero-copy IO.
     pub fn is_zero_copy(&self) -> bool {
-        !matches!(self.allocator, Allocator::Default)
+        match &self.allocator {
+            Allocator::Chunk(parent) => parent.is_zero_copy(),
+            allocator => !matches!(
+                allocator,
+                Allocator::Default
+                    | Allocator::External
+                    | Allocator::Aligned(_)
+                    | Allocator::Custom(_)
+            ),
+        }
     }
 
     /// Convert the buffer into a `Vec<u8>`.
@@ -262,7 +419,7 @@ impl Debug for Buffer {
 
 impl Drop for Buffer {
     fn drop(&mut self) {
-        match self.allocator {
+        match &self.allocator {
             Allocator::Default => unsafe {
                 drop(Vec::from_raw_parts(
                     self.ptr,
@@ -274,6 +431,57 @@ impl Drop for Buffer {
             Allocator::Mmap => unsafe {
                 rustix::mm::munmap(self.ptr as *mut _, self.capacity as usize).unwrap();
             },
+            Allocator::External | Allocator::Chunk(_) => {}
+            Allocator::Aligned(align) => unsafe {
+                alloc::dealloc(
+                    self.ptr,
+                    Self::aligned_layout(self.capacity as usize, *align),
+                );
+            },
+            Allocator::Custom(allocator) => unsafe {
+                allocator.deallocate(NonNull::new_unchecked(self.ptr), self.capacity as usize);
+            },
         }
     }
 }
+
+#[test]
+fn aligned() {
+    let mut buf = Buffer::new_aligned(100, 4096);
+    assert_eq!(buf.ptr as usize % 4096, 0);
+    assert_eq!(buf.requested_len(), 100);
+    assert!(!buf.is_zero_copy());
+    buf.extend_from_slice(&[1, 2, 3]);
+    assert_eq!(buf.into_vec(), [1, 2, 3]);
+
+    let buf = Buffer::new_aligned(0, 64);
+    assert_eq!(buf.capacity(), 0);
+}
+
+#[test]
+fn custom_allocator() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct Counting(AtomicUsize);
+
+    unsafe impl BufferAllocator for Counting {
+        fn allocate(&self, len: usize) -> Option<NonNull<u8>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            NonNull::new(unsafe { alloc::alloc(Layout::from_size_align(len, 1).ok()?) })
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, len: usize) {
+            self.0.fetch_sub(1, Ordering::SeqCst);
+            alloc::dealloc(ptr.as_ptr(), Layout::from_size_align(len, 1).unwrap());
+        }
+    }
+
+    let allocator = Arc::new(Counting::default());
+    let mut buf = Buffer::new_in(16, allocator.clone());
+    assert_eq!(allocator.0.load(Ordering::SeqCst), 1);
+    buf.extend_from_slice(&[1, 2, 3]);
+    assert_eq!(&buf[..], [1, 2, 3]);
+    drop(buf);
+    assert_eq!(allocator.0.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,169 @@
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Weak,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+use super::{Buffer, BulkOrInterrupt, Completion, EndpointDirection, TransferId};
+use crate::Endpoint;
+
+type Callback<EpType, Dir> = Box<dyn FnMut(&mut Endpoint<EpType, Dir>, Completion) + Send>;
+
+/// An [`Endpoint`] that delivers completions to a callback instead of
+/// through `next_complete`.
+///
+/// Create a `CallbackEndpoint` with
+/// [`Endpoint::on_complete`][crate::Endpoint::on_complete]. The callback is
+/// invoked with each [`Completion`], in order, on the thread that handles the
+/// completion, which is usually nusb's event thread. This allows using the
+/// endpoint from code that does not use async, without dedicating a thread to
+/// waiting for completions.
+///
+/// The callback is also passed the endpoint, so it can resubmit a buffer to
+/// keep a stream of transfers going. While the callback runs, other calls on
+/// the `CallbackEndpoint` block, and other endpoints on the same device may
+/// not be completed, so the callback should return quickly and must not
+/// block.
+///
+/// Dropping the `CallbackEndpoint` cancels all pending transfers, like
+/// dropping an `Endpoint`. Use [`into_inner`][Self::into_inner] to stop
+/// calling the callback and return to waiting for completions instead.
+pub struct CallbackEndpoint<EpType: BulkOrInterrupt + 'static, Dir: EndpointDirection + 'static> {
+    shared: Arc<Shared<EpType, Dir>>,
+}
+
+struct Shared<EpType: BulkOrInterrupt, Dir: EndpointDirection> {
+    state: Mutex<Option<State<EpType, Dir>>>,
+
+    /// Set when completions may be ready, so that whoever holds the lock
+    /// dispatches them before returning.
+    dirty: AtomicBool,
+}
+
+struct State<EpType: BulkOrInterrupt, Dir: EndpointDirection> {
+    endpoint: Endpoint<EpType, Dir>,
+    callback: Callback<EpType, Dir>,
+}
+
+impl<EpType: BulkOrInterrupt + 'static, Dir: EndpointDirection + 'static>
+    CallbackEndpoint<EpType, Dir>
+{
+    pub(crate) fn new(endpoint: Endpoint<EpType, Dir>, callback: Callback<EpType, Dir>) -> Self {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(None),
+            dirty: AtomicBool::new(false),
+        });
+
+        let weak: Weak<Shared<EpType, Dir>> = Arc::downgrade(&shared);
+        endpoint.set_notify_callback(Some(Arc::new(move || {
+            if let Some(shared) = weak.upgrade() {
+                shared.dispatch();
+            }
+        })));
+        *shared.state.lock().unwrap() = Some(State { endpoint, callback });
+
+        // Transfers may have completed before the callback was registered.
+        shared.dispatch();
+        CallbackEndpoint { shared }
+    }
+
+    /// Submit a new transfer on the endpoint.
+    ///
+    /// See [`Endpoint::submit`][crate::Endpoint::submit].
+    pub fn submit(&self, buf: Buffer) -> TransferId {
+        self.with_endpoint(|ep| ep.submit(buf))
+    }
+
+    /// Request cancellation of all pending transfers.
+    ///
+    /// The cancelled transfers are still passed to the callback.
+    pub fn cancel_all(&self) {
+        self.with_endpoint(|ep| ep.cancel_all())
+    }
+
+    /// Get the number of transfers that have been submitted but not yet
+    /// passed to the callback.
+    pub fn pending(&self) -> usize {
+        self.with_endpoint(|ep| ep.pending())
+    }
+
+    /// Run a function with exclusive access to the endpoint.
+    ///
+    /// Completions are not passed to the callback while `f` runs, and are
+    /// dispatched once it returns. Completions taken by `f` with
+    /// `next_complete` or similar are not passed to the callback.
+    pub fn with_endpoint<R>(&self, f: impl FnOnce(&mut Endpoint<EpType, Dir>) -> R) -> R {
+        let res = {
+            let mut state = self.shared.state.lock().unwrap();
+            f(&mut state.as_mut().unwrap().endpoint)
+        };
+        self.shared.dispatch();
+        res
+    }
+
+    /// Stop calling the callback, and return the underlying [`Endpoint`].
+    ///
+    /// Pending transfers are not cancelled, and can be waited for with
+    /// `next_complete`. If the callback is running on another thread, this
+    /// blocks until it returns.
+    pub fn into_inner(self) -> Endpoint<EpType, Dir> {
+        let state = self.shared.state.lock().unwrap().take().unwrap();
+        state.endpoint.set_notify_callback(None);
+        state.endpoint
+    }
+}
+
+impl<EpType: BulkOrInterrupt, Dir: EndpointDirection> Shared<EpType, Dir> {
+    fn dispatch(&self) {
+        self.dirty.store(true, Ordering::SeqCst);
+        while self.dirty.load(Ordering::SeqCst) {
+            // If another thread holds the lock, it will see `dirty` and
+            // dispatch the completion after releasing it.
+            let Ok(mut guard) = self.state.try_lock() else {
+                return;
+            };
+            self.dirty.store(false, Ordering::SeqCst);
+            let Some(state) = guard.as_mut() else {
+                return;
+            };
+
+            let waker = Waker::from(Arc::new(NoopWaker));
+            let mut cx = Context::from_waker(&waker);
+            while state.endpoint.ready() > 0 {
+                let Poll::Ready(c) = state.endpoint.poll_next_complete(&mut cx) else {
+                    break;
+                };
+                (state.callback)(&mut state.endpoint, c);
+            }
+        }
+    }
+}
+
+impl<EpType: BulkOrInterrupt + 'static, Dir: EndpointDirection + 'static> Drop
+    for CallbackEndpoint<EpType, Dir>
+{
+    fn drop(&mut self) {
+        let state = self.shared.state.lock().unwrap().take();
+        if let Some(state) = state {
+            state.endpoint.set_notify_callback(None);
+        }
+    }
+}
+
+impl<EpType: BulkOrInterrupt + 'static, Dir: EndpointDirection + 'static> Debug
+    for CallbackEndpoint<EpType, Dir>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallbackEndpoint").finish_non_exhaustive()
+    }
+}
+
+/// Waker for polling completions that are known to be ready.
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
@@ -0,0 +1,30 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// An external signal that requests cancellation of an endpoint's transfers.
+///
+/// Set with
+/// [`Endpoint::set_cancel_signal`][crate::Endpoint::set_cancel_signal]. Once
+/// the signal fires, all pending transfers on the endpoint are cancelled and
+/// any further submissions complete immediately with
+/// [`TransferError::Cancelled`][super::TransferError::Cancelled].
+///
+/// This is implemented for any `Unpin` future resolving to `()`, so a
+/// `tokio_util::sync::CancellationToken` can be used as a signal with
+/// `Box::pin(token.cancelled_owned())`.
+pub trait CancelSignal: Send {
+    /// Poll whether cancellation has been requested.
+    ///
+    /// Returns `Poll::Ready(())` once cancellation has been requested, or
+    /// arranges for the context's waker to be notified when it is.
+    fn poll_cancelled(&mut self, cx: &mut Context<'_>) -> Poll<()>;
+}
+
+impl<F: Future<Output = ()> + Send + Unpin> CancelSignal for F {
+    fn poll_cancelled(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        Pin::new(self).poll(cx)
+    }
+}
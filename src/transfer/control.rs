@@ -150,6 +150,111 @@ impl ControlIn {
     }
 }
 
+/// A standard feature that is enabled with a `SET_FEATURE` request and
+/// disabled with a `CLEAR_FEATURE` request.
+///
+/// Use [`set`][Self::set] or [`clear`][Self::clear] to build the request, and
+/// submit it with `control_out` on a [`Device`][crate::Device] or
+/// [`Interface`][crate::Interface], or use
+/// [`Interface::set_feature`][crate::Interface::set_feature].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Feature {
+    /// `ENDPOINT_HALT` feature of the endpoint with the given address.
+    ///
+    /// Prefer [`Endpoint::clear_halt`][crate::Endpoint::clear_halt] to clear
+    /// the halt condition, as it also resets the host-side state.
+    EndpointHalt(u8),
+
+    /// `FUNCTION_SUSPEND` feature of the function whose first interface is
+    /// `interface` (SuperSpeed only).
+    ///
+    /// Bit 0 of `options` requests suspend, and bit 1 enables function remote
+    /// wake.
+    FunctionSuspend {
+        /// First interface of the function.
+        interface: u8,
+        /// Suspend options.
+        options: u8,
+    },
+
+    /// `DEVICE_REMOTE_WAKEUP` feature of the device.
+    DeviceRemoteWakeup,
+
+    /// `TEST_MODE` feature of the device, with the given test selector.
+    ///
+    /// This can only be set, not cleared, and the device must be reset or
+    /// power cycled to leave the test mode.
+    TestMode(u8),
+
+    /// `U1_ENABLE` feature of the device (SuperSpeed only).
+    U1Enable,
+
+    /// `U2_ENABLE` feature of the device (SuperSpeed only).
+    U2Enable,
+
+    /// `LTM_ENABLE` feature of the device (SuperSpeed only).
+    LtmEnable,
+}
+
+impl Feature {
+    const SET_FEATURE: u8 = 0x03;
+    const CLEAR_FEATURE: u8 = 0x01;
+
+    /// Get the recipient of requests for this feature.
+    pub fn recipient(&self) -> Recipient {
+        match self {
+            Feature::EndpointHalt(_) => Recipient::Endpoint,
+            Feature::FunctionSuspend { .. } => Recipient::Interface,
+            _ => Recipient::Device,
+        }
+    }
+
+    /// Get the feature selector sent in the `wValue` field.
+    pub fn selector(&self) -> u16 {
+        match self {
+            Feature::EndpointHalt(_) | Feature::FunctionSuspend { .. } => 0,
+            Feature::DeviceRemoteWakeup => 1,
+            Feature::TestMode(_) => 2,
+            Feature::U1Enable => 48,
+            Feature::U2Enable => 49,
+            Feature::LtmEnable => 50,
+        }
+    }
+
+    fn index(&self) -> u16 {
+        match *self {
+            Feature::EndpointHalt(address) => address as u16,
+            Feature::FunctionSuspend { interface, options } => {
+                ((options as u16) << 8) | interface as u16
+            }
+            Feature::TestMode(selector) => (selector as u16) << 8,
+            _ => 0,
+        }
+    }
+
+    fn request(&self, request: u8) -> ControlOut<'static> {
+        ControlOut {
+            control_type: ControlType::Standard,
+            recipient: self.recipient(),
+            request,
+            value: self.selector(),
+            index: self.index(),
+            data: &[],
+        }
+    }
+
+    /// Build a `SET_FEATURE` request for this feature.
+    pub fn set(&self) -> ControlOut<'static> {
+        self.request(Self::SET_FEATURE)
+    }
+
+    /// Build a `CLEAR_FEATURE` request for this feature.
+    pub fn clear(&self) -> ControlOut<'static> {
+        self.request(Self::CLEAR_FEATURE)
+    }
+}
+
 pub(crate) const SETUP_PACKET_SIZE: usize = 8;
 
 fn pack_setup(
@@ -182,3 +287,28 @@ pub(crate) fn request_type(
 ) -> u8 {
     (direction as u8) | ((control_type as u8) << 5) | (recipient as u8)
 }
+
+#[test]
+fn feature_requests() {
+    assert_eq!(
+        Feature::DeviceRemoteWakeup.set().setup_packet(),
+        [0x00, 0x03, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(
+        Feature::EndpointHalt(0x81).clear().setup_packet(),
+        [0x02, 0x01, 0x00, 0x00, 0x81, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(
+        Feature::FunctionSuspend {
+            interface: 2,
+            options: 0x01
+        }
+        .set()
+        .setup_packet(),
+        [0x01, 0x03, 0x00, 0x00, 0x02, 0x01, 0x00, 0x00]
+    );
+    assert_eq!(
+        Feature::TestMode(4).set().setup_packet(),
+        [0x00, 0x03, 0x02, 0x00, 0x00, 0x04, 0x00, 0x00]
+    );
+}
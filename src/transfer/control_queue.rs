@@ -0,0 +1,148 @@
+use std::{
+    collections::VecDeque,
+    future::{poll_fn, Future, IntoFuture},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use super::{ControlIn, ControlOut, TransferError};
+use crate::{
+    timer::{block_on_timeout, with_timeout},
+    Interface,
+};
+
+type ControlFuture = Pin<Box<dyn Future<Output = Result<Vec<u8>, TransferError>> + Send>>;
+
+/// A queue of control transfers on the default control endpoint.
+///
+/// Obtain a `ControlQueue` with [`Interface::control_queue`].
+///
+/// Like an [`Endpoint`][crate::Endpoint], submitting a transfer adds it to the
+/// queue without waiting for it to complete, and completed transfers are
+/// returned in the order they were submitted. This allows a sequence of
+/// control requests, such as the writes of a firmware update, to be pipelined
+/// with the OS instead of awaiting each request before submitting the next.
+///
+/// Each completion is a `Result` containing the data received for an IN
+/// transfer, or an empty `Vec` for an OUT transfer.
+///
+/// When the `ControlQueue` is dropped, pending transfers are not cancelled,
+/// but their results are discarded.
+pub struct ControlQueue {
+    interface: Interface,
+    pending: VecDeque<ControlFuture>,
+}
+
+impl ControlQueue {
+    pub(crate) fn new(interface: Interface) -> Self {
+        ControlQueue {
+            interface,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Get the number of transfers that have been submitted and not yet
+    /// returned from `next_complete`.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Begin an **IN (device-to-host)** control transfer.
+    ///
+    /// See [`Interface::control_in`] for details and platform limitations.
+    pub fn submit_in(&mut self, data: ControlIn, timeout: Duration) {
+        let fut = self.interface.control_in(data, timeout).into_future();
+        self.pending.push_back(Box::pin(fut));
+    }
+
+    /// Begin an **OUT (host-to-device)** control transfer.
+    ///
+    /// The data is copied, so the buffer passed in `data` can be reused
+    /// immediately. See [`Interface::control_out`] for details and platform
+    /// limitations.
+    pub fn submit_out(&mut self, data: ControlOut, timeout: Duration) {
+        let fut = self.interface.control_out(data, timeout).into_future();
+        self.pending
+            .push_back(Box::pin(async move { fut.await.map(|()| Vec::new()) }));
+    }
+
+    /// Return a `Future` that waits for the next pending transfer to complete.
+    ///
+    /// This future is cancel-safe: it can be cancelled and re-created without
+    /// side effects.
+    ///
+    /// ## Panics
+    /// * if there are no transfers pending (that is, if [`Self::pending()`]
+    ///   would return 0).
+    pub fn next_complete(
+        &mut self,
+    ) -> impl Future<Output = Result<Vec<u8>, TransferError>> + Send + '_ {
+        poll_fn(|cx| self.poll_next_complete(cx))
+    }
+
+    /// Return a `Future` that waits up to `timeout` for the next pending
+    /// transfer to complete.
+    ///
+    /// Resolves to `None` if the timeout is reached. The transfer is not
+    /// cancelled after the timeout, and can still be returned from a
+    /// subsequent call.
+    ///
+    /// ## Panics
+    /// * if there are no transfers pending (that is, if [`Self::pending()`]
+    ///   would return 0).
+    pub fn next_complete_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> impl Future<Output = Option<Result<Vec<u8>, TransferError>>> + Send + '_ {
+        with_timeout(timeout, move |cx| self.poll_next_complete(cx))
+    }
+
+    /// Poll for a pending transfer completion.
+    ///
+    /// Returns a completed transfer if one is available, or arranges for the
+    /// context's waker to be notified when a transfer completes.
+    ///
+    /// ## Panics
+    ///  * if there are no transfers pending (that is, if [`Self::pending()`]
+    ///    would return 0).
+    pub fn poll_next_complete(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Vec<u8>, TransferError>> {
+        let fut = self
+            .pending
+            .front_mut()
+            .expect("no control transfer pending");
+        let res = fut.as_mut().poll(cx);
+        if res.is_ready() {
+            self.pending.pop_front();
+        }
+        res
+    }
+
+    /// Wait for a pending transfer completion.
+    ///
+    /// Blocks for up to `timeout` waiting for a transfer to complete, or
+    /// returns `None` if the timeout is reached. The transfer is not cancelled
+    /// after the timeout, and can still be returned from a subsequent call.
+    ///
+    /// ## Panics
+    ///  * if there are no transfers pending (that is, if [`Self::pending()`]
+    ///    would return 0).
+    pub fn wait_next_complete(
+        &mut self,
+        timeout: Duration,
+    ) -> Option<Result<Vec<u8>, TransferError>> {
+        block_on_timeout(timeout, |cx| self.poll_next_complete(cx))
+    }
+}
+
+impl std::fmt::Debug for ControlQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ControlQueue")
+            .field("interface", &self.interface)
+            .field("pending", &self.pending.len())
+            .finish()
+    }
+}
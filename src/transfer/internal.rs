@@ -6,7 +6,7 @@ use std::{
     pin::Pin,
     ptr::{addr_of_mut, NonNull},
     sync::{
-        atomic::{AtomicU8, Ordering},
+        atomic::{AtomicBool, AtomicU8, Ordering},
         Arc, Mutex,
     },
     task::{Context, Poll, Waker},
@@ -18,6 +18,7 @@ use crate::MaybeFuture;
 
 pub struct Notify {
     state: Mutex<NotifyState>,
+    callback: Mutex<Option<Arc<dyn Fn() + Send + Sync>>>,
 }
 
 pub enum NotifyState {
@@ -36,9 +37,16 @@ impl Notify {
     pub fn new() -> Self {
         Self {
             state: Mutex::new(NotifyState::None),
+            callback: Mutex::new(None),
         }
     }
 
+    /// Set a function called on every notification, in addition to waking
+    /// the subscribed waker or thread.
+    pub fn set_callback(&self, callback: Option<Arc<dyn Fn() + Send + Sync>>) {
+        *self.callback.lock().unwrap() = callback;
+    }
+
     pub fn subscribe(&self, cx: &mut Context) {
         *self.state.lock().unwrap() = NotifyState::Waker(cx.waker().clone());
     }
@@ -75,6 +83,69 @@ impl Notify {
             NotifyState::Waker(waker) => waker.wake_by_ref(),
             NotifyState::Thread(thread) => thread.unpark(),
         }
+        let callback = self.callback.lock().unwrap().clone();
+        if let Some(callback) = callback {
+            callback();
+        }
+    }
+}
+
+/// Notifies waiters when the object containing it is dropped.
+///
+/// Used by `wait_closed` to wait for a device or interface to be released. Fields
+/// are dropped in declaration order, so this must be the last field so that
+/// the OS handles owned by the other fields are released first.
+#[derive(Default)]
+pub(crate) struct CloseNotify {
+    waiters: Mutex<Vec<Arc<CloseWaiter>>>,
+}
+
+struct CloseWaiter {
+    closed: AtomicBool,
+    notify: Notify,
+}
+
+impl CloseNotify {
+    /// Get a `MaybeFuture` that completes once this is dropped.
+    pub fn closed(&self) -> Closed {
+        let waiter = Arc::new(CloseWaiter {
+            closed: AtomicBool::new(false),
+            notify: Notify::new(),
+        });
+        self.waiters.lock().unwrap().push(waiter.clone());
+        Closed(waiter)
+    }
+}
+
+impl Drop for CloseNotify {
+    fn drop(&mut self) {
+        for waiter in self.waiters.get_mut().unwrap().drain(..) {
+            waiter.closed.store(true, Ordering::Release);
+            waiter.notify.notify();
+        }
+    }
+}
+
+pub(crate) struct Closed(Arc<CloseWaiter>);
+
+impl Closed {
+    fn check(&self) -> Option<()> {
+        self.0.closed.load(Ordering::Acquire).then_some(())
+    }
+}
+
+impl Future for Closed {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        self.0.notify.subscribe(cx);
+        self.check().map_or(Poll::Pending, Poll::Ready)
+    }
+}
+
+impl MaybeFuture for Closed {
+    fn wait(self) -> Self::Output {
+        self.0.notify.wait(|| self.check())
     }
 }
 
@@ -89,6 +160,12 @@ struct TransferInner<P> {
 
     /// Object notified when transfer completes.
     notify: Arc<dyn AsRef<Notify> + Send + Sync>,
+
+    /// Whether to record `completed_at`.
+    timestamps: bool,
+
+    /// Time at which the completion was handled by nusb.
+    completed_at: Option<Instant>,
 }
 
 /// Either the transfer has not yet been submitted, or it has been completed.
@@ -114,6 +191,8 @@ impl<P> Idle<P> {
             platform_data: inner,
             state: AtomicU8::new(STATE_IDLE),
             notify,
+            timestamps: false,
+            completed_at: None,
         }))
     }
 
@@ -129,11 +208,23 @@ impl<P> Idle<P> {
         }
     }
 
-    pub(crate) fn simulate_complete(self) -> Pending<P> {
+    /// Set whether the time of the transfer's completion is recorded.
+    pub(crate) fn set_timestamps(&mut self, enabled: bool) {
+        self.0.timestamps = enabled;
+        self.0.completed_at = None;
+    }
+
+    pub(crate) fn simulate_complete(mut self) -> Pending<P> {
+        self.0.completed_at = self.0.timestamps.then(Instant::now);
         Pending {
             ptr: unsafe { NonNull::new_unchecked(Box::into_raw(self.0)) },
         }
     }
+
+    /// Time at which the last completion of this transfer was handled.
+    pub(crate) fn completed_at(&self) -> Option<Instant> {
+        self.0.completed_at
+    }
 }
 
 impl<P> Deref for Idle<P> {
@@ -222,6 +313,9 @@ impl<P> Drop for Pending<P> {
 pub(crate) unsafe fn notify_completion<P>(transfer: *mut P) {
     unsafe {
         let transfer = transfer as *mut TransferInner<P>;
+        if *addr_of_mut!((*transfer).timestamps) {
+            *addr_of_mut!((*transfer).completed_at) = Some(Instant::now());
+        }
         let notify = (*transfer).notify.clone();
         match (*transfer).state.swap(STATE_IDLE, Ordering::AcqRel) {
             STATE_PENDING => (*notify).as_ref().notify(),
@@ -267,3 +361,19 @@ where
             .wait(|| take_completed_from_option(&mut self.transfer))
     }
 }
+
+#[test]
+fn close_notify() {
+    let notify = CloseNotify::default();
+    let closed = notify.closed();
+    let t = thread::spawn(move || closed.wait());
+    drop(notify);
+    t.join().unwrap();
+
+    let notify = CloseNotify::default();
+    let mut closed = std::pin::pin!(notify.closed());
+    let mut poll = |cx: &mut Context| closed.as_mut().poll(cx);
+    assert!(crate::timer::block_on_timeout(Duration::ZERO, &mut poll).is_none());
+    drop(notify);
+    assert!(crate::timer::block_on_timeout(Duration::ZERO, &mut poll).is_some());
+}
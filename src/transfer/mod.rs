@@ -3,27 +3,80 @@
 //! Use the methods on an [`Interface`][`super::Interface`] and
 //! [`Endpoint`][`super::Endpoint`] to perform transfers.
 
-use std::{fmt::Display, io};
+use std::{fmt::Display, io, time::Instant};
 
 mod control;
 #[allow(unused)]
 pub(crate) use control::{request_type, SETUP_PACKET_SIZE};
-pub use control::{ControlIn, ControlOut, ControlType, Direction, Recipient};
+pub use control::{ControlIn, ControlOut, ControlType, Direction, Feature, Recipient};
 
 mod buffer;
 pub(crate) use buffer::Allocator;
-pub use buffer::Buffer;
+pub use buffer::{Buffer, BufferAllocator};
+
+mod pool;
+pub use pool::BufferPool;
+
+mod cancel;
+pub use cancel::CancelSignal;
+
+mod callback;
+pub use callback::CallbackEndpoint;
+
+mod retry;
+pub use retry::RetryPolicy;
+
+mod control_queue;
+pub use control_queue::ControlQueue;
+
+mod submitter;
+pub(crate) use submitter::SubmitReceiver;
+pub use submitter::Submitter;
+
+mod stats;
+pub(crate) use stats::StatsCollector;
+pub use stats::TransferStats;
 
 pub(crate) mod internal;
 
 use crate::{descriptors::TransferType, platform};
 
+/// Identifies a transfer submitted on an [`Endpoint`][crate::Endpoint].
+///
+/// Returned from `submit` and used with
+/// [`Endpoint::cancel`][crate::Endpoint::cancel].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TransferId(pub(crate) u64);
+
 /// Transfer error.
+///
+/// The OS error code is preserved for [`Fault`][Self::Fault] and
+/// [`Unknown`][Self::Unknown], and is available from
+/// [`os_error`][Self::os_error]. Other variants correspond to specific codes:
+///
+/// | Variant        | Linux (`errno`)                  | Windows (`WIN32_ERROR`)                      | macOS (`IOReturn`)          |
+/// |----------------|----------------------------------|----------------------------------------------|-----------------------------|
+/// | `Cancelled`    | `ENOENT`, `ECONNRESET`           | `ERROR_OPERATION_ABORTED`, `ERROR_REQUEST_ABORTED` | `kIOReturnAborted`    |
+/// | `TimedOut`     | `ETIMEDOUT`                      | `ERROR_SEM_TIMEOUT`, `ERROR_TIMEOUT`         | `kIOUSBTransactionTimeout`  |
+/// | `Stall`        | `EPIPE`                          | `ERROR_GEN_FAILURE`                          | `kIOUSBPipeStalled`         |
+/// | `Disconnected` | `ENODEV`, `ESHUTDOWN`            | `ERROR_DEVICE_NOT_CONNECTED`, `ERROR_NO_SUCH_DEVICE`, `ERROR_FILE_NOT_FOUND` | `kIOReturnNoDevice` |
+/// | `Overflow`     | `EOVERFLOW`                      |                                              | `kIOReturnOverrun`          |
+/// | `Fault`        | `EPROTO`, `EILSEQ`, `ECOMM`, `ETIME` | `ERROR_CRC`, `ERROR_IO_DEVICE`           | `kIOReturnNotResponding`, `kIOReturnIOError`, `kIOReturnDMAError`, `kIOUSBHighSpeedSplitError` |
+/// | `InvalidArgument` | `EINVAL`                      |                                              | `kIOReturnBadArgument`      |
+///
+/// `ShortPacket` is not an OS error, and is produced by nusb according to the
+/// endpoint's [`ShortPacketPolicy`].
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum TransferError {
-    /// Transfer was cancelled or timed out.
+    /// Transfer was cancelled.
     Cancelled,
 
+    /// Transfer did not complete before its timeout expired and was cancelled.
+    ///
+    /// Any data transferred before the timeout is still available in the
+    /// returned buffer.
+    TimedOut,
+
     /// Endpoint in a STALL condition.
     ///
     /// This is used by the device to signal that an error occurred. For bulk
@@ -36,7 +89,28 @@ pub enum TransferError {
     /// Device disconnected.
     Disconnected,
 
-    /// Hardware issue or protocol violation.
+    /// IN transfer ended with a short packet.
+    ///
+    /// This is only returned when the endpoint's policy is
+    /// [`ShortPacketPolicy::Error`]. The data received up to and including the
+    /// short packet is still available in the returned buffer.
+    ShortPacket,
+
+    /// The device sent more data than requested (babble).
+    ///
+    /// The host controller detected a packet longer than expected: larger
+    /// than the endpoint's maximum packet size, or, for a control transfer,
+    /// past the `length` of the request. This points to a device whose
+    /// firmware doesn't match its descriptors, or a device transmitting out
+    /// of turn. Unlike a [`Fault`][Self::Fault], this is unlikely to succeed
+    /// if the same transfer is retried, and the endpoint may be halted.
+    Overflow,
+
+    /// Hardware issue or protocol violation, such as a CRC error or a device
+    /// that did not respond.
+    ///
+    /// Contains the OS-specific error code. Faults may be caused by noise on
+    /// the link, and retrying the transfer may succeed.
     Fault(u32),
 
     /// The request has an invalid argument or is not supported by this OS.
@@ -55,9 +129,16 @@ impl Display for TransferError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             TransferError::Cancelled => write!(f, "transfer was cancelled"),
+            TransferError::TimedOut => write!(f, "transfer timed out"),
             TransferError::Stall => write!(f, "endpoint stalled"),
             TransferError::Disconnected => write!(f, "device disconnected"),
-            TransferError::Fault(errno) => write!(f, "hardware fault or protocol violation (errno {errno})"),
+            TransferError::ShortPacket => write!(f, "transfer ended with a short packet"),
+            TransferError::Overflow => write!(f, "device sent more data than requested"),
+            TransferError::Fault(e) => {
+                write!(f, "hardware fault or protocol violation (")?;
+                platform::format_os_error_code(f, *e)?;
+                write!(f, ")")
+            }
             TransferError::InvalidArgument => write!(f, "invalid or unsupported argument"),
             TransferError::Unknown(e) => {
                 write!(f, "unknown (")?;
@@ -68,21 +149,202 @@ impl Display for TransferError {
     }
 }
 
+impl TransferError {
+    /// Get the error code from the OS, if the variant preserves it.
+    ///
+    /// * On Linux this is the `errno` value.
+    /// * On Windows this is the `WIN32_ERROR` value.
+    /// * On macOS this is the `IOReturn` value.
+    pub fn os_error(&self) -> Option<u32> {
+        match *self {
+            TransferError::Fault(code) | TransferError::Unknown(code) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if retrying the transfer may succeed.
+    ///
+    /// This is the case for [`Fault`][Self::Fault], such as a CRC error
+    /// caused by noise on the link, and [`TimedOut`][Self::TimedOut]. This is
+    /// the default condition for retrying in a [`RetryPolicy`].
+    pub fn is_transient(&self) -> bool {
+        matches!(self, TransferError::Fault(_) | TransferError::TimedOut)
+    }
+}
+
 impl std::error::Error for TransferError {}
 
+/// Convert a `TransferError` to an [`io::Error`] with the closest matching
+/// [`io::ErrorKind`], so that code using the [`io`][crate::io] adapters can
+/// handle errors by kind without downcasting.
+///
+/// | Variant        | `io::ErrorKind`     |
+/// |----------------|---------------------|
+/// | `Cancelled`    | `Interrupted`       |
+/// | `TimedOut`     | `TimedOut`          |
+/// | `Stall`        | `BrokenPipe`        |
+/// | `Disconnected` | `NotConnected`      |
+/// | `ShortPacket`  | `UnexpectedEof`     |
+/// | `Overflow`     | `InvalidData`       |
+/// | `Fault`        | `Other`             |
+/// | `InvalidArgument` | `InvalidInput`   |
+/// | `Unknown`      | kind of the OS error code on Linux and Windows, otherwise `Other` |
+///
+/// The `TransferError` is preserved as the error's inner value.
 impl From<TransferError> for io::Error {
     fn from(value: TransferError) -> Self {
-        match value {
-            TransferError::Cancelled => io::Error::new(io::ErrorKind::Interrupted, value),
-            TransferError::Stall => io::Error::new(io::ErrorKind::ConnectionReset, value),
-            TransferError::Disconnected => io::Error::new(io::ErrorKind::ConnectionAborted, value),
-            TransferError::Fault(_) => io::Error::other(value),
-            TransferError::InvalidArgument => io::Error::new(io::ErrorKind::InvalidInput, value),
-            TransferError::Unknown(_) => io::Error::other(value),
-        }
+        let kind = match value {
+            TransferError::Cancelled => io::ErrorKind::Interrupted,
+            TransferError::TimedOut => io::ErrorKind::TimedOut,
+            TransferError::Stall => io::ErrorKind::BrokenPipe,
+            TransferError::Disconnected => io::ErrorKind::NotConnected,
+            TransferError::ShortPacket => io::ErrorKind::UnexpectedEof,
+            TransferError::Overflow => io::ErrorKind::InvalidData,
+            TransferError::Fault(_) => io::ErrorKind::Other,
+            TransferError::InvalidArgument => io::ErrorKind::InvalidInput,
+            #[cfg(any(target_os = "linux", target_os = "android", target_os = "windows"))]
+            TransferError::Unknown(code) => io::Error::from_raw_os_error(code as i32).kind(),
+            #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "windows")))]
+            TransferError::Unknown(_) => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, value)
     }
 }
 
+/// Error from a helper that performs multiple transfers, such as
+/// [`Endpoint::read_exact`][crate::Endpoint::read_exact], with the progress
+/// made before the error.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PartialTransferError {
+    /// The error that ended the operation.
+    pub error: TransferError,
+
+    /// Number of bytes transferred successfully before the error.
+    pub transferred: usize,
+}
+
+impl Display for PartialTransferError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} after {} bytes", self.error, self.transferred)
+    }
+}
+
+impl std::error::Error for PartialTransferError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<PartialTransferError> for io::Error {
+    fn from(value: PartialTransferError) -> Self {
+        let kind = io::Error::from(value.error).kind();
+        io::Error::new(kind, value)
+    }
+}
+
+/// A [`TransferError`] with the endpoint and length of the failed transfer.
+///
+/// Returned by [`Completion::into_endpoint_result`] to keep track of where
+/// an error came from when it is propagated from code that uses several
+/// endpoints.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EndpointError {
+    /// The error that ended the transfer.
+    pub error: TransferError,
+
+    /// Address of the endpoint, including the direction bit.
+    pub endpoint: u8,
+
+    /// Requested length of an IN transfer, or length of an OUT transfer.
+    pub requested_len: usize,
+
+    /// Number of bytes transferred before the error.
+    pub actual_len: usize,
+}
+
+impl EndpointError {
+    /// Get the direction of the endpoint.
+    pub fn direction(&self) -> Direction {
+        Direction::from_address(self.endpoint)
+    }
+}
+
+impl Display for EndpointError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} on endpoint 0x{:02x} ({:?}) after {} of {} bytes",
+            self.error,
+            self.endpoint,
+            self.direction(),
+            self.actual_len,
+            self.requested_len
+        )
+    }
+}
+
+impl std::error::Error for EndpointError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<EndpointError> for io::Error {
+    fn from(value: EndpointError) -> Self {
+        let kind = io::Error::from(value.error).kind();
+        io::Error::new(kind, value)
+    }
+}
+
+/// Scheduling hint for the transfers on an endpoint.
+///
+/// Set with [`Endpoint::set_priority`][crate::Endpoint::set_priority]. The
+/// order only applies among completions that nusb handles from the OS
+/// together, and currently only on Linux; see the platform-specific notes
+/// of `set_priority`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum TransferPriority {
+    /// Latency-sensitive traffic, such as commands and responses.
+    ///
+    /// Completions are delivered before those of `Normal` and `Throughput`
+    /// endpoints that completed at the same time.
+    Latency,
+
+    /// Default priority.
+    #[default]
+    Normal,
+
+    /// Throughput-oriented traffic, such as a deep queue of streaming
+    /// transfers.
+    ///
+    /// Completions are delivered after those of `Latency` and `Normal`
+    /// endpoints that completed at the same time, which keeps a busy stream from delaying
+    /// interactive traffic on the same device.
+    Throughput,
+}
+
+/// How a short packet affects an IN transfer on a Bulk or Interrupt endpoint.
+///
+/// A packet shorter than the endpoint's maximum packet size ends an IN
+/// transfer, even if less than the buffer's `requested_len` was received. Set
+/// with [`Endpoint::set_short_packet_policy`][crate::Endpoint::set_short_packet_policy].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ShortPacketPolicy {
+    /// A short packet completes the transfer successfully (default).
+    #[default]
+    Complete,
+
+    /// A short packet completes the transfer with
+    /// [`TransferError::ShortPacket`], and ends a multi-buffer transfer
+    /// submitted with
+    /// [`Endpoint::submit_vectored`][crate::Endpoint::submit_vectored]: its
+    /// remaining buffers are cancelled.
+    ///
+    /// Use this when the protocol expects every transfer to fill its buffer,
+    /// and a short packet indicates the device ended a message early.
+    Error,
+}
+
 mod private {
     pub trait Sealed {}
 }
@@ -132,6 +394,13 @@ impl EndpointType for Interrupt {
 }
 impl BulkOrInterrupt for Interrupt {}
 
+/// Type-level endpoint type: Isochronous
+pub enum Isochronous {}
+impl private::Sealed for Isochronous {}
+impl EndpointType for Isochronous {
+    const TYPE: TransferType = TransferType::Isochronous;
+}
+
 /// A completed transfer returned from [`Endpoint::next_complete`][`crate::Endpoint::next_complete`].
 ///
 /// A transfer can partially complete even in the case of failure or
@@ -147,6 +416,41 @@ pub struct Completion {
 
     /// Status of the transfer.
     pub status: Result<(), TransferError>,
+
+    /// Tag passed to
+    /// [`Endpoint::submit_tagged`][`crate::Endpoint::submit_tagged`], or 0
+    /// for a transfer submitted without a tag.
+    pub tag: u64,
+
+    /// Address of the endpoint the transfer was submitted on, including the
+    /// direction bit.
+    pub endpoint: u8,
+
+    /// Time at which the transfer was submitted, if timestamps are enabled
+    /// with [`Endpoint::set_timestamps`][`crate::Endpoint::set_timestamps`].
+    pub submitted_at: Option<Instant>,
+
+    /// Time at which nusb handled the transfer's completion from the OS, if
+    /// timestamps are enabled with
+    /// [`Endpoint::set_timestamps`][`crate::Endpoint::set_timestamps`].
+    ///
+    /// This is recorded by nusb's event handling when the OS reports the
+    /// completion, not when the application retrieves it from
+    /// `next_complete`, so the difference between the two measures delay
+    /// in the application.
+    ///
+    /// ### Platform-specific notes
+    ///
+    /// None of the supported OS APIs report when the host controller
+    /// completed a bulk or interrupt transfer, so this is the closest
+    /// available timestamp:
+    ///
+    /// * On Linux, it is recorded by the event thread immediately after
+    ///   reaping the URB from usbfs.
+    /// * On Windows, it is recorded by the event thread when the completion
+    ///   is dequeued from the I/O completion port.
+    /// * On macOS, it is recorded in the IOKit completion callback.
+    pub completed_at: Option<Instant>,
 }
 
 impl Completion {
@@ -156,4 +460,153 @@ impl Completion {
     pub fn into_result(self) -> Result<Buffer, TransferError> {
         self.status.map(|()| self.buffer)
     }
+
+    /// Like [`into_result`][Self::into_result], but on error returns an
+    /// [`EndpointError`] that includes the endpoint address and transfer
+    /// length.
+    pub fn into_endpoint_result(self) -> Result<Buffer, EndpointError> {
+        match self.status {
+            Ok(()) => Ok(self.buffer),
+            Err(error) => Err(EndpointError {
+                error,
+                endpoint: self.endpoint,
+                requested_len: match Direction::from_address(self.endpoint) {
+                    Direction::In => self.buffer.requested_len(),
+                    Direction::Out => self.buffer.len(),
+                },
+                actual_len: self.actual_len,
+            }),
+        }
+    }
+}
+
+/// When an isochronous transfer is scheduled to start.
+///
+/// Used with
+/// [`Endpoint::submit_packets_scheduled`][crate::Endpoint::submit_packets_scheduled].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum IsoSchedule {
+    /// Start in the next available (micro)frame after any transfers already
+    /// pending on the endpoint (default).
+    ///
+    /// If the endpoint's queue ran empty, for example after an underrun, the
+    /// transfer starts as soon as possible rather than at the frame where the
+    /// previous transfer ended.
+    #[default]
+    Asap,
+
+    /// Start at the specified frame number.
+    ///
+    /// The frame number and its units (frames or microframes) are as defined
+    /// by the host controller driver. A transfer scheduled for a frame that
+    /// has already passed fails, or has its late packets skipped, depending
+    /// on the host controller. Use
+    /// [`Endpoint::current_frame`][crate::Endpoint::current_frame] or the
+    /// [`start_frame`][IsoCompletion::start_frame] of a completion to pick
+    /// a frame.
+    Frame(u32),
+}
+
+/// Result of an individual packet of an isochronous transfer, as part of an
+/// [`IsoCompletion`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IsoPacketResult {
+    /// Offset of the packet's data within the transfer buffer.
+    pub offset: usize,
+
+    /// Number of bytes requested (IN) or submitted (OUT) for this packet.
+    pub requested_len: usize,
+
+    /// Number of bytes transferred in this packet.
+    pub actual_len: usize,
+
+    /// Status of this packet.
+    pub status: Result<(), TransferError>,
+}
+
+/// A completed isochronous transfer returned from
+/// [`Endpoint::next_complete`][`crate::Endpoint::next_complete`] on an
+/// [`Isochronous`] endpoint.
+///
+/// Each packet occupies `requested_len` bytes of the buffer starting at its
+/// `offset`. For IN transfers, a packet shorter than requested leaves a gap
+/// before the next packet, so use [`packet_data`][`Self::packet_data`] or
+/// [`iter_packets`][`Self::iter_packets`] rather than reading the buffer
+/// contiguously.
+/// Bytes not written by the device are zero.
+#[derive(Debug)]
+pub struct IsoCompletion {
+    /// The transfer buffer.
+    pub buffer: Buffer,
+
+    /// Per-packet lengths and status, in the order the packets were submitted.
+    pub packets: Vec<IsoPacketResult>,
+
+    /// Address of the endpoint the transfer was submitted on, including the
+    /// direction bit.
+    pub endpoint: u8,
+
+    /// Status of the transfer as a whole.
+    ///
+    /// Errors on individual packets are reported in [`packets`][`Self::packets`],
+    /// and do not necessarily cause this status to be an error.
+    pub status: Result<(), TransferError>,
+
+    /// (Micro)frame number at which the transfer started, as reported by the
+    /// host controller driver.
+    ///
+    /// For a transfer submitted with [`IsoSchedule::Asap`], this is the frame
+    /// chosen by the host controller. The units are as for
+    /// [`IsoSchedule::Frame`].
+    pub start_frame: u32,
+
+    /// Time at which nusb handled the transfer's completion from the OS.
+    ///
+    /// This is recorded by nusb's event thread immediately after reaping the
+    /// transfer from usbfs, which is closer to the time the last packet was
+    /// received than the time the application retrieves the completion.
+    /// usbfs does not report a host controller timestamp.
+    pub completed_at: Option<Instant>,
+}
+
+impl IsoCompletion {
+    /// Get the data transferred in the packet at `index`.
+    ///
+    /// ### Panics
+    /// * If `index` is out of range.
+    pub fn packet_data(&self, index: usize) -> &[u8] {
+        let p = &self.packets[index];
+        &self.buffer[p.offset..p.offset + p.actual_len]
+    }
+
+    /// Iterate over the packets, yielding the packet result and its data.
+    pub fn iter_packets(&self) -> impl Iterator<Item = (&IsoPacketResult, &[u8])> {
+        self.packets
+            .iter()
+            .map(|p| (p, &self.buffer[p.offset..p.offset + p.actual_len]))
+    }
+
+    /// Total number of bytes transferred across all packets.
+    pub fn actual_len(&self) -> usize {
+        self.packets.iter().map(|p| p.actual_len).sum()
+    }
+}
+
+#[test]
+fn io_error_kind() {
+    let kind = |e: TransferError| io::Error::from(e).kind();
+    assert_eq!(kind(TransferError::TimedOut), io::ErrorKind::TimedOut);
+    assert_eq!(kind(TransferError::Stall), io::ErrorKind::BrokenPipe);
+    assert_eq!(
+        kind(TransferError::Disconnected),
+        io::ErrorKind::NotConnected
+    );
+    assert_eq!(
+        kind(TransferError::ShortPacket),
+        io::ErrorKind::UnexpectedEof
+    );
+
+    let err = io::Error::from(TransferError::Stall);
+    let inner = err.get_ref().unwrap().downcast_ref::<TransferError>();
+    assert_eq!(inner, Some(&TransferError::Stall));
 }
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use super::{Buffer, BufferAllocator};
+
+/// A pool of reusable [`Buffer`]s of a fixed size.
+///
+/// Streaming data typically submits a buffer, receives it back in a
+/// [`Completion`][super::Completion], and then submits another buffer of the
+/// same size. Returning completed buffers to a pool with [`put`][Self::put]
+/// and taking them again with [`get`][Self::get] avoids allocating a new
+/// buffer for each transfer.
+///
+/// Use [`Endpoint::buffer_pool`][crate::Endpoint::buffer_pool] to create a
+/// pool of zero-copy buffers for an endpoint.
+pub struct BufferPool {
+    buffer_size: usize,
+    free: Vec<Buffer>,
+    allocator: Option<Arc<dyn BufferAllocator>>,
+}
+
+impl BufferPool {
+    /// Create an empty pool of buffers of `buffer_size` bytes.
+    pub fn new(buffer_size: usize) -> Self {
+        BufferPool {
+            buffer_size,
+            free: Vec::new(),
+            allocator: None,
+        }
+    }
+
+    /// Create an empty pool of buffers of `buffer_size` bytes, allocating
+    /// new buffers from a custom allocator with [`Buffer::new_in`].
+    pub fn new_in(buffer_size: usize, allocator: Arc<dyn BufferAllocator>) -> Self {
+        BufferPool {
+            buffer_size,
+            free: Vec::new(),
+            allocator: Some(allocator),
+        }
+    }
+
+    /// Get the size of the buffers in the pool.
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    /// Get the number of buffers available in the pool.
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    /// Returns `true` if there are no buffers available in the pool.
+    pub fn is_empty(&self) -> bool {
+        self.free.is_empty()
+    }
+
+    /// Take a buffer from the pool, or `None` if the pool is empty.
+    ///
+    /// The buffer is empty, with a `requested_len` of the pool's buffer size.
+    pub fn take(&mut self) -> Option<Buffer> {
+        self.free.pop()
+    }
+
+    /// Take a buffer from the pool, allocating a new one with
+    /// [`Buffer::new`], or from the pool's allocator if it was created with
+    /// [`new_in`][Self::new_in], if the pool is empty.
+    pub fn get(&mut self) -> Buffer {
+        self.take().unwrap_or_else(|| match &self.allocator {
+            Some(allocator) => Buffer::new_in(self.buffer_size, allocator.clone()),
+            None => Buffer::new(self.buffer_size),
+        })
+    }
+
+    /// Return a buffer to the pool.
+    ///
+    /// The buffer is cleared and its `requested_len` is reset to the pool's
+    /// buffer size. A buffer with a capacity smaller than the pool's buffer
+    /// size is dropped instead.
+    pub fn put(&mut self, mut buf: Buffer) {
+        if buf.capacity() < self.buffer_size {
+            return;
+        }
+        buf.clear();
+        buf.set_requested_len(self.buffer_size);
+        self.free.push(buf);
+    }
+}
+
+impl Extend<Buffer> for BufferPool {
+    fn extend<T: IntoIterator<Item = Buffer>>(&mut self, iter: T) {
+        for buf in iter {
+            self.put(buf);
+        }
+    }
+}
+
+impl std::fmt::Debug for BufferPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BufferPool")
+            .field("buffer_size", &self.buffer_size)
+            .field("free", &self.free.len())
+            .finish()
+    }
+}
+
+#[test]
+fn recycles_buffers() {
+    let mut pool = BufferPool::new(64);
+    assert!(pool.take().is_none());
+
+    let mut buf = pool.get();
+    buf.extend_from_slice(&[1, 2, 3]);
+    let ptr = buf.ptr;
+    pool.put(buf);
+    assert_eq!(pool.len(), 1);
+
+    let buf = pool.get();
+    assert_eq!(buf.ptr, ptr);
+    assert!(buf.is_empty());
+    assert_eq!(buf.requested_len(), 64);
+
+    pool.put(Buffer::new(16));
+    assert!(pool.is_empty());
+}
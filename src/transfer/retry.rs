@@ -0,0 +1,218 @@
+use std::{
+    future::Future,
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{Buffer, BulkOrInterrupt, Completion, EndpointDirection, TransferError};
+use crate::{timer::Sleep, Endpoint};
+
+/// Policy for retrying transfers that fail with transient errors.
+///
+/// Flaky cables and electrical noise cause occasional protocol errors that
+/// succeed when the request is repeated. A `RetryPolicy` repeats an operation
+/// up to [`max_attempts`][Self::new] times while it fails with an error
+/// accepted by its [retryable](Self::with_retryable) condition, waiting an
+/// exponentially increasing delay between attempts.
+///
+/// By default, 3 attempts are made, errors for which
+/// [`TransferError::is_transient`] returns `true` are retried, and the delay
+/// starts at 10 ms and doubles up to 1 s.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use nusb::transfer::{ControlIn, ControlType, Recipient, RetryPolicy};
+/// # use nusb::MaybeFuture;
+/// # fn example(interface: nusb::Interface) -> Result<(), nusb::transfer::TransferError> {
+/// let policy = RetryPolicy::new(5);
+/// let data = policy.retry_blocking(|| {
+///     interface.control_in(ControlIn {
+///         control_type: ControlType::Vendor,
+///         recipient: Recipient::Interface,
+///         request: 0x01,
+///         value: 0,
+///         index: 0,
+///         length: 64,
+///     }, Duration::from_millis(100)).wait()
+/// })?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    retryable: fn(&TransferError) -> bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+            retryable: TransferError::is_transient,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a policy making up to `max_attempts` attempts, including the
+    /// first.
+    ///
+    /// ### Panics
+    /// * If `max_attempts` is zero.
+    pub fn new(max_attempts: u32) -> Self {
+        assert!(max_attempts > 0, "max_attempts must be greater than zero");
+        RetryPolicy {
+            max_attempts,
+            ..Default::default()
+        }
+    }
+
+    /// Set the delay before the first retry, which doubles on each
+    /// subsequent retry up to `max`.
+    ///
+    /// Use `Duration::ZERO` to retry immediately.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Set the condition for which errors are retried.
+    pub fn with_retryable(mut self, retryable: fn(&TransferError) -> bool) -> Self {
+        self.retryable = retryable;
+        self
+    }
+
+    /// Get the maximum number of attempts, including the first.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Get the delay before retrying after `attempt` failed attempts.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Returns `true` if an operation that failed with `error` after
+    /// `attempt` attempts should be retried.
+    pub fn should_retry(&self, error: &TransferError, attempt: u32) -> bool {
+        attempt < self.max_attempts && (self.retryable)(error)
+    }
+
+    /// Run a blocking operation, retrying it according to the policy.
+    ///
+    /// Returns the result of the first successful attempt, or the error of
+    /// the last attempt.
+    pub fn retry_blocking<T>(
+        &self,
+        mut f: impl FnMut() -> Result<T, TransferError>,
+    ) -> Result<T, TransferError> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match f() {
+                Err(e) if self.should_retry(&e, attempt) => thread::sleep(self.delay(attempt)),
+                res => return res,
+            }
+        }
+    }
+
+    /// Run an async operation, retrying it according to the policy.
+    ///
+    /// `f` is called to start each attempt. The delay between attempts does
+    /// not depend on an async runtime.
+    pub async fn retry<T, F: Future<Output = Result<T, TransferError>>>(
+        &self,
+        mut f: impl FnMut() -> F,
+    ) -> Result<T, TransferError> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match f().await {
+                Err(e) if self.should_retry(&e, attempt) => {
+                    Sleep::new(Instant::now() + self.delay(attempt)).await
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Perform a blocking transfer on a Bulk or Interrupt endpoint,
+    /// resubmitting the buffer according to the policy.
+    ///
+    /// Each attempt is performed as with
+    /// [`Endpoint::transfer_blocking`], and the completion of the last
+    /// attempt is returned. For an OUT transfer, the same data is sent again
+    /// on each attempt. For an IN transfer, data received by a failed
+    /// attempt is discarded.
+    ///
+    /// ## Panics
+    ///  * if other transfers are already pending on the endpoint.
+    pub fn transfer_blocking<EpType: BulkOrInterrupt, Dir: EndpointDirection>(
+        &self,
+        endpoint: &mut Endpoint<EpType, Dir>,
+        buf: Buffer,
+        timeout: Duration,
+    ) -> Completion {
+        let mut attempt = 0;
+        let mut buf = buf;
+        loop {
+            attempt += 1;
+            let c = endpoint.transfer_blocking(buf, timeout);
+            match c.status {
+                Err(e) if self.should_retry(&e, attempt) => {
+                    thread::sleep(self.delay(attempt));
+                    buf = c.buffer;
+                }
+                _ => return c,
+            }
+        }
+    }
+}
+
+#[test]
+fn retry_transient() {
+    let policy = RetryPolicy::new(3).with_backoff(Duration::ZERO, Duration::ZERO);
+
+    let mut calls = 0;
+    let res = policy.retry_blocking(|| {
+        calls += 1;
+        if calls < 3 {
+            Err(TransferError::Fault(71))
+        } else {
+            Ok(calls)
+        }
+    });
+    assert_eq!(res, Ok(3));
+
+    let mut calls = 0;
+    let res: Result<(), _> = policy.retry_blocking(|| {
+        calls += 1;
+        Err(TransferError::Stall)
+    });
+    assert_eq!(res, Err(TransferError::Stall));
+    assert_eq!(calls, 1);
+
+    let res: Result<(), _> =
+        futures_lite::future::block_on(policy.retry(|| async { Err(TransferError::TimedOut) }));
+    assert_eq!(res, Err(TransferError::TimedOut));
+}
+
+#[test]
+fn backoff() {
+    let policy =
+        RetryPolicy::new(10).with_backoff(Duration::from_millis(10), Duration::from_millis(50));
+    assert_eq!(policy.delay(1), Duration::from_millis(10));
+    assert_eq!(policy.delay(2), Duration::from_millis(20));
+    assert_eq!(policy.delay(3), Duration::from_millis(40));
+    assert_eq!(policy.delay(4), Duration::from_millis(50));
+    assert_eq!(policy.delay(100), Duration::from_millis(50));
+}
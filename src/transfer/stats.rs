@@ -0,0 +1,145 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Snapshot of transfer statistics for an [`Endpoint`][crate::Endpoint].
+///
+/// Collection is enabled with
+/// [`Endpoint::enable_stats`][crate::Endpoint::enable_stats].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TransferStats {
+    /// Number of transfers submitted.
+    pub submitted: u64,
+
+    /// Number of transfers returned from `next_complete`.
+    pub completed: u64,
+
+    /// Number of completed transfers with an error status, including
+    /// cancelled transfers.
+    pub failed: u64,
+
+    /// Total number of bytes transferred by completed transfers.
+    pub bytes: u64,
+
+    /// Mean time from submission until a transfer was returned.
+    pub mean_latency: Duration,
+
+    /// Approximate 99th percentile time from submission until a transfer was
+    /// returned, rounded up to a power of two microseconds.
+    pub p99_latency: Duration,
+
+    /// Total time during which no transfers were pending between a
+    /// completion and the next submission.
+    pub idle_time: Duration,
+
+    /// Longest single period during which no transfers were pending between a
+    /// completion and the next submission.
+    pub max_idle_gap: Duration,
+}
+
+/// Number of histogram buckets, the last covering latencies of 2^39 µs and up.
+const BUCKETS: usize = 40;
+
+/// Collects [`TransferStats`] for an endpoint.
+pub(crate) struct StatsCollector {
+    stats: TransferStats,
+
+    /// Submission time of each pending transfer, in order.
+    submit_times: VecDeque<Instant>,
+
+    /// Time at which the last pending transfer was returned, if none are
+    /// pending.
+    idle_since: Option<Instant>,
+
+    total_latency: Duration,
+
+    /// Number of transfers completing in under 2^i µs, for each bucket `i`.
+    histogram: [u64; BUCKETS],
+}
+
+impl StatsCollector {
+    /// Start collecting with `pending` transfers already pending.
+    pub(crate) fn new(pending: usize) -> Self {
+        // Transfers submitted before collection started are timed from now.
+        let now = Instant::now();
+        StatsCollector {
+            stats: TransferStats::default(),
+            submit_times: (0..pending).map(|_| now).collect(),
+            idle_since: (pending == 0).then_some(now),
+            total_latency: Duration::ZERO,
+            histogram: [0; BUCKETS],
+        }
+    }
+
+    pub(crate) fn on_submit(&mut self) {
+        let now = Instant::now();
+        if let Some(since) = self.idle_since.take() {
+            let gap = now - since;
+            self.stats.idle_time += gap;
+            self.stats.max_idle_gap = self.stats.max_idle_gap.max(gap);
+        }
+        self.stats.submitted += 1;
+        self.submit_times.push_back(now);
+    }
+
+    pub(crate) fn on_complete(&mut self, actual_len: usize, ok: bool, pending: usize) {
+        let now = Instant::now();
+        self.stats.completed += 1;
+        self.stats.bytes += actual_len as u64;
+        if !ok {
+            self.stats.failed += 1;
+        }
+
+        if let Some(submitted) = self.submit_times.pop_front() {
+            let latency = now - submitted;
+            self.total_latency += latency;
+            // Round up so the bucket bound is never below the actual latency.
+            let micros = latency.as_nanos().div_ceil(1000).max(1);
+            let bucket = (u128::BITS - (micros - 1).leading_zeros()) as usize;
+            self.histogram[bucket.min(BUCKETS - 1)] += 1;
+        }
+
+        if pending == 0 {
+            self.idle_since = Some(now);
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> TransferStats {
+        let timed: u64 = self.histogram.iter().sum();
+        let mut stats = self.stats;
+        if timed > 0 {
+            stats.mean_latency =
+                Duration::from_nanos((self.total_latency.as_nanos() / timed as u128) as u64);
+
+            let threshold = (timed * 99).div_ceil(100);
+            let mut count = 0;
+            for (bucket, &n) in self.histogram.iter().enumerate() {
+                count += n;
+                if count >= threshold {
+                    stats.p99_latency = Duration::from_micros(1 << bucket);
+                    break;
+                }
+            }
+        }
+        stats
+    }
+}
+
+#[test]
+fn latency_histogram() {
+    let mut c = StatsCollector::new(0);
+    for _ in 0..10 {
+        c.on_submit();
+    }
+    for i in 0..10 {
+        c.on_complete(64, i != 0, 9 - i);
+    }
+    let stats = c.snapshot();
+    assert_eq!(stats.submitted, 10);
+    assert_eq!(stats.completed, 10);
+    assert_eq!(stats.failed, 1);
+    assert_eq!(stats.bytes, 640);
+    assert!(stats.p99_latency >= stats.mean_latency);
+}
@@ -0,0 +1,166 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    task::{Poll, Waker},
+    time::Duration,
+};
+
+use super::Buffer;
+use crate::timer::block_on_timeout;
+
+/// Handle for submitting transfers to an [`Endpoint`][crate::Endpoint] from
+/// other threads or tasks.
+///
+/// Obtain a `Submitter` with
+/// [`Endpoint::submitter`][crate::Endpoint::submitter]. It can be cloned and
+/// used concurrently from any number of threads, while the endpoint itself
+/// is owned by a single consumer that waits for completions.
+///
+/// Buffers are queued and submitted to the OS by the endpoint the next time
+/// the consumer polls or waits for a completion, so the consumer must be
+/// waiting for completions for submissions to make progress. A consumer
+/// awaiting [`next_complete`][crate::Endpoint::next_complete] is woken to
+/// submit them immediately.
+///
+/// ### Ordering
+///
+/// Transfers are submitted in the order that calls to `submit` returned,
+/// across all clones of the `Submitter`, and completions are returned from
+/// the endpoint in that order. Transfers submitted directly on the endpoint
+/// are ordered relative to these according to when the endpoint forwarded
+/// the queued buffers. Use [`submit_tagged`][Self::submit_tagged] to
+/// identify which producer a [`Completion`][super::Completion] belongs to.
+#[derive(Clone)]
+pub struct Submitter {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    queue: VecDeque<(Buffer, u64)>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+impl Submitter {
+    /// Queue a transfer for submission on the endpoint.
+    ///
+    /// Returns the buffer as an error if the endpoint has been dropped. See
+    /// [`Endpoint::submit`][crate::Endpoint::submit] for the requirements on
+    /// the buffer.
+    pub fn submit(&self, buf: Buffer) -> Result<(), Buffer> {
+        self.submit_tagged(buf, 0)
+    }
+
+    /// Queue a transfer for submission on the endpoint, attaching a tag that
+    /// is returned in its [`Completion`][super::Completion].
+    ///
+    /// Returns the buffer as an error if the endpoint has been dropped.
+    pub fn submit_tagged(&self, buf: Buffer, tag: u64) -> Result<(), Buffer> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.closed {
+            return Err(buf);
+        }
+        state.queue.push_back((buf, tag));
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the endpoint has been dropped, so that further
+    /// submissions will fail.
+    pub fn is_closed(&self) -> bool {
+        self.shared.state.lock().unwrap().closed
+    }
+}
+
+impl std::fmt::Debug for Submitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.shared.state.lock().unwrap();
+        f.debug_struct("Submitter")
+            .field("queued", &state.queue.len())
+            .field("closed", &state.closed)
+            .finish()
+    }
+}
+
+/// Endpoint side of the queue of buffers from [`Submitter`]s.
+///
+/// Closes the queue when dropped with the endpoint.
+pub(crate) struct SubmitReceiver {
+    shared: Arc<Shared>,
+}
+
+impl SubmitReceiver {
+    pub(crate) fn new() -> Self {
+        SubmitReceiver {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State::default()),
+            }),
+        }
+    }
+
+    pub(crate) fn submitter(&self) -> Submitter {
+        Submitter {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Take all queued buffers, first registering `waker` to be woken by the
+    /// next submission.
+    pub(crate) fn take(&self, waker: Option<&Waker>) -> VecDeque<(Buffer, u64)> {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(waker) = waker {
+            if !state.waker.as_ref().is_some_and(|w| w.will_wake(waker)) {
+                state.waker = Some(waker.clone());
+            }
+        }
+        std::mem::take(&mut state.queue)
+    }
+
+    /// Block until a buffer is queued or the timeout elapses, and take all
+    /// queued buffers.
+    pub(crate) fn wait(&self, timeout: Duration) -> VecDeque<(Buffer, u64)> {
+        block_on_timeout(timeout, |cx| {
+            let queued = self.take(Some(cx.waker()));
+            if queued.is_empty() {
+                Poll::Pending
+            } else {
+                Poll::Ready(queued)
+            }
+        })
+        .unwrap_or_default()
+    }
+}
+
+impl Drop for SubmitReceiver {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.closed = true;
+        state.queue.clear();
+    }
+}
+
+#[test]
+fn submit_and_close() {
+    let rx = SubmitReceiver::new();
+    let submitter = rx.submitter();
+    submitter.submit_tagged(Buffer::new(64), 1).unwrap();
+    submitter.clone().submit_tagged(Buffer::new(64), 2).unwrap();
+
+    let queued = rx.take(None);
+    assert_eq!(
+        queued.iter().map(|(_, tag)| *tag).collect::<Vec<_>>(),
+        [1, 2]
+    );
+    assert!(rx.wait(Duration::from_millis(1)).is_empty());
+
+    drop(rx);
+    assert!(submitter.is_closed());
+    assert!(submitter.submit(Buffer::new(64)).is_err());
+}